@@ -1,14 +1,20 @@
-use crate::client::{Client, GatewayIntentBits};
-use dotenv;
+use discord_rs::client::{Client, GatewayIntentBits};
 
 pub async fn main() {
     let token = &dotenv::var("DISCORD_TOKEN").unwrap();
 
-    let client = Client::new(token, &[
+    let mut client = Client::new(token, &[
         GatewayIntentBits::Guilds,
         GatewayIntentBits::GuildMessages,
         GatewayIntentBits::DirectMessages,
     ]);
 
-    client.login().expect("Failed to login");
-}
\ No newline at end of file
+    // Go offline cleanly on ctrl-c instead of leaving the session to time out
+    let shutdown = client.shutdown_handle();
+    tokio::spawn(async move {
+        tokio::signal::ctrl_c().await.expect("Failed to listen for ctrl-c");
+        shutdown.shutdown();
+    });
+
+    client.run().await.expect("Gateway connection failed");
+}
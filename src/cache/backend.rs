@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Serialize, Deserialize};
+
+use crate::models::{Channel, Emoji, Guild, GuildMember, Presence, Role, Snowflake, User, VoiceState};
+
+/// Where a [super::Cache] keeps its entities so they outlive the process
+///
+/// The cache always keeps every entity in memory for reads. A backend is
+/// given the entries stored by a previous run once, when the cache is created
+/// through [super::Cache::with_backend], then every entry an event changed.
+/// Changes are written from a thread of their own, in the order they were made
+pub trait CacheBackend: std::fmt::Debug + Send + Sync {
+    /// Every entry stored so far
+    fn load(&self) -> io::Result<Vec<CacheEntry>>;
+
+    /// Stores the current version of every entry changed by an event
+    fn write(&self, changes: &[CacheChange]) -> io::Result<()>;
+
+    /// Whether the backend keeps anything. The cache does not keep track of
+    /// its changes for backends which do not
+    fn persists(&self) -> bool {
+        true
+    }
+}
+
+/// The default backend, which keeps nothing once the process exits
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryBackend;
+
+impl CacheBackend for MemoryBackend {
+    fn load(&self) -> io::Result<Vec<CacheEntry>> {
+        Ok(Vec::new())
+    }
+
+    fn write(&self, _changes: &[CacheChange]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn persists(&self) -> bool {
+        false
+    }
+}
+
+/// Identifies a single cached entity
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CacheKey {
+    CurrentUser,
+    User { id: Snowflake },
+    Guild { id: Snowflake },
+    UnavailableGuild { id: Snowflake },
+    Channel { id: Snowflake },
+    Role { guild_id: Snowflake, id: Snowflake },
+    Emoji { guild_id: Snowflake, id: Snowflake },
+    Member { guild_id: Snowflake, user_id: Snowflake },
+    VoiceState { guild_id: Snowflake, user_id: Snowflake },
+    Presence { guild_id: Snowflake, user_id: Snowflake }
+}
+
+/// A cached entity, stored the way the cache keeps it: guilds without their
+/// roles, emojis, members and channels, and members without their user
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CacheEntry {
+    CurrentUser { user: User },
+    User { user: User },
+    Guild { guild: Box<Guild> },
+    UnavailableGuild { id: Snowflake },
+    Channel { channel: Box<Channel> },
+    Role { guild_id: Snowflake, role: Role },
+    Emoji { guild_id: Snowflake, emoji: Emoji },
+    Member { guild_id: Snowflake, user_id: Snowflake, member: GuildMember },
+    VoiceState { guild_id: Snowflake, voice_state: VoiceState },
+    Presence { guild_id: Snowflake, presence: Presence }
+}
+
+impl CacheEntry {
+    pub fn key(&self) -> CacheKey {
+        match self {
+            CacheEntry::CurrentUser { .. } => CacheKey::CurrentUser,
+            CacheEntry::User { user } => CacheKey::User { id: user.id.to_owned() },
+            CacheEntry::Guild { guild } => CacheKey::Guild { id: guild.id.to_owned() },
+            CacheEntry::UnavailableGuild { id } => CacheKey::UnavailableGuild { id: id.to_owned() },
+            CacheEntry::Channel { channel } => CacheKey::Channel { id: channel.id.to_owned() },
+            CacheEntry::Role { guild_id, role } => CacheKey::Role { guild_id: guild_id.to_owned(), id: role.id.to_owned() },
+            CacheEntry::Emoji { guild_id, emoji } => CacheKey::Emoji {
+                guild_id: guild_id.to_owned(),
+                id: emoji.id.clone().unwrap_or_default()
+            },
+            CacheEntry::Member { guild_id, user_id, .. } => CacheKey::Member {
+                guild_id: guild_id.to_owned(),
+                user_id: user_id.to_owned()
+            },
+            CacheEntry::VoiceState { guild_id, voice_state } => CacheKey::VoiceState {
+                guild_id: guild_id.to_owned(),
+                user_id: voice_state.user_id.to_owned()
+            },
+            CacheEntry::Presence { guild_id, presence } => CacheKey::Presence {
+                guild_id: guild_id.to_owned(),
+                user_id: presence.user.id.to_owned()
+            },
+        }
+    }
+}
+
+/// A change made to the cache by an event
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum CacheChange {
+    /// An entity was created or replaced
+    Insert { entry: Box<CacheEntry> },
+    Remove { key: CacheKey }
+}
+
+/// Keeps the cache in a local file, as a log of every change encoded as one
+/// JSON object per line, so it can be read offline with any JSON tool
+///
+/// The log is compacted to a single insert per entity whenever it is loaded,
+/// and again whenever it has grown to twice its compacted size, with at least
+/// [FileBackend::with_compaction_threshold] lines in between. A line left
+/// incomplete by a crash at the end of the log is ignored
+///
+/// # Example
+/// ```no_run
+/// use discord_rs::cache::{Cache, FileBackend};
+///
+/// let backend = FileBackend::open("cache.jsonl").expect("Failed to open the cache");
+/// let cache = Cache::with_backend(backend).expect("Failed to load the cache");
+///
+/// println!("{} guilds were cached by the last run", cache.guild_ids().len());
+/// ```
+#[derive(Debug)]
+pub struct FileBackend {
+    path: PathBuf,
+    log: Mutex<Log>,
+    /// How many lines may be appended before the log is compacted again
+    compaction_threshold: usize
+}
+
+/// The open log along with how much of it is redundant
+#[derive(Debug)]
+struct Log {
+    file: BufWriter<File>,
+    /// Lines appended since the log was last compacted
+    appended: usize,
+    /// Lines left by the last compaction, one per entity
+    compacted: usize
+}
+
+impl FileBackend {
+    /// Opens the log at `path`, creating it if it does not exist
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        Ok(Self {
+            log: Mutex::new(Log {
+                file: BufWriter::new(append(&path)?),
+                appended: 0,
+                compacted: 0
+            }),
+            path,
+            compaction_threshold: 10_000
+        })
+    }
+
+    /// Sets how many lines may be appended to the log before it is compacted
+    /// again, as long as it has also doubled in size. Defaults to 10000
+    pub fn with_compaction_threshold(&mut self, lines: usize) -> &mut Self {
+        self.compaction_threshold = lines;
+        self
+    }
+
+    /// The entries of the log, without compacting it
+    pub fn read(&self) -> io::Result<Vec<CacheEntry>> {
+        let lines: Vec<_> = BufReader::new(File::open(&self.path)?).lines().collect::<io::Result<_>>()?;
+        let mut entries = HashMap::new();
+
+        for (index, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let change = match serde_json::from_str(line) {
+                Ok(change) => change,
+                Err(_) if index + 1 == lines.len() => break,
+                Err(error) => return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid cache change on line {}: {error}", index + 1)
+                )),
+            };
+
+            match change {
+                CacheChange::Insert { entry } => { entries.insert(entry.key(), *entry); },
+                CacheChange::Remove { key } => { entries.remove(&key); },
+            }
+        }
+
+        Ok(entries.into_values().collect())
+    }
+}
+
+impl CacheBackend for FileBackend {
+    fn load(&self) -> io::Result<Vec<CacheEntry>> {
+        let mut log = self.log.lock().unwrap_or_else(|error| error.into_inner());
+        self.compact(&mut log)
+    }
+
+    fn write(&self, changes: &[CacheChange]) -> io::Result<()> {
+        let mut log = self.log.lock().unwrap_or_else(|error| error.into_inner());
+
+        for change in changes {
+            serde_json::to_writer(&mut log.file, change)?;
+            log.file.write_all(b"\n")?;
+        }
+
+        log.appended += changes.len();
+        log.file.flush()?;
+
+        if log.appended >= self.compaction_threshold.max(log.compacted) {
+            self.compact(&mut log)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FileBackend {
+    /// Rewrites the log with a single insert per entity, returning the entities.
+    /// Holding the lock makes sure nothing is written while the log is replaced
+    fn compact(&self, log: &mut Log) -> io::Result<Vec<CacheEntry>> {
+        log.file.flush()?;
+
+        let entries = self.read()?;
+        let compacted = self.path.with_extension("compact");
+        let mut writer = BufWriter::new(File::create(&compacted)?);
+
+        for entry in &entries {
+            serde_json::to_writer(&mut writer, &CacheChange::Insert { entry: Box::new(entry.clone()) })?;
+            writer.write_all(b"\n")?;
+        }
+
+        writer.flush()?;
+        fs::rename(&compacted, &self.path)?;
+
+        log.file = BufWriter::new(append(&self.path)?);
+        log.appended = 0;
+        log.compacted = entries.len();
+
+        Ok(entries)
+    }
+}
+
+fn append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+
+use bitflags::bitflags;
+
+use crate::client::ReceiveEvent;
+use super::messages::MessageCacheConfig;
+
+bitflags! {
+    /// The kinds of entities a [super::Cache] keeps
+    ///
+    /// Members are returned without their user unless users are cached too
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub struct CacheResources: u32 {
+        const GUILDS = 1 << 0;
+        const CHANNELS = 1 << 1;
+        const ROLES = 1 << 2;
+        const EMOJIS = 1 << 3;
+        const MEMBERS = 1 << 4;
+        const USERS = 1 << 5;
+        const VOICE_STATES = 1 << 6;
+        const PRESENCES = 1 << 7;
+    }
+}
+
+/// What a [super::Cache] keeps and which events update it, see [super::Cache::with_config]
+///
+/// # Example
+/// ```
+/// use discord_rs::cache::{Cache, CacheConfig, CacheResources};
+/// use discord_rs::client::ReceiveEvent;
+///
+/// let cache = Cache::with_config(CacheConfig {
+///     resources: CacheResources::all() - CacheResources::PRESENCES - CacheResources::EMOJIS,
+///     events: Some([ReceiveEvent::Ready, ReceiveEvent::GuildCreate, ReceiveEvent::GuildDelete].into()),
+///     active_members_only: true,
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    pub resources: CacheResources,
+    /// The events which update the cache, or every event if `None`
+    pub events: Option<HashSet<ReceiveEvent>>,
+    /// Only cache the members who sent a message or joined a voice channel
+    /// since the bot connected, along with the bot itself. Members who are
+    /// already cached are still kept up to date
+    pub active_members_only: bool,
+    /// Also keep the messages sent in every channel, see [super::Cache::with_messages]
+    pub messages: Option<MessageCacheConfig>
+}
+
+impl Default for CacheConfig {
+    /// Caches every resource except messages, updated by every event
+    fn default() -> Self {
+        Self {
+            resources: CacheResources::all(),
+            events: None,
+            active_members_only: false,
+            messages: None
+        }
+    }
+}
+
+impl CacheConfig {
+    pub fn caches(&self, resources: CacheResources) -> bool {
+        self.resources.contains(resources)
+    }
+
+    /// Whether an event updates the cache
+    pub fn updates(&self, event: ReceiveEvent) -> bool {
+        self.events.as_ref().map_or(true, |events| events.contains(&event))
+    }
+}
@@ -0,0 +1,54 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+/// A field whose value changed between two versions of an entity
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    /// The name of the field as sent by Discord, such as `nick`
+    pub field: String,
+    pub before: Value,
+    pub after: Value
+}
+
+/// Compares the fields of two versions of an entity, returning every field
+/// whose value differs. Nested objects are compared as a whole
+///
+/// # Example
+/// ```
+/// use discord_rs::cache::diff;
+/// use discord_rs::models::Role;
+/// use serde_json::json;
+///
+/// let before: Role = serde_json::from_value(json!({
+///     "id": "1", "name": "Member", "color": 0, "hoist": false,
+///     "position": 1, "permissions": "0", "managed": false, "mentionable": false
+/// })).unwrap();
+/// let after = Role { name: "Regular".to_string(), hoist: true, ..before.clone() };
+///
+/// let changes = diff(&before, &after);
+/// assert_eq!(changes.len(), 2);
+/// assert_eq!(changes[0].field, "hoist");
+/// assert_eq!(changes[1].after, json!("Regular"));
+/// ```
+pub fn diff<T: Serialize>(before: &T, after: &T) -> Vec<FieldChange> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(mut after))) = (serde_json::to_value(before), serde_json::to_value(after)) else {
+        return Vec::new();
+    };
+
+    let mut changes: Vec<_> = before
+        .into_iter()
+        .filter_map(|(field, before)| {
+            let after = after.remove(&field).unwrap_or(Value::Null);
+            (before != after).then_some(FieldChange { field, before, after })
+        })
+        .collect();
+
+    // Fields only present in the new version
+    changes.extend(after
+        .into_iter()
+        .filter(|(_, after)| !after.is_null())
+        .map(|(field, after)| FieldChange { field, before: Value::Null, after }));
+
+    changes.sort_by(|a, b| a.field.cmp(&b.field));
+    changes
+}
@@ -0,0 +1,202 @@
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::{Message, Snowflake};
+use super::stats::{encoded_size, ResourceUsage};
+
+/// How many messages the [super::Cache] keeps, see [super::Cache::with_messages]
+///
+/// Once a limit is reached, the least recently created or updated message is
+/// dropped first, from the same channel for `per_channel` and from any
+/// channel for the global limits
+///
+/// # Example
+/// ```
+/// use discord_rs::cache::{Cache, MessageCacheConfig};
+///
+/// let cache = Cache::with_messages(MessageCacheConfig {
+///     per_channel: 50,
+///     max_messages: Some(10_000),
+///     max_bytes: Some(32 * 1024 * 1024)
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageCacheConfig {
+    /// How many messages are kept for every channel
+    pub per_channel: usize,
+    /// How many messages are kept across every channel
+    pub max_messages: Option<usize>,
+    /// Roughly how much memory the messages may take across every channel,
+    /// measured as the size of the messages encoded as JSON
+    pub max_bytes: Option<usize>
+}
+
+impl Default for MessageCacheConfig {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl MessageCacheConfig {
+    /// Keeps up to `per_channel` messages for every channel, without any global limit
+    pub fn new(per_channel: usize) -> Self {
+        Self {
+            per_channel,
+            max_messages: None,
+            max_bytes: None
+        }
+    }
+}
+
+/// The messages of every channel, along with the order they were last
+/// written in so the oldest can be dropped
+#[derive(Debug, Default)]
+pub(crate) struct MessageStore {
+    config: MessageCacheConfig,
+    channels: HashMap<Snowflake, ChannelMessages>,
+    /// Every message by when it was last written
+    order: BTreeMap<u64, (Snowflake, Snowflake)>,
+    tick: u64,
+    count: usize,
+    bytes: usize
+}
+
+#[derive(Debug, Default)]
+struct ChannelMessages {
+    messages: HashMap<Snowflake, StoredMessage>,
+    /// The messages of the channel by when they were last written
+    order: BTreeMap<u64, Snowflake>
+}
+
+#[derive(Debug)]
+struct StoredMessage {
+    message: Message,
+    tick: u64,
+    size: usize
+}
+
+impl MessageStore {
+    pub fn new(config: MessageCacheConfig) -> Self {
+        Self { config, ..Default::default() }
+    }
+
+    pub fn get(&self, channel_id: &str, message_id: &str) -> Option<&Message> {
+        Some(&self.channels.get(channel_id)?.messages.get(message_id)?.message)
+    }
+
+    /// The messages of a channel, from the least to the most recently written
+    pub fn channel(&self, channel_id: &str) -> Vec<Message> {
+        let Some(channel) = self.channels.get(channel_id) else {
+            return Vec::new();
+        };
+
+        channel.order
+            .values()
+            .map(|message_id| channel.messages[message_id].message.clone())
+            .collect()
+    }
+
+    /// Stores a message, returning the version it replaces if any
+    pub fn insert(&mut self, message: Message) -> Option<Message> {
+        if self.config.per_channel == 0 {
+            return None;
+        }
+
+        let channel_id = message.channel_id.to_owned();
+        let message_id = message.id.to_owned();
+        let previous = self.remove(&channel_id, &message_id);
+
+        self.tick += 1;
+        let size = encoded_size(&message);
+        let channel = self.channels.entry(channel_id.to_owned()).or_default();
+
+        channel.order.insert(self.tick, message_id.to_owned());
+        channel.messages.insert(message_id.to_owned(), StoredMessage { message, tick: self.tick, size });
+        self.order.insert(self.tick, (channel_id.to_owned(), message_id));
+        self.count += 1;
+        self.bytes += size;
+
+        // Make room in the channel first, then across every channel
+        while self.channels[&channel_id].messages.len() > self.config.per_channel {
+            let oldest = self.channels[&channel_id].order.values().next().cloned();
+            if let Some(oldest) = oldest {
+                self.remove(&channel_id, &oldest);
+            }
+        }
+
+        while self.over_limit() {
+            let Some((_, (channel_id, message_id))) = self.order.iter().next() else {
+                break;
+            };
+
+            let (channel_id, message_id) = (channel_id.to_owned(), message_id.to_owned());
+            self.remove(&channel_id, &message_id);
+        }
+
+        previous
+    }
+
+    /// Applies a MESSAGE_UPDATE, which only carries the fields that changed.
+    /// The fields it lacks are kept from the cached version. Without one, the
+    /// update is only stored if it carries a whole message
+    pub fn update(&mut self, update: Message) -> Option<Message> {
+        let message = match self.get(&update.channel_id, &update.id) {
+            Some(cached) => merge(cached, update),
+            None if update.author.is_some() && update.content.is_some() => update,
+            None => return None,
+        };
+
+        self.insert(message)
+    }
+
+    pub fn remove(&mut self, channel_id: &str, message_id: &str) -> Option<Message> {
+        let channel = self.channels.get_mut(channel_id)?;
+        let stored = channel.messages.remove(message_id)?;
+
+        channel.order.remove(&stored.tick);
+        if channel.messages.is_empty() {
+            self.channels.remove(channel_id);
+        }
+
+        self.order.remove(&stored.tick);
+        self.count -= 1;
+        self.bytes -= stored.size;
+
+        Some(stored.message)
+    }
+
+    pub fn usage(&self) -> ResourceUsage {
+        ResourceUsage {
+            count: self.count,
+            bytes: self.bytes
+        }
+    }
+
+    /// Drops every message of a deleted channel
+    pub fn remove_channel(&mut self, channel_id: &str) {
+        let Some(channel) = self.channels.remove(channel_id) else {
+            return;
+        };
+
+        for stored in channel.messages.into_values() {
+            self.order.remove(&stored.tick);
+            self.count -= 1;
+            self.bytes -= stored.size;
+        }
+    }
+
+    fn over_limit(&self) -> bool {
+        self.config.max_messages.is_some_and(|max| self.count > max)
+            || self.config.max_bytes.is_some_and(|max| self.bytes > max)
+    }
+}
+
+/// Overwrites the fields of a cached message with every field present in an update
+fn merge(cached: &Message, update: Message) -> Message {
+    let (Ok(Value::Object(mut merged)), Ok(Value::Object(fields))) = (serde_json::to_value(cached), serde_json::to_value(&update)) else {
+        return update;
+    };
+
+    merged.extend(fields.into_iter().filter(|(_, value)| !value.is_null()));
+    serde_json::from_value(Value::Object(merged)).unwrap_or(update)
+}
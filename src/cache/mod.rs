@@ -0,0 +1,952 @@
+//! A typed cache of the entities sent by the gateway
+//!
+//! # Example
+//! ```no_run
+//! use discord_rs::cache::Cache;
+//! use discord_rs::client::{async_trait, ClientBuilder, EventHandler, GatewayIntentBits};
+//! use discord_rs::models::Message;
+//!
+//! struct Handler {
+//!     cache: Cache
+//! }
+//!
+//! #[async_trait]
+//! impl EventHandler for Handler {
+//!     async fn message_create(&self, message: Message) {
+//!         let (Some(guild_id), Some(author)) = (&message.guild_id, &message.author) else {
+//!             return;
+//!         };
+//!
+//!         if let Some(member) = self.cache.member(guild_id, &author.id) {
+//!             println!("{:?} has {} roles", member.nick, member.roles.len());
+//!         }
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let cache = Cache::new();
+//!     let mut client = ClientBuilder::new("YOUR_TOKEN", &[
+//!         GatewayIntentBits::Guilds,
+//!         GatewayIntentBits::GuildMembers,
+//!         GatewayIntentBits::GuildMessages
+//!     ])
+//!         .with_cache(cache.clone())
+//!         .with_event_handler(Handler { cache })
+//!         .build();
+//!
+//!     client.run().await.expect("Gateway connection failed");
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use serde::Serialize;
+
+use crate::client::Event;
+use crate::models::{Channel, Emoji, Guild, GuildMember, Message, Presence, Role, Snowflake, Status, User, VoiceState};
+
+mod backend;
+pub use backend::{CacheBackend, CacheChange, CacheEntry, CacheKey, FileBackend, MemoryBackend};
+
+mod config;
+pub use config::{CacheConfig, CacheResources};
+
+mod diff;
+pub use diff::{diff, FieldChange};
+
+mod messages;
+pub use messages::MessageCacheConfig;
+use messages::MessageStore;
+
+mod stats;
+pub use stats::{CacheStats, ResourceUsage};
+use stats::usage;
+
+mod writer;
+use writer::CacheWriter;
+
+pub mod types;
+pub use types::Cache;
+use types::CacheState;
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for Cache {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+            writer: Arc::clone(&self.writer)
+        }
+    }
+}
+
+impl std::fmt::Debug for Cache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.read();
+
+        f.debug_struct("Cache")
+            .field("guilds", &state.guilds.len())
+            .field("channels", &state.channels.len())
+            .field("users", &state.users.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Self::with_config(CacheConfig::default())
+    }
+
+    /// Creates a cache which also keeps the messages sent in every channel, so
+    /// [crate::client::events::MessageUpdate] and [crate::client::events::MessageDelete]
+    /// carry the message as it was before being edited or deleted
+    pub fn with_messages(config: MessageCacheConfig) -> Self {
+        Self::with_config(CacheConfig {
+            messages: Some(config),
+            ..Default::default()
+        })
+    }
+
+    /// Creates a cache which only keeps some resources or is only updated by
+    /// some events, see [CacheConfig]
+    pub fn with_config(config: CacheConfig) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(CacheState::new(config))),
+            writer: Arc::new(CacheWriter::new(Arc::new(MemoryBackend)))
+        }
+    }
+
+    /// Creates a cache kept by a [CacheBackend], starting with the entities
+    /// it stored during previous runs
+    ///
+    /// Entities left from a previous run are replaced or removed as the
+    /// gateway sends READY and GUILD_CREATE. Messages are never stored
+    pub fn with_backend(backend: impl CacheBackend + 'static) -> io::Result<Self> {
+        Self::open(CacheConfig::default(), backend)
+    }
+
+    /// Creates a cache with a [CacheConfig], kept by a [CacheBackend]. Stored
+    /// entities of the resources which are not cached are dropped
+    pub fn open(config: CacheConfig, backend: impl CacheBackend + 'static) -> io::Result<Self> {
+        let mut state = CacheState::new(config);
+        for entry in backend.load()? {
+            state.load(entry);
+        }
+
+        state.changed = backend.persists().then(HashSet::new);
+
+        Ok(Self {
+            state: Arc::new(RwLock::new(state)),
+            writer: Arc::new(CacheWriter::new(Arc::new(backend)))
+        })
+    }
+
+    /// Waits until every change made so far has been written by the
+    /// [CacheBackend]. Changes are written in the background as events arrive
+    ///
+    /// Whatever is left is written once the last clone of the cache is dropped,
+    /// and that drop blocks the thread until the backend is done. Awaiting this
+    /// before dropping the cache inside async code leaves nothing to wait for
+    pub async fn flush(&self) {
+        self.writer.flush().await;
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, CacheState> {
+        // A handler panicking while reading leaves the cache intact
+        self.state.read().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, CacheState> {
+        self.state.write().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// The user of the bot, known once READY is received
+    pub fn current_user(&self) -> Option<User> {
+        self.read().current_user.clone()
+    }
+
+    /// A guild along with its roles and emojis. Use [Cache::guild_channels],
+    /// [Cache::members] and [Cache::voice_states] for the rest
+    pub fn guild(&self, guild_id: &str) -> Option<Guild> {
+        self.read().guild(guild_id)
+    }
+
+    /// The ids of every available guild
+    pub fn guild_ids(&self) -> Vec<Snowflake> {
+        self.read().guilds.keys().cloned().collect()
+    }
+
+    /// Whether a guild the bot is in is unavailable, either because it was
+    /// not received yet or because of an outage
+    pub fn is_unavailable(&self, guild_id: &str) -> bool {
+        self.read().unavailable_guilds.contains(guild_id)
+    }
+
+    /// A guild channel, thread or direct message channel
+    pub fn channel(&self, channel_id: &str) -> Option<Channel> {
+        self.read().channels.get(channel_id).cloned()
+    }
+
+    /// Every channel and thread of a guild
+    pub fn guild_channels(&self, guild_id: &str) -> Vec<Channel> {
+        let state = self.read();
+
+        state.guild_channels
+            .get(guild_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|channel_id| state.channels.get(channel_id).cloned())
+            .collect()
+    }
+
+    pub fn role(&self, guild_id: &str, role_id: &str) -> Option<Role> {
+        self.read().roles.get(guild_id)?.get(role_id).cloned()
+    }
+
+    pub fn roles(&self, guild_id: &str) -> Vec<Role> {
+        values(self.read().roles.get(guild_id))
+    }
+
+    pub fn emoji(&self, guild_id: &str, emoji_id: &str) -> Option<Emoji> {
+        self.read().emojis.get(guild_id)?.get(emoji_id).cloned()
+    }
+
+    pub fn emojis(&self, guild_id: &str) -> Vec<Emoji> {
+        values(self.read().emojis.get(guild_id))
+    }
+
+    /// A member of a guild, along with its user
+    pub fn member(&self, guild_id: &str, user_id: &str) -> Option<GuildMember> {
+        self.read().member(guild_id, user_id)
+    }
+
+    /// Every cached member of a guild. Unless the bot requested every member,
+    /// only part of the members of large guilds are known
+    pub fn members(&self, guild_id: &str) -> Vec<GuildMember> {
+        let state = self.read();
+
+        state.members
+            .get(guild_id)
+            .into_iter()
+            .flatten()
+            .map(|(user_id, member)| with_user(&state, user_id, member))
+            .collect()
+    }
+
+    pub fn user(&self, user_id: &str) -> Option<User> {
+        self.read().users.get(user_id).cloned()
+    }
+
+    /// The voice state of a user connected to a voice channel of a guild
+    pub fn voice_state(&self, guild_id: &str, user_id: &str) -> Option<VoiceState> {
+        self.read().voice_states.get(guild_id)?.get(user_id).cloned()
+    }
+
+    /// The voice states of every user connected to a voice channel of a guild
+    pub fn voice_states(&self, guild_id: &str) -> Vec<VoiceState> {
+        values(self.read().voice_states.get(guild_id))
+    }
+
+    /// The presence of a member of a guild, unless they are offline
+    pub fn presence(&self, guild_id: &str, user_id: &str) -> Option<Presence> {
+        self.read().presences.get(guild_id)?.get(user_id).cloned()
+    }
+
+    /// The presences of every member of a guild who is not offline
+    pub fn presences(&self, guild_id: &str) -> Vec<Presence> {
+        values(self.read().presences.get(guild_id))
+    }
+
+    /// A message, if the message cache is enabled and still has it
+    pub fn message(&self, channel_id: &str, message_id: &str) -> Option<Message> {
+        self.read().messages.as_ref()?.get(channel_id, message_id).cloned()
+    }
+
+    /// The cached messages of a channel, from the least to the most recently
+    /// created or updated
+    pub fn messages(&self, channel_id: &str) -> Vec<Message> {
+        self.read().messages.as_ref().map(|messages| messages.channel(channel_id)).unwrap_or_default()
+    }
+
+    /// How many entities of every kind are cached and roughly how much memory
+    /// they take. Every entity is measured, so this is slow for large caches
+    pub fn stats(&self) -> CacheStats {
+        let state = self.read();
+        CacheStats {
+            guilds: usage(state.guilds.values()),
+            channels: usage(state.channels.values()),
+            roles: usage(state.roles.values().flat_map(HashMap::values)),
+            emojis: usage(state.emojis.values().flat_map(HashMap::values)),
+            members: usage(state.members.values().flat_map(HashMap::values)),
+            users: usage(state.users.values()),
+            voice_states: usage(state.voice_states.values().flat_map(HashMap::values)),
+            presences: usage(state.presences.values().flat_map(HashMap::values)),
+            messages: state.messages.as_ref().map(MessageStore::usage).unwrap_or_default()
+        }
+    }
+
+    /// Updates the cache from a dispatch event. Called by the gateway before
+    /// the event reaches any handler
+    ///
+    /// Events about messages are given the cached version of their message,
+    /// and update events the cached version of what they update along with
+    /// the fields which changed
+    pub(crate) fn update(&self, event: &mut Event) {
+        // The lock is released before the changes are handed to the backend
+        let changes = self.apply(event);
+        if !changes.is_empty() {
+            self.writer.write(changes);
+        }
+    }
+
+    /// Applies an event to the cached entities, returning what it changed
+    fn apply(&self, event: &mut Event) -> Vec<CacheChange> {
+        let mut state = self.write();
+        if !state.config.updates(event.kind()) {
+            return Vec::new();
+        }
+
+        match event {
+            Event::Ready(ready) => {
+                state.insert_user(&ready.user);
+                state.set_current_user(ready.user.clone());
+
+                // Guilds the bot was removed from while it was offline
+                let received: HashSet<_> = ready.guilds.iter().map(|guild| guild.id.as_str()).collect();
+                let removed: Vec<_> = state.guilds
+                    .keys()
+                    .chain(&state.unavailable_guilds)
+                    .filter(|guild_id| !received.contains(guild_id.as_str()) && on_shard(guild_id, ready.shard))
+                    .cloned()
+                    .collect();
+
+                for guild_id in removed {
+                    state.remove_guild(&guild_id);
+                    state.set_unavailable(&guild_id, false);
+                }
+
+                for guild in &ready.guilds {
+                    state.set_unavailable(&guild.id, true);
+                }
+            },
+            Event::UserUpdate(event) => {
+                state.insert_user(&event.user);
+                event.cached = state.set_current_user(event.user.clone());
+                event.changes = changes(event.cached.as_ref(), state.current_user.as_ref());
+            },
+            Event::GuildCreate(guild) => state.insert_guild(guild),
+            Event::GuildUpdate(event) => {
+                event.cached = state.guild(&event.guild.id);
+                state.update_guild(&event.guild);
+                event.changes = changes(event.cached.as_ref(), state.guild(&event.guild.id).as_ref());
+            },
+            Event::GuildDelete(guild) => {
+                state.remove_guild(&guild.id);
+
+                // Without `unavailable` the bot was removed from the guild
+                if guild.unavailable == Some(true) {
+                    state.set_unavailable(&guild.id, true);
+                }
+            },
+            Event::ChannelUpdate(event) => {
+                event.cached = state.channels.get(&event.channel.id).cloned();
+                state.insert_channel(&event.channel);
+                event.changes = changes(event.cached.as_ref(), Some(&event.channel));
+            },
+            Event::ChannelCreate(channel)
+            | Event::ThreadCreate(channel)
+            | Event::ThreadUpdate(channel) => state.insert_channel(channel),
+            Event::ChannelDelete(channel) | Event::ThreadDelete(channel) => state.remove_channel(&channel.id),
+            Event::ThreadListSync(sync) => {
+                for thread in &sync.threads {
+                    state.insert_channel(&Channel { guild_id: Some(sync.guild_id.to_owned()), ..thread.clone() });
+                }
+            },
+            Event::GuildRoleCreate(event) => {
+                state.insert_role(&event.guild_id, &event.role);
+            },
+            Event::GuildRoleUpdate(event) => {
+                event.cached = state.insert_role(&event.guild_id, &event.role);
+                event.changes = changes(event.cached.as_ref(), Some(&event.role));
+            },
+            Event::GuildRoleDelete(event) => state.remove_role(&event.guild_id, &event.role_id),
+            Event::GuildEmojisUpdate(event) => state.replace_emojis(&event.guild_id, &event.emojis),
+            Event::GuildMemberAdd(event) => {
+                state.insert_member(&event.guild_id, &event.member, false);
+                state.update_member_count(&event.guild_id, |count| count + 1);
+            },
+            Event::GuildMemberUpdate(event) => {
+                if let Some(user_id) = event.member.user.as_ref().map(|user| user.id.to_owned()) {
+                    event.cached = state.member(&event.guild_id, &user_id);
+                    state.update_member(&event.guild_id, &event.member, false);
+                    event.changes = changes(event.cached.as_ref(), state.member(&event.guild_id, &user_id).as_ref());
+                }
+            },
+            Event::GuildMemberRemove(event) => {
+                state.remove_member(&event.guild_id, &event.user.id);
+                state.update_member_count(&event.guild_id, |count| count.saturating_sub(1));
+            },
+            Event::GuildMembersChunk(chunk) => {
+                for member in &chunk.members {
+                    state.insert_member(&chunk.guild_id, member, false);
+                }
+
+                for presence in chunk.presences.iter().flatten() {
+                    state.insert_presence(&chunk.guild_id, presence);
+                }
+            },
+            Event::VoiceStateUpdate(voice_state) => {
+                if let Some(guild_id) = voice_state.guild_id.clone() {
+                    state.insert_voice_state(&guild_id, voice_state);
+                }
+            },
+            Event::PresenceUpdate(presence) => {
+                if let Some(guild_id) = presence.guild_id.clone() {
+                    state.insert_presence(&guild_id, presence);
+                }
+            },
+            Event::MessageCreate(message) => {
+                // The author of a message is an active member
+                if let (Some(guild_id), Some(author), Some(member)) = (&message.guild_id, &message.author, &message.member) {
+                    let member = GuildMember { user: Some(author.clone()), ..member.clone() };
+                    state.update_member(guild_id, &member, true);
+                }
+
+                if let Some(messages) = &mut state.messages {
+                    messages.insert(*message.clone());
+                }
+            },
+            Event::MessageUpdate(event) => {
+                if let Some(messages) = &mut state.messages {
+                    event.cached = messages.update(event.message.clone());
+                }
+            },
+            Event::MessageDelete(event) => {
+                if let Some(messages) = &mut state.messages {
+                    event.cached = messages.remove(&event.channel_id, &event.id);
+                }
+            },
+            Event::MessageDeleteBulk(event) => {
+                if let Some(messages) = &mut state.messages {
+                    event.cached = event.ids
+                        .iter()
+                        .filter_map(|id| messages.remove(&event.channel_id, id))
+                        .collect();
+                }
+            },
+            _ => {},
+        }
+
+        state.take_changes()
+    }
+}
+
+impl CacheState {
+    fn new(config: CacheConfig) -> Self {
+        Self {
+            messages: config.messages.clone().map(MessageStore::new),
+            config,
+            ..Default::default()
+        }
+    }
+
+    fn caches(&self, resources: CacheResources) -> bool {
+        self.config.caches(resources)
+    }
+
+    fn guild(&self, guild_id: &str) -> Option<Guild> {
+        let mut guild = self.guilds.get(guild_id)?.clone();
+
+        guild.roles = values(self.roles.get(guild_id));
+        guild.emojis = values(self.emojis.get(guild_id));
+        guild.roles.sort_by_key(|role| role.position);
+
+        Some(guild)
+    }
+
+    fn member(&self, guild_id: &str, user_id: &str) -> Option<GuildMember> {
+        let member = self.members.get(guild_id)?.get(user_id)?;
+        Some(with_user(self, user_id, member))
+    }
+
+    /// Marks an entity as changed, so it is given to the backend once the
+    /// event is applied
+    fn touch(&mut self, key: CacheKey) {
+        if let Some(changed) = &mut self.changed {
+            changed.insert(key);
+        }
+    }
+
+    /// The current version of every entity changed since the last call
+    fn take_changes(&mut self) -> Vec<CacheChange> {
+        let Some(changed) = &mut self.changed else {
+            return Vec::new();
+        };
+
+        let keys: Vec<_> = changed.drain().collect();
+        keys
+            .into_iter()
+            .map(|key| match self.entry(&key) {
+                Some(entry) => CacheChange::Insert { entry: Box::new(entry) },
+                None => CacheChange::Remove { key },
+            })
+            .collect()
+    }
+
+    fn entry(&self, key: &CacheKey) -> Option<CacheEntry> {
+        let entry = match key {
+            CacheKey::CurrentUser => CacheEntry::CurrentUser { user: self.current_user.clone()? },
+            CacheKey::User { id } => CacheEntry::User { user: self.users.get(id)?.clone() },
+            CacheKey::Guild { id } => CacheEntry::Guild { guild: Box::new(self.guilds.get(id)?.clone()) },
+            CacheKey::UnavailableGuild { id } => {
+                if !self.unavailable_guilds.contains(id) {
+                    return None;
+                }
+
+                CacheEntry::UnavailableGuild { id: id.to_owned() }
+            },
+            CacheKey::Channel { id } => CacheEntry::Channel { channel: Box::new(self.channels.get(id)?.clone()) },
+            CacheKey::Role { guild_id, id } => CacheEntry::Role {
+                guild_id: guild_id.to_owned(),
+                role: self.roles.get(guild_id)?.get(id)?.clone()
+            },
+            CacheKey::Emoji { guild_id, id } => CacheEntry::Emoji {
+                guild_id: guild_id.to_owned(),
+                emoji: self.emojis.get(guild_id)?.get(id)?.clone()
+            },
+            CacheKey::Member { guild_id, user_id } => CacheEntry::Member {
+                guild_id: guild_id.to_owned(),
+                user_id: user_id.to_owned(),
+                member: self.members.get(guild_id)?.get(user_id)?.clone()
+            },
+            CacheKey::VoiceState { guild_id, user_id } => CacheEntry::VoiceState {
+                guild_id: guild_id.to_owned(),
+                voice_state: self.voice_states.get(guild_id)?.get(user_id)?.clone()
+            },
+            CacheKey::Presence { guild_id, user_id } => CacheEntry::Presence {
+                guild_id: guild_id.to_owned(),
+                presence: self.presences.get(guild_id)?.get(user_id)?.clone()
+            },
+        };
+
+        Some(entry)
+    }
+
+    /// Puts back an entry stored by the backend
+    fn load(&mut self, entry: CacheEntry) {
+        let resources = match &entry {
+            CacheEntry::CurrentUser { .. } => CacheResources::empty(),
+            CacheEntry::User { .. } => CacheResources::USERS,
+            CacheEntry::Guild { .. } | CacheEntry::UnavailableGuild { .. } => CacheResources::GUILDS,
+            CacheEntry::Channel { .. } => CacheResources::CHANNELS,
+            CacheEntry::Role { .. } => CacheResources::ROLES,
+            CacheEntry::Emoji { .. } => CacheResources::EMOJIS,
+            CacheEntry::Member { .. } => CacheResources::MEMBERS,
+            CacheEntry::VoiceState { .. } => CacheResources::VOICE_STATES,
+            CacheEntry::Presence { .. } => CacheResources::PRESENCES,
+        };
+
+        if !self.caches(resources) {
+            return;
+        }
+
+        match entry {
+            CacheEntry::CurrentUser { user } => self.current_user = Some(user),
+            CacheEntry::User { user } => self.insert_user(&user),
+            CacheEntry::Guild { guild } => {
+                self.guilds.insert(guild.id.to_owned(), *guild);
+            },
+            CacheEntry::UnavailableGuild { id } => {
+                self.unavailable_guilds.insert(id);
+            },
+            CacheEntry::Channel { channel } => self.insert_channel(&channel),
+            CacheEntry::Role { guild_id, role } => {
+                self.insert_role(&guild_id, &role);
+            },
+            CacheEntry::Emoji { guild_id, emoji } => {
+                if let Some(emoji_id) = emoji.id.clone() {
+                    self.emojis.entry(guild_id).or_default().insert(emoji_id, emoji);
+                }
+            },
+            CacheEntry::Member { guild_id, user_id, member } => {
+                self.members.entry(guild_id).or_default().insert(user_id, member);
+            },
+            CacheEntry::VoiceState { guild_id, voice_state } => {
+                self.voice_states.entry(guild_id).or_default().insert(voice_state.user_id.to_owned(), voice_state);
+            },
+            CacheEntry::Presence { guild_id, presence } => {
+                self.presences.entry(guild_id).or_default().insert(presence.user.id.to_owned(), presence);
+            },
+        }
+    }
+
+    fn set_current_user(&mut self, user: User) -> Option<User> {
+        self.touch(CacheKey::CurrentUser);
+        self.current_user.replace(user)
+    }
+
+    fn insert_user(&mut self, user: &User) {
+        if !self.caches(CacheResources::USERS) {
+            return;
+        }
+
+        self.touch(CacheKey::User { id: user.id.to_owned() });
+        self.users.insert(user.id.to_owned(), user.clone());
+    }
+
+    fn set_unavailable(&mut self, guild_id: &str, unavailable: bool) {
+        let changed = match unavailable {
+            true if !self.caches(CacheResources::GUILDS) => false,
+            true => self.unavailable_guilds.insert(guild_id.to_owned()),
+            false => self.unavailable_guilds.remove(guild_id),
+        };
+
+        if changed {
+            self.touch(CacheKey::UnavailableGuild { id: guild_id.to_owned() });
+        }
+    }
+
+    fn insert_guild(&mut self, guild: &Guild) {
+        let guild_id = guild.id.to_owned();
+        self.set_unavailable(&guild_id, false);
+
+        // GUILD_CREATE carries every channel and voice state, so any other was
+        // left by an earlier session
+        let channels: Vec<_> = guild.channels.iter().flatten().chain(guild.threads.iter().flatten()).collect();
+        let stale: Vec<_> = self.guild_channels
+            .get(&guild_id)
+            .into_iter()
+            .flatten()
+            .filter(|channel_id| !channels.iter().any(|channel| channel.id == **channel_id))
+            .cloned()
+            .collect();
+
+        for channel_id in stale {
+            self.remove_channel(&channel_id);
+        }
+
+        for channel in channels {
+            // Channels sent in GUILD_CREATE do not carry their guild id
+            self.insert_channel(&Channel { guild_id: Some(guild_id.to_owned()), ..channel.clone() });
+        }
+
+        for member in guild.members.iter().flatten() {
+            self.insert_member(&guild_id, member, false);
+        }
+
+        for user_id in self.voice_states.remove(&guild_id).into_iter().flat_map(|voice_states| voice_states.into_keys()) {
+            self.touch(CacheKey::VoiceState { guild_id: guild_id.to_owned(), user_id });
+        }
+
+        for voice_state in guild.voice_states.iter().flatten() {
+            self.insert_voice_state(&guild_id, voice_state);
+        }
+
+        // Members who are offline are left out, so presences are replaced too
+        for user_id in self.presences.remove(&guild_id).into_iter().flat_map(|presences| presences.into_keys()) {
+            self.touch(CacheKey::Presence { guild_id: guild_id.to_owned(), user_id });
+        }
+
+        for presence in guild.presences.iter().flatten() {
+            self.insert_presence(&guild_id, presence);
+        }
+
+        self.update_guild(guild);
+    }
+
+    /// Stores a guild and replaces its roles and emojis, keeping what is only
+    /// sent in GUILD_CREATE
+    fn update_guild(&mut self, guild: &Guild) {
+        let guild_id = guild.id.to_owned();
+
+        for role_id in self.roles.remove(&guild_id).into_iter().flat_map(|roles| roles.into_keys()) {
+            self.touch(CacheKey::Role { guild_id: guild_id.to_owned(), id: role_id });
+        }
+
+        for role in &guild.roles {
+            self.insert_role(&guild_id, role);
+        }
+
+        self.replace_emojis(&guild_id, &guild.emojis);
+
+        let previous = self.guilds.remove(&guild_id);
+        let mut guild = Guild {
+            roles: Vec::new(),
+            emojis: Vec::new(),
+            members: None,
+            channels: None,
+            threads: None,
+            presences: None,
+            voice_states: None,
+            ..guild.clone()
+        };
+
+        if let Some(previous) = previous {
+            guild.joined_at = guild.joined_at.or(previous.joined_at);
+            guild.large = guild.large.or(previous.large);
+            guild.member_count = guild.member_count.or(previous.member_count);
+            guild.stage_instances = guild.stage_instances.or(previous.stage_instances);
+            guild.guild_scheduled_events = guild.guild_scheduled_events.or(previous.guild_scheduled_events);
+        }
+
+        if self.caches(CacheResources::GUILDS) {
+            self.touch(CacheKey::Guild { id: guild_id.to_owned() });
+            self.guilds.insert(guild_id, guild);
+        }
+    }
+
+    fn update_member_count(&mut self, guild_id: &str, update: impl FnOnce(u32) -> u32) {
+        if let Some(guild) = self.guilds.get_mut(guild_id) {
+            guild.member_count = guild.member_count.map(update);
+            self.touch(CacheKey::Guild { id: guild_id.to_owned() });
+        }
+    }
+
+    fn remove_guild(&mut self, guild_id: &str) {
+        if self.guilds.remove(guild_id).is_some() {
+            self.touch(CacheKey::Guild { id: guild_id.to_owned() });
+        }
+
+        for role_id in self.roles.remove(guild_id).into_iter().flat_map(|roles| roles.into_keys()) {
+            self.touch(CacheKey::Role { guild_id: guild_id.to_owned(), id: role_id });
+        }
+
+        for emoji_id in self.emojis.remove(guild_id).into_iter().flat_map(|emojis| emojis.into_keys()) {
+            self.touch(CacheKey::Emoji { guild_id: guild_id.to_owned(), id: emoji_id });
+        }
+
+        for user_id in self.members.remove(guild_id).into_iter().flat_map(|members| members.into_keys()) {
+            self.touch(CacheKey::Member { guild_id: guild_id.to_owned(), user_id });
+        }
+
+        for user_id in self.voice_states.remove(guild_id).into_iter().flat_map(|voice_states| voice_states.into_keys()) {
+            self.touch(CacheKey::VoiceState { guild_id: guild_id.to_owned(), user_id });
+        }
+
+        for user_id in self.presences.remove(guild_id).into_iter().flat_map(|presences| presences.into_keys()) {
+            self.touch(CacheKey::Presence { guild_id: guild_id.to_owned(), user_id });
+        }
+
+        for channel_id in self.guild_channels.remove(guild_id).into_iter().flatten() {
+            self.remove_channel(&channel_id);
+        }
+    }
+
+    fn insert_channel(&mut self, channel: &Channel) {
+        if !self.caches(CacheResources::CHANNELS) {
+            return;
+        }
+
+        if let Some(guild_id) = &channel.guild_id {
+            self.guild_channels
+                .entry(guild_id.to_owned())
+                .or_default()
+                .insert(channel.id.to_owned());
+        }
+
+        self.touch(CacheKey::Channel { id: channel.id.to_owned() });
+        self.channels.insert(channel.id.to_owned(), channel.clone());
+    }
+
+    fn remove_channel(&mut self, channel_id: &str) {
+        if let Some(messages) = &mut self.messages {
+            messages.remove_channel(channel_id);
+        }
+
+        let Some(channel) = self.channels.remove(channel_id) else {
+            return;
+        };
+
+        self.touch(CacheKey::Channel { id: channel_id.to_owned() });
+        if let Some(channels) = channel.guild_id.and_then(|guild_id| self.guild_channels.get_mut(&guild_id)) {
+            channels.remove(channel_id);
+        }
+    }
+
+    /// Stores a role, returning the version it replaces if any
+    fn insert_role(&mut self, guild_id: &str, role: &Role) -> Option<Role> {
+        if !self.caches(CacheResources::ROLES) {
+            return None;
+        }
+
+        self.touch(CacheKey::Role { guild_id: guild_id.to_owned(), id: role.id.to_owned() });
+        self.roles
+            .entry(guild_id.to_owned())
+            .or_default()
+            .insert(role.id.to_owned(), role.clone())
+    }
+
+    fn remove_role(&mut self, guild_id: &str, role_id: &str) {
+        if let Some(roles) = self.roles.get_mut(guild_id) {
+            roles.remove(role_id);
+            self.touch(CacheKey::Role { guild_id: guild_id.to_owned(), id: role_id.to_owned() });
+        }
+
+        // Discord does not send member updates for a deleted role
+        let mut updated = Vec::new();
+        for (user_id, member) in self.members.get_mut(guild_id).into_iter().flatten() {
+            if member.roles.iter().any(|id| id == role_id) {
+                member.roles.retain(|id| id != role_id);
+                updated.push(user_id.to_owned());
+            }
+        }
+
+        for user_id in updated {
+            self.touch(CacheKey::Member { guild_id: guild_id.to_owned(), user_id });
+        }
+    }
+
+    fn replace_emojis(&mut self, guild_id: &str, emojis: &[Emoji]) {
+        for emoji_id in self.emojis.remove(guild_id).into_iter().flat_map(|emojis| emojis.into_keys()) {
+            self.touch(CacheKey::Emoji { guild_id: guild_id.to_owned(), id: emoji_id });
+        }
+
+        if !self.caches(CacheResources::EMOJIS) {
+            return;
+        }
+
+        let emojis: HashMap<_, _> = emojis
+            .iter()
+            .filter_map(|emoji| Some((emoji.id.clone()?, emoji.clone())))
+            .collect();
+
+        for emoji_id in emojis.keys() {
+            self.touch(CacheKey::Emoji { guild_id: guild_id.to_owned(), id: emoji_id.to_owned() });
+        }
+
+        self.emojis.insert(guild_id.to_owned(), emojis);
+    }
+
+    /// Stores a member along with its user. Members who are not `active`
+    /// are only stored if every member is cached, or to update one already
+    /// cached
+    fn insert_member(&mut self, guild_id: &str, member: &GuildMember, active: bool) {
+        let Some(user) = &member.user else {
+            return;
+        };
+
+        let cached = self.members.get(guild_id).is_some_and(|members| members.contains_key(&user.id));
+        let current_user = self.current_user.as_ref().is_some_and(|current_user| current_user.id == user.id);
+        if self.config.active_members_only && !active && !cached && !current_user {
+            return;
+        }
+
+        self.insert_user(user);
+        if !self.caches(CacheResources::MEMBERS) {
+            return;
+        }
+
+        self.touch(CacheKey::Member { guild_id: guild_id.to_owned(), user_id: user.id.to_owned() });
+        self.members
+            .entry(guild_id.to_owned())
+            .or_default()
+            .insert(user.id.to_owned(), GuildMember { user: None, ..member.clone() });
+    }
+
+    /// GUILD_MEMBER_UPDATE and the members of messages do not carry `deaf`
+    /// and `mute`, so they are kept
+    fn update_member(&mut self, guild_id: &str, member: &GuildMember, active: bool) {
+        let previous = member.user
+            .as_ref()
+            .and_then(|user| self.members.get(guild_id)?.get(&user.id));
+
+        let member = match previous {
+            Some(previous) => GuildMember { deaf: previous.deaf, mute: previous.mute, ..member.clone() },
+            None => member.clone(),
+        };
+
+        self.insert_member(guild_id, &member, active);
+    }
+
+    fn remove_member(&mut self, guild_id: &str, user_id: &str) {
+        if let Some(members) = self.members.get_mut(guild_id) {
+            members.remove(user_id);
+            self.touch(CacheKey::Member { guild_id: guild_id.to_owned(), user_id: user_id.to_owned() });
+        }
+    }
+
+    /// Stores the voice state of a user, or removes it once they leave the voice channel
+    fn insert_voice_state(&mut self, guild_id: &str, voice_state: &VoiceState) {
+        // Joining a voice channel makes a member active
+        if let (Some(member), Some(_)) = (&voice_state.member, &voice_state.channel_id) {
+            self.insert_member(guild_id, member, true);
+        }
+
+        if !self.caches(CacheResources::VOICE_STATES) {
+            return;
+        }
+
+        self.touch(CacheKey::VoiceState { guild_id: guild_id.to_owned(), user_id: voice_state.user_id.to_owned() });
+        let voice_states = self.voice_states.entry(guild_id.to_owned()).or_default();
+
+        if voice_state.channel_id.is_none() {
+            voice_states.remove(&voice_state.user_id);
+            return;
+        }
+
+        voice_states.insert(voice_state.user_id.to_owned(), VoiceState {
+            guild_id: Some(guild_id.to_owned()),
+            member: None,
+            ..voice_state.clone()
+        });
+    }
+
+    /// Stores the presence of a user, or removes it once they go offline
+    fn insert_presence(&mut self, guild_id: &str, presence: &Presence) {
+        if !self.caches(CacheResources::PRESENCES) {
+            return;
+        }
+
+        self.touch(CacheKey::Presence { guild_id: guild_id.to_owned(), user_id: presence.user.id.to_owned() });
+        let presences = self.presences.entry(guild_id.to_owned()).or_default();
+
+        if presence.status == Status::Offline {
+            presences.remove(&presence.user.id);
+            return;
+        }
+
+        presences.insert(presence.user.id.to_owned(), Presence {
+            guild_id: Some(guild_id.to_owned()),
+            ..presence.clone()
+        });
+    }
+}
+
+/// Puts the cached user back into a member
+fn with_user(state: &CacheState, user_id: &str, member: &GuildMember) -> GuildMember {
+    GuildMember {
+        user: state.users.get(user_id).cloned(),
+        ..member.clone()
+    }
+}
+
+/// The fields which changed between the cached and updated version of an
+/// entity, if the cache had it
+fn changes<T: Serialize>(before: Option<&T>, after: Option<&T>) -> Vec<FieldChange> {
+    match (before, after) {
+        (Some(before), Some(after)) => diff(before, after),
+        _ => Vec::new()
+    }
+}
+
+/// Whether a guild is sent to a shard, given the shard id and the total
+/// amount of shards of its session
+fn on_shard(guild_id: &str, shard: Option<[u32; 2]>) -> bool {
+    match shard {
+        Some([shard_id, shards]) if shards > 1 => guild_id
+            .parse::<u64>()
+            .is_ok_and(|guild_id| (guild_id >> 22) % u64::from(shards) == u64::from(shard_id)),
+        _ => true
+    }
+}
+
+fn values<T: Clone>(map: Option<&HashMap<Snowflake, T>>) -> Vec<T> {
+    map.map(|map| map.values().cloned().collect()).unwrap_or_default()
+}
@@ -0,0 +1,77 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+/// How many entities of a kind are cached and roughly how much memory they
+/// take, measured as their size encoded as JSON
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ResourceUsage {
+    pub count: usize,
+    pub bytes: usize
+}
+
+impl std::ops::Add for ResourceUsage {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            count: self.count + other.count,
+            bytes: self.bytes + other.bytes
+        }
+    }
+}
+
+/// The memory used by every kind of entity in a [super::Cache], see [super::Cache::stats]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+    pub guilds: ResourceUsage,
+    pub channels: ResourceUsage,
+    pub roles: ResourceUsage,
+    pub emojis: ResourceUsage,
+    pub members: ResourceUsage,
+    pub users: ResourceUsage,
+    pub voice_states: ResourceUsage,
+    pub presences: ResourceUsage,
+    pub messages: ResourceUsage
+}
+
+impl CacheStats {
+    /// The usage of every kind of entity combined
+    pub fn total(&self) -> ResourceUsage {
+        self.guilds
+            + self.channels
+            + self.roles
+            + self.emojis
+            + self.members
+            + self.users
+            + self.voice_states
+            + self.presences
+            + self.messages
+    }
+}
+
+/// The usage of a set of entities
+pub(crate) fn usage<'a, T: Serialize + 'a>(values: impl IntoIterator<Item = &'a T>) -> ResourceUsage {
+    values
+        .into_iter()
+        .fold(ResourceUsage::default(), |usage, value| usage + ResourceUsage { count: 1, bytes: encoded_size(value) })
+}
+
+/// The length of a value encoded as JSON, without allocating it
+pub(crate) fn encoded_size<T: Serialize>(value: &T) -> usize {
+    struct Counter(usize);
+
+    impl Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    serde_json::to_writer(&mut counter, value).map_or(0, |_| counter.0)
+}
@@ -0,0 +1,51 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use crate::models::{Channel, Emoji, Guild, GuildMember, Presence, Role, Snowflake, User, VoiceState};
+use super::backend::CacheKey;
+use super::config::CacheConfig;
+use super::messages::MessageStore;
+use super::writer::CacheWriter;
+
+/// Entities received from the gateway, kept up to date as dispatch events
+/// arrive. Cloning a cache is cheap, and every clone shares the same entities
+///
+/// Reads never wait for the gateway for long, so the cache can be read from
+/// any number of handlers at once. Accessors return copies of the cached
+/// entities, which do not change as the cache is updated
+pub struct Cache {
+    pub(crate) state: Arc<RwLock<CacheState>>,
+    pub(crate) writer: Arc<CacheWriter>
+}
+
+/// Every cached entity, indexed by id
+///
+/// Entities which belong to a guild are indexed by guild first. Guilds are
+/// stored without their members, channels, roles, emojis, voice states and
+/// presences, which are stored in their own maps instead. Roles and emojis
+/// are put back by [Cache::guild]
+#[derive(Debug, Default)]
+pub(crate) struct CacheState {
+    pub current_user: Option<User>,
+    pub guilds: HashMap<Snowflake, Guild>,
+    /// Guilds the bot is in which are not available yet or because of an outage
+    pub unavailable_guilds: HashSet<Snowflake>,
+    /// Guild channels, threads and direct message channels
+    pub channels: HashMap<Snowflake, Channel>,
+    /// The channels and threads of every guild
+    pub guild_channels: HashMap<Snowflake, HashSet<Snowflake>>,
+    pub roles: HashMap<Snowflake, HashMap<Snowflake, Role>>,
+    pub emojis: HashMap<Snowflake, HashMap<Snowflake, Emoji>>,
+    /// Members are stored without their user, which is stored in `users`
+    pub members: HashMap<Snowflake, HashMap<Snowflake, GuildMember>>,
+    pub users: HashMap<Snowflake, User>,
+    pub voice_states: HashMap<Snowflake, HashMap<Snowflake, VoiceState>>,
+    /// Presences are stored as received, so their user is usually partial
+    pub presences: HashMap<Snowflake, HashMap<Snowflake, Presence>>,
+    /// Only kept if enabled through [Cache::with_messages]
+    pub messages: Option<MessageStore>,
+    /// The entities changed by the current event, only kept if the backend
+    /// persists them
+    pub changed: Option<HashSet<CacheKey>>,
+    pub config: CacheConfig
+}
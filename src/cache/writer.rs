@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use crate::util::{log_message, BackgroundWriter, Sink};
+use super::backend::{CacheBackend, CacheChange};
+
+/// Hands the changes made to a [super::Cache] to its [CacheBackend] from a
+/// dedicated thread, so the gateway never waits on the backend's I/O
+///
+/// The thread stops once the last clone of the cache is dropped, after
+/// writing what is left
+pub(crate) type CacheWriter = BackgroundWriter<Vec<CacheChange>>;
+
+impl CacheWriter {
+    pub fn new(backend: Arc<dyn CacheBackend>) -> Self {
+        // Backends which keep nothing do not need a thread
+        if !backend.persists() {
+            return Self::disabled();
+        }
+
+        Self::spawn("discord-rs-cache", BackendSink(backend))
+    }
+}
+
+struct BackendSink(Arc<dyn CacheBackend>);
+
+impl Sink for BackendSink {
+    type Item = Vec<CacheChange>;
+
+    fn write(&mut self, changes: Vec<CacheChange>) {
+        if let Err(error) = self.0.write(&changes) {
+            log_message("error", &format!("Failed to store the cache: {error}"));
+        }
+    }
+}
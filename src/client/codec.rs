@@ -0,0 +1,96 @@
+use std::fmt::Debug;
+
+use super::errors::GatewayError;
+use super::etf;
+use super::types::GatewayEvent;
+
+/// Turns gateway payloads into [GatewayEvent]s and back
+/// https://discord.com/developers/docs/topics/gateway#encoding-and-compression
+///
+/// The codec of a [super::Client] is set through [super::ClientBuilder::with_codec],
+/// and defaults to [JsonCodec]
+///
+/// # Example
+/// ```
+/// use discord_rs::client::{EtfCodec, GatewayCodec, GatewayEvent, JsonCodec};
+/// use serde_json::json;
+///
+/// let heartbeat = GatewayEvent { op: 1, d: Some(json!(251)), s: None, t: None };
+///
+/// for codec in [&JsonCodec as &dyn GatewayCodec, &EtfCodec] {
+///     let payload = codec.encode(&heartbeat).unwrap();
+///     let decoded = codec.decode(&payload).unwrap();
+///
+///     assert_eq!(decoded.op, 1);
+///     assert_eq!(decoded.d, Some(json!(251)));
+/// }
+/// ```
+pub trait GatewayCodec: Debug + Send + Sync {
+    /// The value of the `encoding` query parameter used to connect
+    fn encoding(&self) -> &'static str;
+
+    /// Whether payloads are sent as binary frames rather than text frames
+    fn is_binary(&self) -> bool;
+
+    /// Decodes a payload sent by the gateway, after any transport compression was removed
+    fn decode(&self, payload: &[u8]) -> Result<GatewayEvent, GatewayError>;
+
+    /// Encodes a command to be sent to the gateway
+    fn encode(&self, event: &GatewayEvent) -> Result<Vec<u8>, GatewayError>;
+}
+
+/// Sends and receives payloads as JSON text
+#[derive(Debug, Copy, Clone, Default)]
+pub struct JsonCodec;
+
+impl GatewayCodec for JsonCodec {
+    fn encoding(&self) -> &'static str {
+        "json"
+    }
+
+    fn is_binary(&self) -> bool {
+        false
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<GatewayEvent, GatewayError> {
+        serde_json::from_slice(payload)
+            .map_err(|_| GatewayError::Protocol("Failed to deserialize incoming data JSON"))
+    }
+
+    fn encode(&self, event: &GatewayEvent) -> Result<Vec<u8>, GatewayError> {
+        serde_json::to_vec(event)
+            .map_err(|_| GatewayError::Protocol("Failed to serialize gateway command"))
+    }
+}
+
+/// Sends and receives payloads in the Erlang External Term Format, which is
+/// cheaper to parse for large payloads such as GUILD_CREATE
+///
+/// Snowflakes are sent as integers over ETF, and are turned back into strings
+/// so the same models work with both codecs
+#[derive(Debug, Copy, Clone, Default)]
+pub struct EtfCodec;
+
+impl GatewayCodec for EtfCodec {
+    fn encoding(&self) -> &'static str {
+        "etf"
+    }
+
+    fn is_binary(&self) -> bool {
+        true
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<GatewayEvent, GatewayError> {
+        let value = etf::decode(payload).map_err(GatewayError::Protocol)?;
+
+        serde_json::from_value(value)
+            .map_err(|_| GatewayError::Protocol("Failed to deserialize incoming data ETF"))
+    }
+
+    fn encode(&self, event: &GatewayEvent) -> Result<Vec<u8>, GatewayError> {
+        let value = serde_json::to_value(event)
+            .map_err(|_| GatewayError::Protocol("Failed to serialize gateway command"))?;
+
+        Ok(etf::encode(&value))
+    }
+}
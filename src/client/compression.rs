@@ -0,0 +1,107 @@
+use flate2::{Decompress, FlushDecompress, Status};
+
+use super::errors::GatewayError;
+
+/// Every complete message of a zlib stream ends with the suffix of a `Z_SYNC_FLUSH`
+const ZLIB_SUFFIX: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// How a gateway connection compresses the payloads sent by Discord
+/// https://discord.com/developers/docs/topics/gateway#transport-compression
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// Payloads are sent as plain text frames
+    #[default]
+    None,
+    /// Every payload of a connection is part of a single zlib stream, sent as binary frames
+    ZlibStream
+}
+
+impl Compression {
+    /// The query parameter which requests this compression from the gateway
+    pub(crate) fn query(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::ZlibStream => "&compress=zlib-stream",
+        }
+    }
+}
+
+/// Inflates the binary frames of a `compress=zlib-stream` connection
+///
+/// Discord compresses the whole connection as one zlib stream, so a single
+/// inflater has to see every frame of a connection in order. A payload may be
+/// split across several frames, and is only complete once a frame ends with
+/// the `Z_SYNC_FLUSH` suffix
+///
+/// # Example
+/// ```
+/// use discord_rs::client::ZlibInflater;
+/// use flate2::{Compress, Compression, FlushCompress};
+///
+/// // Compress a payload the same way the gateway does
+/// let mut compress = Compress::new(Compression::default(), true);
+/// let mut frame = Vec::with_capacity(128);
+/// compress.compress_vec(br#"{"op":11,"d":null}"#, &mut frame, FlushCompress::Sync).unwrap();
+///
+/// let mut inflater = ZlibInflater::new();
+/// let (first, last) = frame.split_at(5);
+///
+/// assert_eq!(inflater.push(first).unwrap(), None);
+/// assert_eq!(inflater.push(last).unwrap().as_deref(), Some(&br#"{"op":11,"d":null}"#[..]));
+/// ```
+pub struct ZlibInflater {
+    context: Decompress,
+    /// Frames of the payload which has not been completed yet
+    buffer: Vec<u8>
+}
+
+impl ZlibInflater {
+    pub fn new() -> Self {
+        Self {
+            context: Decompress::new(true),
+            buffer: Vec::new()
+        }
+    }
+
+    /// Adds a binary frame to the stream, returning the payload it completes if any
+    ///
+    /// # Errors
+    /// * If the stream is corrupted. The inflater cannot be used afterwards,
+    ///   and the connection should be dropped
+    pub fn push(&mut self, frame: &[u8]) -> Result<Option<Vec<u8>>, GatewayError> {
+        self.buffer.extend_from_slice(frame);
+
+        if !self.buffer.ends_with(&ZLIB_SUFFIX) {
+            return Ok(None);
+        }
+
+        let mut output = Vec::with_capacity(self.buffer.len() * 4);
+        let mut input = self.buffer.as_slice();
+
+        loop {
+            let consumed = self.context.total_in();
+            let status = self.context
+                .decompress_vec(input, &mut output, FlushDecompress::Sync)
+                .map_err(|_| GatewayError::Protocol("Failed to inflate compressed gateway payload"))?;
+
+            input = &input[(self.context.total_in() - consumed) as usize..];
+
+            match status {
+                Status::StreamEnd => break,
+                // The output was filled before the input ran out
+                Status::Ok | Status::BufError if output.len() == output.capacity() => output.reserve(output.capacity()),
+                _ if input.is_empty() => break,
+                _ => {},
+            }
+        }
+
+        self.buffer.clear();
+        Ok(Some(output))
+    }
+}
+
+impl Default for ZlibInflater {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,166 @@
+/// Why a gateway connection could not be established or was ended
+///
+/// The variants from [GatewayError::UnknownError] to [GatewayError::DisallowedIntents]
+/// map to the close codes 4000 to 4014 sent by Discord
+/// https://discord.com/developers/docs/topics/opcodes-and-status-codes#gateway-gateway-close-event-codes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GatewayError {
+    /// 4000, something went wrong on Discord's side
+    UnknownError,
+    /// 4001, an invalid opcode or payload for an opcode was sent
+    UnknownOpcode,
+    /// 4002, an invalid payload was sent
+    DecodeError,
+    /// 4003, a payload was sent before identifying
+    NotAuthenticated,
+    /// 4004, the token sent when identifying is incorrect
+    AuthenticationFailed,
+    /// 4005, more than one identify was sent
+    AlreadyAuthenticated,
+    /// 4007, an invalid sequence was sent when resuming
+    InvalidSequence,
+    /// 4008, payloads were sent too quickly
+    RateLimited,
+    /// 4009, the session timed out
+    SessionTimedOut,
+    /// 4010, an invalid shard was sent when identifying
+    InvalidShard,
+    /// 4011, the bot is in too many guilds to connect without sharding
+    ShardingRequired,
+    /// 4012, an invalid version of the gateway was requested
+    InvalidApiVersion,
+    /// 4013, an invalid intent was sent when identifying
+    InvalidIntents,
+    /// 4014, an intent the bot is not approved for was sent when identifying
+    DisallowedIntents,
+    /// Closed with a code Discord does not document
+    Closed(u16),
+    /// The websocket could not be opened, the handshake failed or the session ended early
+    Connection(&'static str),
+    /// Discord sent a payload that could not be understood
+    Protocol(&'static str),
+    /// A request to the REST API failed
+    Request(&'static str),
+    /// The client or shard manager was set up incorrectly
+    Configuration(&'static str),
+    /// The [super::EventStream] was not consumed fast enough with [super::Backpressure::Error]
+    EventStreamFull,
+    /// A command was sent to a shard, or a client, whose connection is not running
+    ShardNotRunning,
+    /// A command could not be sent as is, such as one larger than the gateway accepts
+    InvalidCommand(&'static str)
+}
+
+impl GatewayError {
+    /// Maps a close code sent by Discord to the error it stands for
+    ///
+    /// # Example
+    /// ```
+    /// use discord_rs::client::GatewayError;
+    ///
+    /// let error = GatewayError::from_close_code(4014);
+    /// assert_eq!(error, GatewayError::DisallowedIntents);
+    /// assert!(error.is_fatal());
+    ///
+    /// assert!(GatewayError::from_close_code(4009).is_reconnectable());
+    /// assert!(!GatewayError::from_close_code(4009).is_resumable());
+    /// ```
+    pub fn from_close_code(code: u16) -> Self {
+        match code {
+            4000 => GatewayError::UnknownError,
+            4001 => GatewayError::UnknownOpcode,
+            4002 => GatewayError::DecodeError,
+            4003 => GatewayError::NotAuthenticated,
+            4004 => GatewayError::AuthenticationFailed,
+            4005 => GatewayError::AlreadyAuthenticated,
+            4007 => GatewayError::InvalidSequence,
+            4008 => GatewayError::RateLimited,
+            4009 => GatewayError::SessionTimedOut,
+            4010 => GatewayError::InvalidShard,
+            4011 => GatewayError::ShardingRequired,
+            4012 => GatewayError::InvalidApiVersion,
+            4013 => GatewayError::InvalidIntents,
+            4014 => GatewayError::DisallowedIntents,
+            code => GatewayError::Closed(code),
+        }
+    }
+
+    /// The close code this error was created from, if any
+    pub fn close_code(&self) -> Option<u16> {
+        match self {
+            GatewayError::UnknownError => Some(4000),
+            GatewayError::UnknownOpcode => Some(4001),
+            GatewayError::DecodeError => Some(4002),
+            GatewayError::NotAuthenticated => Some(4003),
+            GatewayError::AuthenticationFailed => Some(4004),
+            GatewayError::AlreadyAuthenticated => Some(4005),
+            GatewayError::InvalidSequence => Some(4007),
+            GatewayError::RateLimited => Some(4008),
+            GatewayError::SessionTimedOut => Some(4009),
+            GatewayError::InvalidShard => Some(4010),
+            GatewayError::ShardingRequired => Some(4011),
+            GatewayError::InvalidApiVersion => Some(4012),
+            GatewayError::InvalidIntents => Some(4013),
+            GatewayError::DisallowedIntents => Some(4014),
+            GatewayError::Closed(code) => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Whether the previous session may be resumed after reconnecting
+    pub fn is_resumable(&self) -> bool {
+        matches!(
+            self,
+            GatewayError::UnknownError
+                | GatewayError::UnknownOpcode
+                | GatewayError::DecodeError
+                | GatewayError::NotAuthenticated
+                | GatewayError::AlreadyAuthenticated
+                | GatewayError::RateLimited
+                | GatewayError::Closed(_)
+                | GatewayError::Connection(_)
+                | GatewayError::Protocol(_)
+        )
+    }
+
+    /// Whether connecting again may succeed, either by resuming or with a new session
+    pub fn is_reconnectable(&self) -> bool {
+        self.is_resumable() || matches!(self, GatewayError::InvalidSequence | GatewayError::SessionTimedOut)
+    }
+
+    /// Whether connecting again is pointless until the bot is reconfigured
+    pub fn is_fatal(&self) -> bool {
+        !self.is_reconnectable()
+    }
+}
+
+impl std::error::Error for GatewayError {}
+
+impl std::fmt::Display for GatewayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GatewayError::UnknownError => write!(f, "Discord closed the connection because of an unknown error"),
+            GatewayError::UnknownOpcode => write!(f, "An invalid gateway opcode was sent"),
+            GatewayError::DecodeError => write!(f, "An invalid gateway payload was sent"),
+            GatewayError::NotAuthenticated => write!(f, "A payload was sent before identifying"),
+            GatewayError::AuthenticationFailed => write!(f, "Authentication failed. Make sure your token is correct"),
+            GatewayError::AlreadyAuthenticated => write!(f, "More than one identify was sent"),
+            GatewayError::InvalidSequence => write!(f, "An invalid sequence was sent when resuming"),
+            GatewayError::RateLimited => write!(f, "Gateway commands were sent too quickly"),
+            GatewayError::SessionTimedOut => write!(f, "The gateway session timed out"),
+            GatewayError::InvalidShard => write!(f, "Invalid shard was sent when identifying"),
+            GatewayError::ShardingRequired => write!(f, "Sharding is required to connect with this many guilds"),
+            GatewayError::InvalidApiVersion => write!(f, "Invalid gateway API version"),
+            GatewayError::InvalidIntents => write!(f, "Invalid intents were sent when identifying"),
+            GatewayError::DisallowedIntents => write!(f, "Disallowed intents were sent when identifying"),
+            GatewayError::Closed(code) => write!(f, "The gateway closed the connection with code {}", code),
+            GatewayError::Connection(reason)
+            | GatewayError::Protocol(reason)
+            | GatewayError::Request(reason)
+            | GatewayError::Configuration(reason)
+            | GatewayError::InvalidCommand(reason) => write!(f, "{}", reason),
+            GatewayError::EventStreamFull => write!(f, "The event stream buffer is full"),
+            GatewayError::ShardNotRunning => write!(f, "The gateway connection is not running"),
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Conversion between [serde_json::Value]s and the Erlang External Term Format
+//! https://www.erlang.org/doc/apps/erts/erl_ext_dist.html
+//!
+//! Discord sends snowflakes as 64-bit integers over ETF, while the JSON
+//! encoding sends them as strings. To keep both encodings interchangeable,
+//! integers too large to be represented exactly by a double are decoded as
+//! strings, exactly like the JSON encoding does
+
+use flate2::read::ZlibDecoder;
+use serde_json::{Map, Number, Value};
+use std::io::Read;
+
+const VERSION: u8 = 131;
+const COMPRESSED: u8 = 80;
+const NEW_FLOAT_EXT: u8 = 70;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const FLOAT_EXT: u8 = 99;
+const ATOM_EXT: u8 = 100;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+
+/// The largest integer a double can represent exactly, 2^53
+const MAX_SAFE_INTEGER: u64 = 1 << 53;
+/// How deep lists, tuples and maps may be nested, so hostile terms cannot overflow the stack
+const MAX_DEPTH: usize = 128;
+/// The most memory reserved up front for a compressed term, whose declared size is not trusted
+const MAX_INITIAL_CAPACITY: usize = 1024 * 1024;
+/// The largest size a compressed term may declare. Even the READY of a large
+/// bot stays far below, so anything larger is refused before inflating
+const MAX_INFLATED_SIZE: usize = 64 * 1024 * 1024;
+
+/// Encodes a value as a versioned ETF term
+///
+/// Objects become maps with binary keys, strings become binaries and null
+/// becomes the `nil` atom
+pub(crate) fn encode(value: &Value) -> Vec<u8> {
+    let mut buffer = vec![VERSION];
+    encode_term(value, &mut buffer);
+    buffer
+}
+
+/// Decodes a versioned ETF term into a value
+pub(crate) fn decode(bytes: &[u8]) -> Result<Value, &'static str> {
+    let mut decoder = Decoder { bytes, position: 0, depth: 0 };
+
+    if decoder.u8()? != VERSION {
+        return Err("Unsupported ETF version");
+    }
+
+    if decoder.peek()? == COMPRESSED {
+        decoder.u8()?;
+        let size = decoder.u32()? as usize;
+        if size > MAX_INFLATED_SIZE {
+            return Err("Compressed ETF term is too large");
+        }
+
+        let mut inflated = Vec::with_capacity(size.min(MAX_INITIAL_CAPACITY));
+
+        // Reading one byte past the declared size is enough to tell it was a lie
+        ZlibDecoder::new(&decoder.bytes[decoder.position..])
+            .take(size as u64 + 1)
+            .read_to_end(&mut inflated)
+            .map_err(|_| "Failed to inflate compressed ETF term")?;
+
+        if inflated.len() != size {
+            return Err("Compressed ETF term does not match its declared size");
+        }
+
+        return Decoder { bytes: &inflated, position: 0, depth: 0 }.term();
+    }
+
+    decoder.term()
+}
+
+fn encode_term(value: &Value, buffer: &mut Vec<u8>) {
+    match value {
+        Value::Null => encode_atom("nil", buffer),
+        Value::Bool(true) => encode_atom("true", buffer),
+        Value::Bool(false) => encode_atom("false", buffer),
+        Value::Number(number) => encode_number(number, buffer),
+        Value::String(string) => encode_binary(string, buffer),
+        Value::Array(values) if values.is_empty() => buffer.push(NIL_EXT),
+        Value::Array(values) => {
+            buffer.push(LIST_EXT);
+            buffer.extend_from_slice(&(values.len() as u32).to_be_bytes());
+
+            for value in values {
+                encode_term(value, buffer);
+            }
+
+            buffer.push(NIL_EXT);
+        },
+        Value::Object(map) => {
+            buffer.push(MAP_EXT);
+            buffer.extend_from_slice(&(map.len() as u32).to_be_bytes());
+
+            for (key, value) in map {
+                encode_binary(key, buffer);
+                encode_term(value, buffer);
+            }
+        },
+    }
+}
+
+fn encode_atom(atom: &str, buffer: &mut Vec<u8>) {
+    buffer.push(SMALL_ATOM_UTF8_EXT);
+    buffer.push(atom.len() as u8);
+    buffer.extend_from_slice(atom.as_bytes());
+}
+
+fn encode_binary(string: &str, buffer: &mut Vec<u8>) {
+    buffer.push(BINARY_EXT);
+    buffer.extend_from_slice(&(string.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(string.as_bytes());
+}
+
+fn encode_number(number: &Number, buffer: &mut Vec<u8>) {
+    if let Some(integer) = number.as_i64() {
+        if let Ok(small) = u8::try_from(integer) {
+            buffer.push(SMALL_INTEGER_EXT);
+            buffer.push(small);
+        } else if let Ok(integer) = i32::try_from(integer) {
+            buffer.push(INTEGER_EXT);
+            buffer.extend_from_slice(&integer.to_be_bytes());
+        } else {
+            encode_big(integer.unsigned_abs(), integer < 0, buffer);
+        }
+    } else if let Some(integer) = number.as_u64() {
+        encode_big(integer, false, buffer);
+    } else {
+        buffer.push(NEW_FLOAT_EXT);
+        buffer.extend_from_slice(&number.as_f64().unwrap_or_default().to_be_bytes());
+    }
+}
+
+fn encode_big(magnitude: u64, negative: bool, buffer: &mut Vec<u8>) {
+    let digits: Vec<u8> = magnitude
+        .to_le_bytes()
+        .into_iter()
+        .rev()
+        .skip_while(|byte| *byte == 0)
+        .collect();
+
+    buffer.push(SMALL_BIG_EXT);
+    buffer.push(digits.len() as u8);
+    buffer.push(negative as u8);
+    buffer.extend(digits.into_iter().rev());
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    /// How many terms are being decoded within each other
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], &'static str> {
+        let end = self.position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("Unexpected end of ETF term")?;
+
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn peek(&self) -> Result<u8, &'static str> {
+        self.bytes.get(self.position).copied().ok_or("Unexpected end of ETF term")
+    }
+
+    fn u8(&mut self) -> Result<u8, &'static str> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, &'static str> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, &'static str> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self, length: usize) -> Result<String, &'static str> {
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| "ETF string is not valid UTF-8")
+    }
+
+    /// Latin-1 atoms map each byte to the code point of the same value
+    fn latin1(&mut self, length: usize) -> Result<String, &'static str> {
+        Ok(self.take(length)?.iter().map(|byte| *byte as char).collect())
+    }
+
+    fn term(&mut self) -> Result<Value, &'static str> {
+        if self.depth == MAX_DEPTH {
+            return Err("ETF term is nested too deeply");
+        }
+
+        self.depth += 1;
+        let value = self.value();
+        self.depth -= 1;
+
+        value
+    }
+
+    fn value(&mut self) -> Result<Value, &'static str> {
+        match self.u8()? {
+            SMALL_INTEGER_EXT => Ok(Value::from(self.u8()?)),
+            INTEGER_EXT => Ok(Value::from(self.u32()? as i32)),
+            NEW_FLOAT_EXT => {
+                let float = f64::from_be_bytes(self.take(8)?.try_into().unwrap());
+                Ok(Number::from_f64(float).map_or(Value::Null, Value::Number))
+            },
+            FLOAT_EXT => {
+                let float = self.latin1(31)?;
+                let float = float.trim_end_matches('\0').parse::<f64>().map_err(|_| "Invalid ETF float")?;
+                Ok(Number::from_f64(float).map_or(Value::Null, Value::Number))
+            },
+            ATOM_EXT => {
+                let length = self.u16()? as usize;
+                self.latin1(length).map(atom)
+            },
+            SMALL_ATOM_EXT => {
+                let length = self.u8()? as usize;
+                self.latin1(length).map(atom)
+            },
+            ATOM_UTF8_EXT => {
+                let length = self.u16()? as usize;
+                self.string(length).map(atom)
+            },
+            SMALL_ATOM_UTF8_EXT => {
+                let length = self.u8()? as usize;
+                self.string(length).map(atom)
+            },
+            SMALL_TUPLE_EXT => {
+                let arity = self.u8()? as usize;
+                self.terms(arity).map(Value::Array)
+            },
+            LARGE_TUPLE_EXT => {
+                let arity = self.u32()? as usize;
+                self.terms(arity).map(Value::Array)
+            },
+            NIL_EXT => Ok(Value::Array(Vec::new())),
+            STRING_EXT => {
+                // Lists of small integers are sent as strings of bytes
+                let length = self.u16()? as usize;
+                Ok(Value::Array(self.take(length)?.iter().map(|byte| Value::from(*byte)).collect()))
+            },
+            LIST_EXT => {
+                let length = self.u32()? as usize;
+                let values = self.terms(length)?;
+
+                // Proper lists end with an empty list, which is not part of the values
+                match self.term()? {
+                    Value::Array(tail) if tail.is_empty() => Ok(Value::Array(values)),
+                    _ => Err("Improper ETF lists are not supported"),
+                }
+            },
+            BINARY_EXT => {
+                let length = self.u32()? as usize;
+                self.string(length).map(Value::String)
+            },
+            SMALL_BIG_EXT => {
+                let length = self.u8()? as usize;
+                self.big(length)
+            },
+            LARGE_BIG_EXT => {
+                let length = self.u32()? as usize;
+                self.big(length)
+            },
+            MAP_EXT => {
+                let arity = self.u32()? as usize;
+                let mut map = Map::new();
+
+                for _ in 0..arity {
+                    let key = match self.term()? {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+
+                    map.insert(key, self.term()?);
+                }
+
+                Ok(Value::Object(map))
+            },
+            _ => Err("Unsupported ETF term"),
+        }
+    }
+
+    fn terms(&mut self, count: usize) -> Result<Vec<Value>, &'static str> {
+        // Never trust the count enough to allocate for it up front
+        let mut values = Vec::with_capacity(count.min(self.bytes.len()));
+
+        for _ in 0..count {
+            values.push(self.term()?);
+        }
+
+        Ok(values)
+    }
+
+    fn big(&mut self, length: usize) -> Result<Value, &'static str> {
+        let negative = self.u8()? != 0;
+        let digits = self.take(length)?;
+
+        if length > 8 {
+            return Err("ETF integers larger than 64 bits are not supported");
+        }
+
+        let magnitude = digits
+            .iter()
+            .rev()
+            .fold(0u64, |acc, digit| (acc << 8) | *digit as u64);
+
+        if magnitude >= MAX_SAFE_INTEGER {
+            let sign = if negative { "-" } else { "" };
+            return Ok(Value::String(format!("{}{}", sign, magnitude)));
+        }
+
+        Ok(match negative {
+            true => Value::from(-(magnitude as i64)),
+            false => Value::from(magnitude),
+        })
+    }
+}
+
+/// Atoms are used for booleans and nil, and are otherwise treated as strings
+fn atom(name: String) -> Value {
+    match name.as_str() {
+        "nil" | "null" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(name),
+    }
+}
@@ -0,0 +1,645 @@
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::cache::FieldChange;
+use crate::models::{
+    AuditLogEntry,
+    AutoModerationRule,
+    Channel,
+    Emoji,
+    Guild,
+    GuildMember,
+    GuildScheduledEvent,
+    Integration,
+    Interaction,
+    Message,
+    Presence,
+    Role,
+    Snowflake,
+    StageInstance,
+    Sticker,
+    ThreadMember,
+    UnavailableGuild,
+    User,
+    VoiceState
+};
+use super::types::{ReceiveEvent, ReceiveEventIndexer};
+
+/// A typed event received from the gateway
+/// https://discord.com/developers/docs/topics/gateway-events#receive-events
+///
+/// Every variant of [ReceiveEvent] has a matching variant here. Dispatches
+/// with a name this library does not know about yet, or whose payload could
+/// not be deserialized, are delivered as [Event::Unknown]
+#[derive(Debug, Clone)]
+pub enum Event {
+    Hello(Hello),
+    Ready(Box<Ready>),
+    Resumed,
+    Reconnect,
+    /// Whether the session may be resumed
+    InvalidSession(bool),
+    ApplicationCommandPermissionsUpdate(ApplicationCommandPermissionsUpdate),
+    AutoModerationRuleCreate(AutoModerationRule),
+    AutoModerationRuleUpdate(AutoModerationRule),
+    AutoModerationRuleDelete(AutoModerationRule),
+    AutoModerationActionExecution(AutoModerationActionExecution),
+    ChannelCreate(Box<Channel>),
+    ChannelUpdate(Box<ChannelUpdate>),
+    ChannelDelete(Box<Channel>),
+    ChannelPinsUpdate(ChannelPinsUpdate),
+    ThreadCreate(Box<Channel>),
+    ThreadUpdate(Box<Channel>),
+    ThreadDelete(Box<Channel>),
+    ThreadListSync(ThreadListSync),
+    ThreadMemberUpdate(ThreadMemberUpdate),
+    ThreadMembersUpdate(ThreadMembersUpdate),
+    GuildCreate(Box<Guild>),
+    GuildUpdate(Box<GuildUpdate>),
+    GuildDelete(UnavailableGuild),
+    GuildAuditLogEntryCreate(AuditLogEntry),
+    GuildBanAdd(GuildBan),
+    GuildBanRemove(GuildBan),
+    GuildEmojisUpdate(GuildEmojisUpdate),
+    GuildStickersUpdate(GuildStickersUpdate),
+    GuildIntegrationsUpdate(GuildIntegrationsUpdate),
+    GuildMemberAdd(Box<GuildMemberAdd>),
+    GuildMemberRemove(GuildMemberRemove),
+    GuildMemberUpdate(Box<GuildMemberUpdate>),
+    GuildMembersChunk(GuildMembersChunk),
+    GuildRoleCreate(GuildRole),
+    GuildRoleUpdate(Box<GuildRoleUpdate>),
+    GuildRoleDelete(GuildRoleDelete),
+    GuildScheduledEventCreate(Box<GuildScheduledEvent>),
+    GuildScheduledEventUpdate(Box<GuildScheduledEvent>),
+    GuildScheduledEventDelete(Box<GuildScheduledEvent>),
+    GuildScheduledEventUserAdd(GuildScheduledEventUser),
+    GuildScheduledEventUserRemove(GuildScheduledEventUser),
+    IntegrationCreate(Box<Integration>),
+    IntegrationUpdate(Box<Integration>),
+    IntegrationDelete(IntegrationDelete),
+    InteractionCreate(Box<Interaction>),
+    InviteCreate(Box<InviteCreate>),
+    InviteDelete(InviteDelete),
+    MessageCreate(Box<Message>),
+    MessageUpdate(Box<MessageUpdate>),
+    MessageDelete(Box<MessageDelete>),
+    MessageDeleteBulk(MessageDeleteBulk),
+    MessageReactionAdd(Box<MessageReactionAdd>),
+    MessageReactionRemove(MessageReactionRemove),
+    MessageReactionRemoveAll(MessageReactionRemoveAll),
+    MessageReactionRemoveEmoji(MessageReactionRemoveEmoji),
+    PresenceUpdate(Box<Presence>),
+    StageInstanceCreate(StageInstance),
+    StageInstanceUpdate(StageInstance),
+    StageInstanceDelete(StageInstance),
+    TypingStart(Box<TypingStart>),
+    UserUpdate(Box<UserUpdate>),
+    VoiceStateUpdate(Box<VoiceState>),
+    VoiceServerUpdate(VoiceServerUpdate),
+    WebhooksUpdate(WebhooksUpdate),
+    /// A dispatch which could not be mapped to any of the other variants
+    Unknown {
+        /// The name of the dispatch, such as "MESSAGE_CREATE"
+        name: String,
+        /// The untouched payload of the dispatch
+        raw: Value
+    }
+}
+
+impl Event {
+    /// Deserializes the payload of a [super::GatewayOpCode::Dispatch] event
+    ///
+    /// # Arguments
+    /// * `name` - The name of the dispatch, sent in [super::GatewayEvent::t]
+    /// * `data` - The payload of the dispatch, sent in [super::GatewayEvent::d]
+    ///
+    /// # Example
+    /// ```
+    /// use discord_rs::client::Event;
+    /// use serde_json::json;
+    ///
+    /// let event = Event::from_dispatch("GUILD_ROLE_DELETE", json!({
+    ///     "guild_id": "1",
+    ///     "role_id": "2"
+    /// }));
+    /// assert!(matches!(event, Event::GuildRoleDelete(_)));
+    ///
+    /// let event = Event::from_dispatch("SOMETHING_NEW", json!({}));
+    /// assert!(matches!(event, Event::Unknown { .. }));
+    /// ```
+    pub fn from_dispatch(name: &str, data: Value) -> Self {
+        let event = match ReceiveEventIndexer[name] {
+            ReceiveEvent::Hello => parse(&data).map(Event::Hello),
+            ReceiveEvent::Ready => parse(&data).map(Event::Ready),
+            ReceiveEvent::Resumed => Ok(Event::Resumed),
+            ReceiveEvent::Reconnect => Ok(Event::Reconnect),
+            ReceiveEvent::InvalidSession => Ok(Event::InvalidSession(data.as_bool().unwrap_or(false))),
+            ReceiveEvent::ApplicationCommandPermissionsUpdate => parse(&data).map(Event::ApplicationCommandPermissionsUpdate),
+            ReceiveEvent::AutoModerationRuleCreate => parse(&data).map(Event::AutoModerationRuleCreate),
+            ReceiveEvent::AutoModerationRuleUpdate => parse(&data).map(Event::AutoModerationRuleUpdate),
+            ReceiveEvent::AutoModerationRuleDelete => parse(&data).map(Event::AutoModerationRuleDelete),
+            ReceiveEvent::AutoModerationActionExecution => parse(&data).map(Event::AutoModerationActionExecution),
+            ReceiveEvent::ChannelCreate => parse(&data).map(Event::ChannelCreate),
+            ReceiveEvent::ChannelUpdate => parse(&data).map(Event::ChannelUpdate),
+            ReceiveEvent::ChannelDelete => parse(&data).map(Event::ChannelDelete),
+            ReceiveEvent::ChannelPinsUpdate => parse(&data).map(Event::ChannelPinsUpdate),
+            ReceiveEvent::ThreadCreate => parse(&data).map(Event::ThreadCreate),
+            ReceiveEvent::ThreadUpdate => parse(&data).map(Event::ThreadUpdate),
+            ReceiveEvent::ThreadDelete => parse(&data).map(Event::ThreadDelete),
+            ReceiveEvent::ThreadListSync => parse(&data).map(Event::ThreadListSync),
+            ReceiveEvent::ThreadMemberUpdate => parse(&data).map(Event::ThreadMemberUpdate),
+            ReceiveEvent::ThreadMembersUpdate => parse(&data).map(Event::ThreadMembersUpdate),
+            ReceiveEvent::GuildCreate => parse(&data).map(Event::GuildCreate),
+            ReceiveEvent::GuildUpdate => parse(&data).map(Event::GuildUpdate),
+            ReceiveEvent::GuildDelete => parse(&data).map(Event::GuildDelete),
+            ReceiveEvent::GuildAuditLogEntryCreate => parse(&data).map(Event::GuildAuditLogEntryCreate),
+            ReceiveEvent::GuildBanAdd => parse(&data).map(Event::GuildBanAdd),
+            ReceiveEvent::GuildBanRemove => parse(&data).map(Event::GuildBanRemove),
+            ReceiveEvent::GuildEmojisUpdate => parse(&data).map(Event::GuildEmojisUpdate),
+            ReceiveEvent::GuildStickersUpdate => parse(&data).map(Event::GuildStickersUpdate),
+            ReceiveEvent::GuildIntegrationsUpdate => parse(&data).map(Event::GuildIntegrationsUpdate),
+            ReceiveEvent::GuildMemberAdd => parse(&data).map(Event::GuildMemberAdd),
+            ReceiveEvent::GuildMemberRemove => parse(&data).map(Event::GuildMemberRemove),
+            ReceiveEvent::GuildMemberUpdate => parse(&data).map(Event::GuildMemberUpdate),
+            ReceiveEvent::GuildMembersChunk => parse(&data).map(Event::GuildMembersChunk),
+            ReceiveEvent::GuildRoleCreate => parse(&data).map(Event::GuildRoleCreate),
+            ReceiveEvent::GuildRoleUpdate => parse(&data).map(Event::GuildRoleUpdate),
+            ReceiveEvent::GuildRoleDelete => parse(&data).map(Event::GuildRoleDelete),
+            ReceiveEvent::GuildScheduledEventCreate => parse(&data).map(Event::GuildScheduledEventCreate),
+            ReceiveEvent::GuildScheduledEventUpdate => parse(&data).map(Event::GuildScheduledEventUpdate),
+            ReceiveEvent::GuildScheduledEventDelete => parse(&data).map(Event::GuildScheduledEventDelete),
+            ReceiveEvent::GuildScheduledEventUserAdd => parse(&data).map(Event::GuildScheduledEventUserAdd),
+            ReceiveEvent::GuildScheduledEventUserRemove => parse(&data).map(Event::GuildScheduledEventUserRemove),
+            ReceiveEvent::IntegrationCreate => parse(&data).map(Event::IntegrationCreate),
+            ReceiveEvent::IntegrationUpdate => parse(&data).map(Event::IntegrationUpdate),
+            ReceiveEvent::IntegrationDelete => parse(&data).map(Event::IntegrationDelete),
+            ReceiveEvent::InteractionCreate => parse(&data).map(Event::InteractionCreate),
+            ReceiveEvent::InviteCreate => parse(&data).map(Event::InviteCreate),
+            ReceiveEvent::InviteDelete => parse(&data).map(Event::InviteDelete),
+            ReceiveEvent::MessageCreate => parse(&data).map(Event::MessageCreate),
+            ReceiveEvent::MessageUpdate => parse(&data).map(Event::MessageUpdate),
+            ReceiveEvent::MessageDelete => parse(&data).map(Event::MessageDelete),
+            ReceiveEvent::MessageDeleteBulk => parse(&data).map(Event::MessageDeleteBulk),
+            ReceiveEvent::MessageReactionAdd => parse(&data).map(Event::MessageReactionAdd),
+            ReceiveEvent::MessageReactionRemove => parse(&data).map(Event::MessageReactionRemove),
+            ReceiveEvent::MessageReactionRemoveAll => parse(&data).map(Event::MessageReactionRemoveAll),
+            ReceiveEvent::MessageReactionRemoveEmoji => parse(&data).map(Event::MessageReactionRemoveEmoji),
+            ReceiveEvent::PresenceUpdate => parse(&data).map(Event::PresenceUpdate),
+            ReceiveEvent::StageInstanceCreate => parse(&data).map(Event::StageInstanceCreate),
+            ReceiveEvent::StageInstanceUpdate => parse(&data).map(Event::StageInstanceUpdate),
+            ReceiveEvent::StageInstanceDelete => parse(&data).map(Event::StageInstanceDelete),
+            ReceiveEvent::TypingStart => parse(&data).map(Event::TypingStart),
+            ReceiveEvent::UserUpdate => parse(&data).map(Event::UserUpdate),
+            ReceiveEvent::VoiceStateUpdate => parse(&data).map(Event::VoiceStateUpdate),
+            ReceiveEvent::VoiceServerUpdate => parse(&data).map(Event::VoiceServerUpdate),
+            ReceiveEvent::WebhooksUpdate => parse(&data).map(Event::WebhooksUpdate),
+            ReceiveEvent::Unknown => Err(()),
+        };
+
+        event.unwrap_or_else(|_| Event::Unknown {
+            name: name.to_string(),
+            raw: data
+        })
+    }
+
+    /// The kind of this event, or [ReceiveEvent::Unknown] for [Event::Unknown]
+    pub fn kind(&self) -> ReceiveEvent {
+        match self {
+            Event::Hello(_) => ReceiveEvent::Hello,
+            Event::Ready(_) => ReceiveEvent::Ready,
+            Event::Resumed => ReceiveEvent::Resumed,
+            Event::Reconnect => ReceiveEvent::Reconnect,
+            Event::InvalidSession(_) => ReceiveEvent::InvalidSession,
+            Event::ApplicationCommandPermissionsUpdate(_) => ReceiveEvent::ApplicationCommandPermissionsUpdate,
+            Event::AutoModerationRuleCreate(_) => ReceiveEvent::AutoModerationRuleCreate,
+            Event::AutoModerationRuleUpdate(_) => ReceiveEvent::AutoModerationRuleUpdate,
+            Event::AutoModerationRuleDelete(_) => ReceiveEvent::AutoModerationRuleDelete,
+            Event::AutoModerationActionExecution(_) => ReceiveEvent::AutoModerationActionExecution,
+            Event::ChannelCreate(_) => ReceiveEvent::ChannelCreate,
+            Event::ChannelUpdate(_) => ReceiveEvent::ChannelUpdate,
+            Event::ChannelDelete(_) => ReceiveEvent::ChannelDelete,
+            Event::ChannelPinsUpdate(_) => ReceiveEvent::ChannelPinsUpdate,
+            Event::ThreadCreate(_) => ReceiveEvent::ThreadCreate,
+            Event::ThreadUpdate(_) => ReceiveEvent::ThreadUpdate,
+            Event::ThreadDelete(_) => ReceiveEvent::ThreadDelete,
+            Event::ThreadListSync(_) => ReceiveEvent::ThreadListSync,
+            Event::ThreadMemberUpdate(_) => ReceiveEvent::ThreadMemberUpdate,
+            Event::ThreadMembersUpdate(_) => ReceiveEvent::ThreadMembersUpdate,
+            Event::GuildCreate(_) => ReceiveEvent::GuildCreate,
+            Event::GuildUpdate(_) => ReceiveEvent::GuildUpdate,
+            Event::GuildDelete(_) => ReceiveEvent::GuildDelete,
+            Event::GuildAuditLogEntryCreate(_) => ReceiveEvent::GuildAuditLogEntryCreate,
+            Event::GuildBanAdd(_) => ReceiveEvent::GuildBanAdd,
+            Event::GuildBanRemove(_) => ReceiveEvent::GuildBanRemove,
+            Event::GuildEmojisUpdate(_) => ReceiveEvent::GuildEmojisUpdate,
+            Event::GuildStickersUpdate(_) => ReceiveEvent::GuildStickersUpdate,
+            Event::GuildIntegrationsUpdate(_) => ReceiveEvent::GuildIntegrationsUpdate,
+            Event::GuildMemberAdd(_) => ReceiveEvent::GuildMemberAdd,
+            Event::GuildMemberRemove(_) => ReceiveEvent::GuildMemberRemove,
+            Event::GuildMemberUpdate(_) => ReceiveEvent::GuildMemberUpdate,
+            Event::GuildMembersChunk(_) => ReceiveEvent::GuildMembersChunk,
+            Event::GuildRoleCreate(_) => ReceiveEvent::GuildRoleCreate,
+            Event::GuildRoleUpdate(_) => ReceiveEvent::GuildRoleUpdate,
+            Event::GuildRoleDelete(_) => ReceiveEvent::GuildRoleDelete,
+            Event::GuildScheduledEventCreate(_) => ReceiveEvent::GuildScheduledEventCreate,
+            Event::GuildScheduledEventUpdate(_) => ReceiveEvent::GuildScheduledEventUpdate,
+            Event::GuildScheduledEventDelete(_) => ReceiveEvent::GuildScheduledEventDelete,
+            Event::GuildScheduledEventUserAdd(_) => ReceiveEvent::GuildScheduledEventUserAdd,
+            Event::GuildScheduledEventUserRemove(_) => ReceiveEvent::GuildScheduledEventUserRemove,
+            Event::IntegrationCreate(_) => ReceiveEvent::IntegrationCreate,
+            Event::IntegrationUpdate(_) => ReceiveEvent::IntegrationUpdate,
+            Event::IntegrationDelete(_) => ReceiveEvent::IntegrationDelete,
+            Event::InteractionCreate(_) => ReceiveEvent::InteractionCreate,
+            Event::InviteCreate(_) => ReceiveEvent::InviteCreate,
+            Event::InviteDelete(_) => ReceiveEvent::InviteDelete,
+            Event::MessageCreate(_) => ReceiveEvent::MessageCreate,
+            Event::MessageUpdate(_) => ReceiveEvent::MessageUpdate,
+            Event::MessageDelete(_) => ReceiveEvent::MessageDelete,
+            Event::MessageDeleteBulk(_) => ReceiveEvent::MessageDeleteBulk,
+            Event::MessageReactionAdd(_) => ReceiveEvent::MessageReactionAdd,
+            Event::MessageReactionRemove(_) => ReceiveEvent::MessageReactionRemove,
+            Event::MessageReactionRemoveAll(_) => ReceiveEvent::MessageReactionRemoveAll,
+            Event::MessageReactionRemoveEmoji(_) => ReceiveEvent::MessageReactionRemoveEmoji,
+            Event::PresenceUpdate(_) => ReceiveEvent::PresenceUpdate,
+            Event::StageInstanceCreate(_) => ReceiveEvent::StageInstanceCreate,
+            Event::StageInstanceUpdate(_) => ReceiveEvent::StageInstanceUpdate,
+            Event::StageInstanceDelete(_) => ReceiveEvent::StageInstanceDelete,
+            Event::TypingStart(_) => ReceiveEvent::TypingStart,
+            Event::UserUpdate(_) => ReceiveEvent::UserUpdate,
+            Event::VoiceStateUpdate(_) => ReceiveEvent::VoiceStateUpdate,
+            Event::VoiceServerUpdate(_) => ReceiveEvent::VoiceServerUpdate,
+            Event::WebhooksUpdate(_) => ReceiveEvent::WebhooksUpdate,
+            Event::Unknown { .. } => ReceiveEvent::Unknown,
+        }
+    }
+}
+
+/// Deserializes a dispatch payload without taking ownership of it, so the
+/// payload can still be handed out raw if it does not match the expected shape
+fn parse<T: DeserializeOwned>(data: &Value) -> Result<T, ()> {
+    T::deserialize(data).map_err(|_| ())
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#hello
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Hello {
+    pub heartbeat_interval: u64
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#ready
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Ready {
+    /// The version of the gateway API
+    pub v: u8,
+    pub user: User,
+    pub guilds: Vec<UnavailableGuild>,
+    pub session_id: String,
+    pub resume_gateway_url: String,
+    /// The shard id and the total amount of shards of this session
+    pub shard: Option<[u32; 2]>,
+    /// A partial application object containing its `id` and `flags`
+    pub application: Value
+}
+
+/// https://discord.com/developers/docs/interactions/application-commands#application-command-permissions-object-guild-application-command-permissions-structure
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ApplicationCommandPermissionsUpdate {
+    /// The id of the command, or of the application when the permissions apply to all commands
+    pub id: Snowflake,
+    pub application_id: Snowflake,
+    pub guild_id: Snowflake,
+    pub permissions: Vec<Value>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#auto-moderation-action-execution
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AutoModerationActionExecution {
+    pub guild_id: Snowflake,
+    pub action: Value,
+    pub rule_id: Snowflake,
+    pub rule_trigger_type: u8,
+    pub user_id: Snowflake,
+    pub channel_id: Option<Snowflake>,
+    pub message_id: Option<Snowflake>,
+    pub alert_system_message_id: Option<Snowflake>,
+    pub content: Option<String>,
+    pub matched_keyword: Option<String>,
+    pub matched_content: Option<String>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#channel-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChannelUpdate {
+    #[serde(flatten)]
+    pub channel: Channel,
+    /// The channel before the update, if the cache had it
+    #[serde(skip)]
+    pub cached: Option<Channel>,
+    /// The fields which changed, if the cache had the channel
+    #[serde(skip)]
+    pub changes: Vec<FieldChange>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#channel-pins-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChannelPinsUpdate {
+    pub guild_id: Option<Snowflake>,
+    pub channel_id: Snowflake,
+    pub last_pin_timestamp: Option<String>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#thread-list-sync
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ThreadListSync {
+    pub guild_id: Snowflake,
+    pub channel_ids: Option<Vec<Snowflake>>,
+    pub threads: Vec<Channel>,
+    pub members: Vec<ThreadMember>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#thread-member-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ThreadMemberUpdate {
+    #[serde(flatten)]
+    pub member: ThreadMember,
+    pub guild_id: Snowflake
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#thread-members-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ThreadMembersUpdate {
+    /// The id of the thread
+    pub id: Snowflake,
+    pub guild_id: Snowflake,
+    pub member_count: u32,
+    pub added_members: Option<Vec<ThreadMember>>,
+    pub removed_member_ids: Option<Vec<Snowflake>>
+}
+
+/// Sent when a guild's settings change, with the whole updated guild
+/// https://discord.com/developers/docs/topics/gateway-events#guild-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildUpdate {
+    #[serde(flatten)]
+    pub guild: Guild,
+    /// The guild before the update along with its roles and emojis, if the
+    /// cache had it
+    #[serde(skip)]
+    pub cached: Option<Guild>,
+    /// The fields which changed, if the cache had the guild. Fields only sent
+    /// in GUILD_CREATE, such as `member_count`, are not reported as removed
+    #[serde(skip)]
+    pub changes: Vec<FieldChange>
+}
+
+/// Sent for both GUILD_BAN_ADD and GUILD_BAN_REMOVE
+/// https://discord.com/developers/docs/topics/gateway-events#guild-ban-add
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildBan {
+    pub guild_id: Snowflake,
+    pub user: User
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#guild-emojis-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildEmojisUpdate {
+    pub guild_id: Snowflake,
+    pub emojis: Vec<Emoji>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#guild-stickers-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildStickersUpdate {
+    pub guild_id: Snowflake,
+    pub stickers: Vec<Sticker>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#guild-integrations-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildIntegrationsUpdate {
+    pub guild_id: Snowflake
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#guild-member-add
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildMemberAdd {
+    #[serde(flatten)]
+    pub member: GuildMember,
+    pub guild_id: Snowflake
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#guild-member-remove
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildMemberRemove {
+    pub guild_id: Snowflake,
+    pub user: User
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#guild-member-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildMemberUpdate {
+    #[serde(flatten)]
+    pub member: GuildMember,
+    pub guild_id: Snowflake,
+    /// The member before the update, if the cache had it
+    #[serde(skip)]
+    pub cached: Option<GuildMember>,
+    /// The fields which changed, if the cache had the member
+    #[serde(skip)]
+    pub changes: Vec<FieldChange>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#guild-members-chunk
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildMembersChunk {
+    pub guild_id: Snowflake,
+    pub members: Vec<GuildMember>,
+    pub chunk_index: u32,
+    pub chunk_count: u32,
+    /// Ids which were requested but did not match any member
+    pub not_found: Option<Vec<Value>>,
+    pub presences: Option<Vec<Presence>>,
+    pub nonce: Option<String>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#guild-role-create
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildRole {
+    pub guild_id: Snowflake,
+    pub role: Role
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#guild-role-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildRoleUpdate {
+    pub guild_id: Snowflake,
+    pub role: Role,
+    /// The role before the update, if the cache had it
+    #[serde(skip)]
+    pub cached: Option<Role>,
+    /// The fields which changed, if the cache had the role
+    #[serde(skip)]
+    pub changes: Vec<FieldChange>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#guild-role-delete
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildRoleDelete {
+    pub guild_id: Snowflake,
+    pub role_id: Snowflake
+}
+
+/// Sent for both GUILD_SCHEDULED_EVENT_USER_ADD and GUILD_SCHEDULED_EVENT_USER_REMOVE
+/// https://discord.com/developers/docs/topics/gateway-events#guild-scheduled-event-user-add
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildScheduledEventUser {
+    pub guild_scheduled_event_id: Snowflake,
+    pub user_id: Snowflake,
+    pub guild_id: Snowflake
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#integration-delete
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IntegrationDelete {
+    pub id: Snowflake,
+    pub guild_id: Snowflake,
+    pub application_id: Option<Snowflake>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#invite-create
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InviteCreate {
+    pub channel_id: Snowflake,
+    pub code: String,
+    pub created_at: String,
+    pub guild_id: Option<Snowflake>,
+    pub inviter: Option<User>,
+    pub max_age: u32,
+    pub max_uses: u32,
+    pub target_type: Option<u8>,
+    pub target_user: Option<User>,
+    pub target_application: Option<Value>,
+    pub temporary: bool,
+    pub uses: u32
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#invite-delete
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InviteDelete {
+    pub channel_id: Snowflake,
+    pub guild_id: Option<Snowflake>,
+    pub code: String
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#message-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageUpdate {
+    #[serde(flatten)]
+    pub message: Message,
+    /// The message before it was edited, if the message cache had it
+    #[serde(skip)]
+    pub cached: Option<Message>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#message-delete
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageDelete {
+    pub id: Snowflake,
+    pub channel_id: Snowflake,
+    pub guild_id: Option<Snowflake>,
+    /// The deleted message, if the message cache had it
+    #[serde(skip)]
+    pub cached: Option<Message>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#message-delete-bulk
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageDeleteBulk {
+    pub ids: Vec<Snowflake>,
+    pub channel_id: Snowflake,
+    pub guild_id: Option<Snowflake>,
+    /// The deleted messages which the message cache had
+    #[serde(skip)]
+    pub cached: Vec<Message>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#message-reaction-add
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageReactionAdd {
+    pub user_id: Snowflake,
+    pub channel_id: Snowflake,
+    pub message_id: Snowflake,
+    pub guild_id: Option<Snowflake>,
+    pub member: Option<GuildMember>,
+    pub emoji: Emoji,
+    pub message_author_id: Option<Snowflake>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#message-reaction-remove
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageReactionRemove {
+    pub user_id: Snowflake,
+    pub channel_id: Snowflake,
+    pub message_id: Snowflake,
+    pub guild_id: Option<Snowflake>,
+    pub emoji: Emoji
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#message-reaction-remove-all
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageReactionRemoveAll {
+    pub channel_id: Snowflake,
+    pub message_id: Snowflake,
+    pub guild_id: Option<Snowflake>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#message-reaction-remove-emoji
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageReactionRemoveEmoji {
+    pub channel_id: Snowflake,
+    pub guild_id: Option<Snowflake>,
+    pub message_id: Snowflake,
+    pub emoji: Emoji
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#typing-start
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TypingStart {
+    pub channel_id: Snowflake,
+    pub guild_id: Option<Snowflake>,
+    pub user_id: Snowflake,
+    /// Unix time in seconds of when the user started typing
+    pub timestamp: u64,
+    pub member: Option<GuildMember>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#user-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UserUpdate {
+    #[serde(flatten)]
+    pub user: User,
+    /// The user of the bot before the update, if READY was received
+    #[serde(skip)]
+    pub cached: Option<User>,
+    /// The fields which changed, if READY was received
+    #[serde(skip)]
+    pub changes: Vec<FieldChange>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#voice-server-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoiceServerUpdate {
+    pub token: String,
+    pub guild_id: Snowflake,
+    /// `None` when the voice server is unavailable
+    pub endpoint: Option<String>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#webhooks-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WebhooksUpdate {
+    pub guild_id: Snowflake,
+    pub channel_id: Snowflake
+}
@@ -0,0 +1,606 @@
+use rand::Rng;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc::Receiver;
+use tokio::time::Instant;
+
+use crate::cache::Cache;
+use crate::util::log_message;
+use super::codec::GatewayCodec;
+use super::compression::{Compression, ZlibInflater};
+use super::errors::GatewayError;
+use super::events::{Event, Hello};
+use super::handler::{self, EventHandler};
+use super::intents::Intents;
+use super::members::MemberRequests;
+use super::recording::{Direction, Recorder};
+use super::shutdown::{HandlerTasks, ShutdownHandle};
+use super::stats::ConnectionStats;
+use super::ratelimit::{CommandLimiter, SessionStartLimiter, MAX_PAYLOAD_SIZE};
+use super::stream::EventProducer;
+use super::transport::{Frame, GatewaySocket, GatewayTransport};
+use super::types::{GatewayEvent, GatewayOpCode, Session, UpdatePresence};
+
+/// A socket which has completed the Hello handshake and has already sent
+/// either an Identify or a Resume
+pub(crate) struct Connection {
+    socket: Box<dyn GatewaySocket>,
+    /// Shared by every frame of the connection when it is compressed
+    inflater: Option<ZlibInflater>,
+    heartbeat_interval: u64,
+}
+
+/// What should happen after a connection to the gateway ends
+enum Disconnect {
+    /// Reconnect and resume the previous session
+    Resume,
+    /// Drop the previous session and reconnect with a fresh Identify
+    Reidentify,
+    /// Stop reconnecting altogether
+    Fatal(GatewayError),
+    /// A shutdown was requested through the [ShutdownHandle]
+    Shutdown,
+}
+
+/// Drives a single gateway session, reconnecting and resuming it
+/// whenever Discord asks us to or the connection drops
+pub(crate) struct Gateway {
+    pub token: String,
+    pub intents: Intents,
+    /// The URL used to open new sessions
+    pub url: String,
+    pub api_version: u8,
+    pub compression: Compression,
+    pub codec: Arc<dyn GatewayCodec>,
+    /// Opens the connections to the gateway
+    pub transport: Arc<dyn GatewayTransport>,
+    /// Shared by every shard of the client
+    pub cache: Cache,
+    /// The id of this shard and the total amount of shards, if sharding
+    pub shard: Option<[u32; 2]>,
+    pub session: Arc<Mutex<Option<Session>>>,
+    pub handler: Option<Arc<dyn EventHandler>>,
+    /// Ends the [super::EventStream] once every gateway sharing it is dropped
+    pub events: Option<EventProducer>,
+    /// Shared by every shard of the bot so identifies respect the session start limit
+    pub limiter: Arc<SessionStartLimiter>,
+    /// Commands sent through a [super::ShardHandle], kept across reconnects
+    pub commands: Mutex<Receiver<GatewayEvent>>,
+    /// The last presence sent, so it survives a fresh Identify
+    pub presence: std::sync::Mutex<Option<UpdatePresence>>,
+    /// Requests for guild members which are waiting for their chunks
+    pub members: Arc<MemberRequests>,
+    /// Stops this gateway alone, and is also shut down with the whole client
+    pub shutdown: ShutdownHandle,
+    /// Handler calls which have not returned yet
+    pub tasks: Arc<HandlerTasks>,
+    /// Shared with the [super::ShardHandle] so the health of the connection can be inspected
+    pub stats: Arc<ConnectionStats>,
+    /// Writes every payload received and sent, if recording
+    pub recorder: Option<Arc<Recorder>>,
+}
+
+impl Gateway {
+    /// Opens a websocket to the gateway, waits for [GatewayOpCode::Hello] and
+    /// then sends a [GatewayOpCode::Resume] if a previous session is known, or
+    /// a [GatewayOpCode::Identify] otherwise
+    pub async fn connect(&self) -> Result<Connection, GatewayError> {
+        let session = self.session.lock().await.clone();
+
+        // Resuming does not count towards the session start limit
+        if session.is_none() {
+            self.limiter.acquire(self.shard.map_or(0, |[id, _]| id)).await;
+        }
+
+        let url = match &session {
+            Some(session) => session.resume_gateway_url.as_str(),
+            None => self.url.as_str(),
+        };
+
+        let url = format!(
+            "{}/?v={}&encoding={}{}",
+            url.trim_end_matches('/'),
+            self.api_version,
+            self.codec.encoding(),
+            self.compression.query()
+        );
+        let mut socket = self.transport.connect(&url).await?;
+        let mut inflater = match self.compression {
+            Compression::ZlibStream => Some(ZlibInflater::new()),
+            Compression::None => None,
+        };
+
+        // The first thing Discord sends is always a hello event which tells us how often to heartbeat
+        let heartbeat_interval = match read(socket.as_mut(), &mut inflater).await {
+            Incoming::Payload(payload) => {
+                let event = self.codec.decode(&payload)?;
+                self.record(Direction::Inbound, &event);
+
+                // Ensure this is the right operation code
+                if GatewayOpCode::from_code(event.op) != Some(GatewayOpCode::Hello) {
+                    return Err(GatewayError::Protocol("Received first operation that was not Hello"));
+                }
+
+                event.d
+                    .and_then(|data| data["heartbeat_interval"].as_u64())
+                    .ok_or(GatewayError::Protocol("Received hello without a heartbeat interval"))?
+            },
+            Incoming::Close(Some(code)) => return Err(GatewayError::from_close_code(code)),
+            Incoming::Corrupted(error) => return Err(error),
+            Incoming::Close(None) | Incoming::Lost => return Err(GatewayError::Connection("Failed to handshake with gateway")),
+        };
+
+        self.emit(Event::Hello(Hello { heartbeat_interval })).await?;
+
+        let payload = match &session {
+            Some(session) => self.resume(session),
+            None => self.identify(),
+        };
+
+        self.send(socket.as_mut(), &payload)
+            .await
+            .map_err(|_| GatewayError::Connection("Failed to identify with gateway"))?;
+
+        self.stats.connected();
+        Ok(Connection { socket, inflater, heartbeat_interval })
+    }
+
+    /// Keeps the gateway session alive until Discord closes it with an
+    /// unrecoverable code, or until a shutdown is requested
+    pub async fn run(self, mut connection: Connection) -> Result<(), GatewayError> {
+        let result = 'session: loop {
+            match self.drive(connection).await {
+                Disconnect::Resume => {
+                    log_message("warning", "Gateway connection lost. Attempting to resume...");
+                },
+                Disconnect::Reidentify => {
+                    log_message("warning", "Gateway session is no longer valid. Identifying again...");
+                    *self.session.lock().await = None;
+                    // Chunks requested by the previous session will never arrive
+                    self.members.clear();
+
+                    // Discord asks for a random wait between 1 and 5 seconds before identifying again
+                    let delay = rand::thread_rng().gen_range(1000..=5000);
+                    if self.sleep(Duration::from_millis(delay)).await.is_err() {
+                        break Ok(());
+                    }
+                },
+                Disconnect::Fatal(error) => break Err(error),
+                Disconnect::Shutdown => break Ok(()),
+            }
+
+            let mut attempts = 0;
+            connection = loop {
+                let connection = tokio::select! {
+                    connection = self.connect() => connection,
+                    _ = self.shutdown.requested() => break 'session Ok(()),
+                };
+
+                match connection {
+                    Ok(connection) => {
+                        self.stats.reconnected();
+                        break connection;
+                    },
+                    Err(error) if error.is_fatal() => break 'session Err(error),
+                    Err(error) => {
+                        // Back off exponentially up to a minute between failed attempts
+                        attempts += 1;
+                        log_message("error", &error.to_string());
+
+                        if self.sleep(Duration::from_secs(2u64.pow(attempts.min(6)))).await.is_err() {
+                            break 'session Ok(());
+                        }
+                    }
+                }
+            };
+        };
+
+        if let Err(error) = &result {
+            log_message("error", &error.to_string());
+
+            if *error == GatewayError::DisallowedIntents {
+                log_message("error", &format!(
+                    "Make sure {:?} are enabled for the bot in the developer portal",
+                    self.intents & Intents::privileged()
+                ));
+            }
+        }
+
+        self.stop().await;
+        result
+    }
+
+    /// Waits for running handlers to finish and for the recording to be written.
+    /// The [super::EventStream] ends once the gateway is dropped afterwards,
+    /// unless other shards still share it
+    async fn stop(&self) {
+        self.tasks.flush().await;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.flush().await;
+        }
+    }
+
+    fn record(&self, direction: Direction, event: &GatewayEvent) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(self.shard.map_or(0, |[id, _]| id), direction, event);
+        }
+    }
+
+    /// Sleeps unless a shutdown is requested first, in which case it returns an error
+    async fn sleep(&self, duration: Duration) -> Result<(), ()> {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => Ok(()),
+            _ = self.shutdown.requested() => Err(()),
+        }
+    }
+
+    /// Reads events from a connection until it ends, returning how to proceed
+    ///
+    /// Heartbeats are sent from the same loop so they always carry the last
+    /// sequence number seen on this connection. If Discord does not acknowledge
+    /// a heartbeat before the next one is due the connection is considered
+    /// zombied and is dropped to be resumed
+    ///
+    /// Commands from [super::ShardHandle]s are written from this loop as well,
+    /// as long as the connection's [CommandLimiter] allows it
+    async fn drive(&self, connection: Connection) -> Disconnect {
+        let Connection { mut socket, mut inflater, heartbeat_interval } = connection;
+        let interval = Duration::from_millis(heartbeat_interval);
+        let mut limiter = CommandLimiter::new(heartbeat_interval);
+        let mut commands = self.commands.lock().await;
+        let mut commands_open = true;
+
+        // We need to apply a jitter before our first heartbeat
+        let jitter = rand::thread_rng().gen_range(0..=heartbeat_interval);
+        let mut next_heartbeat = Instant::now() + Duration::from_millis(jitter);
+        let mut awaiting_ack = false;
+
+        let disconnect = loop {
+            let can_send = limiter.available();
+
+            tokio::select! {
+                _ = tokio::time::sleep_until(next_heartbeat) => {
+                    if awaiting_ack {
+                        log_message("warning", "Heartbeat was not acknowledged. Reconnecting...");
+                        break Disconnect::Resume;
+                    }
+
+                    limiter.heartbeat();
+                    if self.heartbeat(socket.as_mut()).await.is_err() {
+                        break Disconnect::Resume;
+                    }
+
+                    awaiting_ack = true;
+                    next_heartbeat = Instant::now() + interval;
+                },
+                command = commands.recv(), if can_send && commands_open => {
+                    let Some(command) = command else {
+                        // Every handle was dropped, so no more commands can arrive
+                        commands_open = false;
+                        continue;
+                    };
+
+                    limiter.command();
+                    match self.send(socket.as_mut(), &command).await {
+                        Ok(()) => self.remember_presence(&command),
+                        Err(SendError::TooLarge) => log_message("error", "Dropped a gateway command larger than 4096 bytes"),
+                        Err(SendError::Invalid(error)) => log_message("error", &format!("Dropped a gateway command. {}", error)),
+                        Err(SendError::Closed) => break Disconnect::Resume,
+                    }
+                },
+                _ = self.shutdown.requested() => break Disconnect::Shutdown,
+                // Wake up once the rate limit resets so queued commands get sent
+                _ = tokio::time::sleep_until(limiter.reset_at()), if !can_send => {},
+                incoming = read(socket.as_mut(), &mut inflater) => {
+                    let payload = match incoming {
+                        Incoming::Payload(payload) => payload,
+                        Incoming::Close(Some(code)) => break on_close(code),
+                        // The socket errored or was dropped without a close frame
+                        Incoming::Close(None) | Incoming::Lost => break Disconnect::Resume,
+                        // A new connection starts a new zlib stream, so the session can still be resumed
+                        Incoming::Corrupted(error) => {
+                            log_message("error", &error.to_string());
+                            break Disconnect::Resume;
+                        },
+                    };
+
+                    let event = match self.codec.decode(&payload) {
+                        Ok(event) => event,
+                        Err(error) => {
+                            log_message("warning", &error.to_string());
+                            continue;
+                        }
+                    };
+
+                    self.record(Direction::Inbound, &event);
+
+                    let Some(op) = GatewayOpCode::from_code(event.op) else {
+                        log_message("warning", &format!("Received unknown gateway opcode {}", event.op));
+                        continue;
+                    };
+
+                    match op {
+                        GatewayOpCode::Dispatch => {
+                            if let Err(reason) = self.on_dispatch(event).await {
+                                break Disconnect::Fatal(reason);
+                            }
+                        },
+                        // Discord may request a heartbeat at any time, which must be answered right away
+                        GatewayOpCode::Heartbeat => {
+                            limiter.heartbeat();
+                            let sent = self.heartbeat(socket.as_mut()).await;
+                            if sent.is_err() {
+                                break Disconnect::Resume;
+                            }
+                        },
+                        GatewayOpCode::HeartbeatAcknowledge => {
+                            self.stats.heartbeat_acknowledged();
+                            awaiting_ack = false;
+                        },
+                        GatewayOpCode::Reconnect => {
+                            break match self.emit(Event::Reconnect).await {
+                                Ok(()) => Disconnect::Resume,
+                                Err(reason) => Disconnect::Fatal(reason),
+                            };
+                        },
+                        GatewayOpCode::InvalidSession => {
+                            // The inner data tells us whether the session may be resumed
+                            let resumable = event.d
+                                .and_then(|d| d.as_bool())
+                                .unwrap_or(false);
+
+                            if let Err(reason) = self.emit(Event::InvalidSession(resumable)).await {
+                                break Disconnect::Fatal(reason);
+                            }
+
+                            break if resumable { Disconnect::Resume } else { Disconnect::Reidentify };
+                        },
+                        _ => {}
+                    }
+                }
+            }
+        };
+
+        // Closing with 1000 or 1001 would invalidate the session, so only do
+        // that when the session is being thrown away anyway
+        let code = match disconnect {
+            Disconnect::Resume => 4000,
+            _ => 1000,
+        };
+
+        socket.close(code).await;
+        self.stats.disconnected();
+
+        disconnect
+    }
+
+    /// Sends a [GatewayOpCode::Heartbeat] carrying the last sequence number received
+    async fn heartbeat(&self, socket: &mut dyn GatewaySocket) -> Result<(), &'static str> {
+        let sequence = self.session
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|session| session.sequence);
+
+        let heartbeat = GatewayEvent {
+            op: GatewayOpCode::Heartbeat as usize,
+            d: Some(sequence.map_or(Value::Null, |sequence| Value::Number(sequence.into()))),
+            s: None,
+            t: None,
+        };
+
+        self.stats.heartbeat_sent();
+        self.send(socket, &heartbeat)
+            .await
+            .map_err(|_| {
+                log_message("error", "Failed to send heartbeat");
+                "Failed to send heartbeat"
+            })
+    }
+
+    /// Receives regular events from the socket
+    async fn on_dispatch(&self, event: GatewayEvent) -> Result<(), GatewayError> {
+        let event_sequence = event.s;
+        let Some(name) = event.t else {
+            log_message("warning", "Received dispatch without an event type");
+            return Ok(());
+        };
+
+        // Some dispatches such as RESUMED are sent with null data
+        let data = event.d.unwrap_or(Value::Null);
+
+        let mut event = Event::from_dispatch(&name, data);
+        let mut session = self.session.lock().await;
+
+        if let Event::Ready(ready) = &event {
+            *session = Some(Session {
+                id: ready.session_id.to_owned(),
+                resume_gateway_url: ready.resume_gateway_url.to_owned(),
+                sequence: None,
+            });
+        }
+
+        // Remember the last sequence number so a resume can replay missed events
+        if let (Some(session), Some(sequence)) = (session.as_mut(), event_sequence) {
+            session.sequence = Some(sequence);
+        }
+
+        if let Some(sequence) = event_sequence {
+            self.stats.sequence(sequence);
+        }
+
+        drop(session);
+
+        // Handlers should see the cache as it is after the event
+        self.cache.update(&mut event);
+
+        if let Event::GuildMembersChunk(chunk) = &event {
+            self.members.on_chunk(chunk);
+        }
+
+        self.emit(event).await
+    }
+
+    /// Hands an event to the [EventHandler] on its own task so the reader is never
+    /// blocked, and to the [super::EventStream] if one was created
+    async fn emit(&self, event: Event) -> Result<(), GatewayError> {
+        let shard_id = self.shard.map_or(0, |[id, _]| id);
+        self.stats.event(event.kind());
+
+        match (&self.handler, &self.events) {
+            (Some(handler), Some(events)) => {
+                self.tasks.spawn(handler::dispatch(Arc::clone(handler), event.clone()));
+                events.push((shard_id, event)).await
+            },
+            (Some(handler), None) => {
+                self.tasks.spawn(handler::dispatch(Arc::clone(handler), event));
+                Ok(())
+            },
+            (None, Some(events)) => events.push((shard_id, event)).await,
+            (None, None) => Ok(()),
+        }
+    }
+
+    fn identify(&self) -> GatewayEvent {
+        let mut data = json!({
+            "token": self.token,
+            "intents": self.intents.bits(),
+            "properties": {
+                "os": std::env::consts::OS,
+                "browser": "discord-rs",
+                "device": "discord_rs"
+            }
+        });
+
+        if let Some(shard) = self.shard {
+            data["shard"] = json!(shard);
+        }
+
+        if let Some(presence) = self.presence.lock().unwrap().as_ref() {
+            data["presence"] = json!(presence);
+        }
+
+        GatewayEvent {
+            op: GatewayOpCode::Identify as usize,
+            d: Some(data),
+            s: None,
+            t: None,
+        }
+    }
+
+    /// Encodes and writes a payload, refusing payloads larger than [MAX_PAYLOAD_SIZE]
+    async fn send(&self, socket: &mut dyn GatewaySocket, event: &GatewayEvent) -> Result<(), SendError> {
+        let payload = self.codec.encode(event).map_err(SendError::Invalid)?;
+
+        if payload.len() > MAX_PAYLOAD_SIZE {
+            return Err(SendError::TooLarge);
+        }
+
+        let frame = match self.codec.is_binary() {
+            true => Frame::Binary(payload),
+            false => Frame::Text(String::from_utf8(payload).map_err(|_| {
+                SendError::Invalid(GatewayError::Protocol("Text payloads must be valid UTF-8"))
+            })?),
+        };
+
+        socket.send(frame)
+            .await
+            .map_err(|_| SendError::Closed)?;
+
+        self.record(Direction::Outbound, event);
+        Ok(())
+    }
+
+    fn remember_presence(&self, command: &GatewayEvent) {
+        if command.op != GatewayOpCode::PresenceUpdate as usize {
+            return;
+        }
+
+        if let Some(presence) = command.d.clone().and_then(|d| serde_json::from_value(d).ok()) {
+            *self.presence.lock().unwrap() = Some(presence);
+        }
+    }
+
+    fn resume(&self, session: &Session) -> GatewayEvent {
+        GatewayEvent {
+            op: GatewayOpCode::Resume as usize,
+            d: Some(json!({
+                "token": self.token,
+                "session_id": session.id,
+                "seq": session.sequence
+            })),
+            s: None,
+            t: None,
+        }
+    }
+}
+
+impl Drop for Gateway {
+    fn drop(&mut self) {
+        // Handles may outlive the connection, so wake up anyone still waiting for chunks
+        self.members.clear();
+    }
+}
+
+/// What was read from the socket
+enum Incoming {
+    /// A complete payload, inflated if the connection is compressed
+    Payload(Vec<u8>),
+    /// The gateway closed the connection, with a close code if it sent one
+    Close(Option<u16>),
+    /// The socket errored or ended without a close frame
+    Lost,
+    /// The compressed stream could not be inflated
+    Corrupted(GatewayError),
+}
+
+/// Reads frames until a complete payload or a close arrives
+///
+/// Compressed payloads may span several frames, which are kept in the
+/// inflater, so this is safe to cancel between frames
+async fn read(socket: &mut dyn GatewaySocket, inflater: &mut Option<ZlibInflater>) -> Incoming {
+    loop {
+        match socket.receive().await {
+            Some(Frame::Text(text_message)) => return Incoming::Payload(text_message.into_bytes()),
+            Some(Frame::Binary(frame)) => match inflater.as_mut().map(|inflater| inflater.push(&frame)) {
+                Some(Ok(Some(payload))) => return Incoming::Payload(payload),
+                // The rest of the payload is in the next frames
+                Some(Ok(None)) => {},
+                Some(Err(error)) => return Incoming::Corrupted(error),
+                // Without compression, binary frames carry whole payloads of a binary codec
+                None => return Incoming::Payload(frame),
+            },
+            Some(Frame::Close(code)) => return Incoming::Close(code),
+            None => return Incoming::Lost,
+        }
+    }
+}
+
+/// Why a payload could not be written to the gateway
+enum SendError {
+    /// The payload is larger than the gateway accepts
+    TooLarge,
+    /// The payload could not be encoded
+    Invalid(GatewayError),
+    /// The socket can no longer be written to
+    Closed,
+}
+
+/// Maps a close code sent by Discord to how the client should react
+/// https://discord.com/developers/docs/topics/opcodes-and-status-codes#gateway-gateway-close-event-codes
+fn on_close(code: u16) -> Disconnect {
+    let error = GatewayError::from_close_code(code);
+
+    if error.is_resumable() {
+        log_message("warning", &error.to_string());
+        Disconnect::Resume
+    } else if error.is_reconnectable() {
+        log_message("warning", &error.to_string());
+        Disconnect::Reidentify
+    } else {
+        Disconnect::Fatal(error)
+    }
+}
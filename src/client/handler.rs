@@ -0,0 +1,227 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::models::{
+    AuditLogEntry,
+    AutoModerationRule,
+    Channel,
+    Guild,
+    GuildScheduledEvent,
+    Integration,
+    Interaction,
+    Message,
+    Presence,
+    StageInstance,
+    UnavailableGuild,
+    VoiceState
+};
+use super::events::{
+    ApplicationCommandPermissionsUpdate,
+    AutoModerationActionExecution,
+    ChannelPinsUpdate,
+    ChannelUpdate,
+    GuildBan,
+    GuildEmojisUpdate,
+    GuildIntegrationsUpdate,
+    GuildMemberAdd,
+    GuildMemberRemove,
+    GuildMemberUpdate,
+    GuildMembersChunk,
+    GuildRole,
+    GuildRoleDelete,
+    GuildRoleUpdate,
+    GuildScheduledEventUser,
+    GuildStickersUpdate,
+    GuildUpdate,
+    Hello,
+    IntegrationDelete,
+    InviteCreate,
+    InviteDelete,
+    MessageDelete,
+    MessageUpdate,
+    MessageDeleteBulk,
+    MessageReactionAdd,
+    MessageReactionRemove,
+    MessageReactionRemoveAll,
+    MessageReactionRemoveEmoji,
+    Ready,
+    ThreadListSync,
+    ThreadMemberUpdate,
+    ThreadMembersUpdate,
+    TypingStart,
+    UserUpdate,
+    VoiceServerUpdate,
+    WebhooksUpdate,
+    Event
+};
+
+/// Receives the events sent by the gateway
+///
+/// Every method has a default implementation which does nothing, so only the
+/// events your bot cares about need to be implemented. Each event is handled
+/// in its own task, so a slow handler never holds up the connection
+///
+/// # Example
+/// ```no_run
+/// use discord_rs::client::{async_trait, ClientBuilder, EventHandler, GatewayIntentBits};
+/// use discord_rs::models::Message;
+///
+/// struct Handler;
+///
+/// #[async_trait]
+/// impl EventHandler for Handler {
+///     async fn message_create(&self, message: Message) {
+///         println!("{:?}", message.content);
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut client = ClientBuilder::new("YOUR_TOKEN", &[GatewayIntentBits::GuildMessages])
+///         .with_event_handler(Handler)
+///         .build();
+///
+///     client.run().await.expect("Gateway connection failed");
+/// }
+/// ```
+#[async_trait]
+pub trait EventHandler: Send + Sync {
+    async fn hello(&self, _hello: Hello) {}
+    async fn ready(&self, _ready: Ready) {}
+    async fn resumed(&self) {}
+    async fn reconnect(&self) {}
+    async fn invalid_session(&self, _resumable: bool) {}
+    async fn application_command_permissions_update(&self, _event: ApplicationCommandPermissionsUpdate) {}
+    async fn auto_moderation_rule_create(&self, _rule: AutoModerationRule) {}
+    async fn auto_moderation_rule_update(&self, _rule: AutoModerationRule) {}
+    async fn auto_moderation_rule_delete(&self, _rule: AutoModerationRule) {}
+    async fn auto_moderation_action_execution(&self, _event: AutoModerationActionExecution) {}
+    async fn channel_create(&self, _channel: Channel) {}
+    async fn channel_update(&self, _event: ChannelUpdate) {}
+    async fn channel_delete(&self, _channel: Channel) {}
+    async fn channel_pins_update(&self, _event: ChannelPinsUpdate) {}
+    async fn thread_create(&self, _channel: Channel) {}
+    async fn thread_update(&self, _channel: Channel) {}
+    async fn thread_delete(&self, _channel: Channel) {}
+    async fn thread_list_sync(&self, _event: ThreadListSync) {}
+    async fn thread_member_update(&self, _event: ThreadMemberUpdate) {}
+    async fn thread_members_update(&self, _event: ThreadMembersUpdate) {}
+    async fn guild_create(&self, _guild: Guild) {}
+    async fn guild_update(&self, _event: GuildUpdate) {}
+    async fn guild_delete(&self, _guild: UnavailableGuild) {}
+    async fn guild_audit_log_entry_create(&self, _entry: AuditLogEntry) {}
+    async fn guild_ban_add(&self, _ban: GuildBan) {}
+    async fn guild_ban_remove(&self, _ban: GuildBan) {}
+    async fn guild_emojis_update(&self, _event: GuildEmojisUpdate) {}
+    async fn guild_stickers_update(&self, _event: GuildStickersUpdate) {}
+    async fn guild_integrations_update(&self, _event: GuildIntegrationsUpdate) {}
+    async fn guild_member_add(&self, _event: GuildMemberAdd) {}
+    async fn guild_member_remove(&self, _event: GuildMemberRemove) {}
+    async fn guild_member_update(&self, _event: GuildMemberUpdate) {}
+    async fn guild_members_chunk(&self, _event: GuildMembersChunk) {}
+    async fn guild_role_create(&self, _role: GuildRole) {}
+    async fn guild_role_update(&self, _event: GuildRoleUpdate) {}
+    async fn guild_role_delete(&self, _event: GuildRoleDelete) {}
+    async fn guild_scheduled_event_create(&self, _scheduled_event: GuildScheduledEvent) {}
+    async fn guild_scheduled_event_update(&self, _scheduled_event: GuildScheduledEvent) {}
+    async fn guild_scheduled_event_delete(&self, _scheduled_event: GuildScheduledEvent) {}
+    async fn guild_scheduled_event_user_add(&self, _subscription: GuildScheduledEventUser) {}
+    async fn guild_scheduled_event_user_remove(&self, _subscription: GuildScheduledEventUser) {}
+    async fn integration_create(&self, _integration: Integration) {}
+    async fn integration_update(&self, _integration: Integration) {}
+    async fn integration_delete(&self, _event: IntegrationDelete) {}
+    async fn interaction_create(&self, _interaction: Interaction) {}
+    async fn invite_create(&self, _event: InviteCreate) {}
+    async fn invite_delete(&self, _event: InviteDelete) {}
+    async fn message_create(&self, _message: Message) {}
+    async fn message_update(&self, _event: MessageUpdate) {}
+    async fn message_delete(&self, _event: MessageDelete) {}
+    async fn message_delete_bulk(&self, _event: MessageDeleteBulk) {}
+    async fn message_reaction_add(&self, _event: MessageReactionAdd) {}
+    async fn message_reaction_remove(&self, _event: MessageReactionRemove) {}
+    async fn message_reaction_remove_all(&self, _event: MessageReactionRemoveAll) {}
+    async fn message_reaction_remove_emoji(&self, _event: MessageReactionRemoveEmoji) {}
+    async fn presence_update(&self, _presence: Presence) {}
+    async fn stage_instance_create(&self, _stage_instance: StageInstance) {}
+    async fn stage_instance_update(&self, _stage_instance: StageInstance) {}
+    async fn stage_instance_delete(&self, _stage_instance: StageInstance) {}
+    async fn typing_start(&self, _event: TypingStart) {}
+    async fn user_update(&self, _event: UserUpdate) {}
+    async fn voice_state_update(&self, _voice_state: VoiceState) {}
+    async fn voice_server_update(&self, _event: VoiceServerUpdate) {}
+    async fn webhooks_update(&self, _event: WebhooksUpdate) {}
+    /// Called for dispatches which could not be mapped to a typed event
+    async fn unknown(&self, _name: String, _raw: Value) {}
+}
+
+/// Calls the method of the handler matching the event
+pub(crate) async fn dispatch(handler: Arc<dyn EventHandler>, event: Event) {
+    match event {
+        Event::Hello(hello) => handler.hello(hello).await,
+        Event::Ready(ready) => handler.ready(*ready).await,
+        Event::Resumed => handler.resumed().await,
+        Event::Reconnect => handler.reconnect().await,
+        Event::InvalidSession(resumable) => handler.invalid_session(resumable).await,
+        Event::ApplicationCommandPermissionsUpdate(event) => handler.application_command_permissions_update(event).await,
+        Event::AutoModerationRuleCreate(rule) => handler.auto_moderation_rule_create(rule).await,
+        Event::AutoModerationRuleUpdate(rule) => handler.auto_moderation_rule_update(rule).await,
+        Event::AutoModerationRuleDelete(rule) => handler.auto_moderation_rule_delete(rule).await,
+        Event::AutoModerationActionExecution(event) => handler.auto_moderation_action_execution(event).await,
+        Event::ChannelCreate(channel) => handler.channel_create(*channel).await,
+        Event::ChannelUpdate(event) => handler.channel_update(*event).await,
+        Event::ChannelDelete(channel) => handler.channel_delete(*channel).await,
+        Event::ChannelPinsUpdate(event) => handler.channel_pins_update(event).await,
+        Event::ThreadCreate(channel) => handler.thread_create(*channel).await,
+        Event::ThreadUpdate(channel) => handler.thread_update(*channel).await,
+        Event::ThreadDelete(channel) => handler.thread_delete(*channel).await,
+        Event::ThreadListSync(event) => handler.thread_list_sync(event).await,
+        Event::ThreadMemberUpdate(event) => handler.thread_member_update(event).await,
+        Event::ThreadMembersUpdate(event) => handler.thread_members_update(event).await,
+        Event::GuildCreate(guild) => handler.guild_create(*guild).await,
+        Event::GuildUpdate(event) => handler.guild_update(*event).await,
+        Event::GuildDelete(guild) => handler.guild_delete(guild).await,
+        Event::GuildAuditLogEntryCreate(entry) => handler.guild_audit_log_entry_create(entry).await,
+        Event::GuildBanAdd(ban) => handler.guild_ban_add(ban).await,
+        Event::GuildBanRemove(ban) => handler.guild_ban_remove(ban).await,
+        Event::GuildEmojisUpdate(event) => handler.guild_emojis_update(event).await,
+        Event::GuildStickersUpdate(event) => handler.guild_stickers_update(event).await,
+        Event::GuildIntegrationsUpdate(event) => handler.guild_integrations_update(event).await,
+        Event::GuildMemberAdd(event) => handler.guild_member_add(*event).await,
+        Event::GuildMemberRemove(event) => handler.guild_member_remove(event).await,
+        Event::GuildMemberUpdate(event) => handler.guild_member_update(*event).await,
+        Event::GuildMembersChunk(event) => handler.guild_members_chunk(event).await,
+        Event::GuildRoleCreate(role) => handler.guild_role_create(role).await,
+        Event::GuildRoleUpdate(event) => handler.guild_role_update(*event).await,
+        Event::GuildRoleDelete(event) => handler.guild_role_delete(event).await,
+        Event::GuildScheduledEventCreate(scheduled_event) => handler.guild_scheduled_event_create(*scheduled_event).await,
+        Event::GuildScheduledEventUpdate(scheduled_event) => handler.guild_scheduled_event_update(*scheduled_event).await,
+        Event::GuildScheduledEventDelete(scheduled_event) => handler.guild_scheduled_event_delete(*scheduled_event).await,
+        Event::GuildScheduledEventUserAdd(subscription) => handler.guild_scheduled_event_user_add(subscription).await,
+        Event::GuildScheduledEventUserRemove(subscription) => handler.guild_scheduled_event_user_remove(subscription).await,
+        Event::IntegrationCreate(integration) => handler.integration_create(*integration).await,
+        Event::IntegrationUpdate(integration) => handler.integration_update(*integration).await,
+        Event::IntegrationDelete(event) => handler.integration_delete(event).await,
+        Event::InteractionCreate(interaction) => handler.interaction_create(*interaction).await,
+        Event::InviteCreate(event) => handler.invite_create(*event).await,
+        Event::InviteDelete(event) => handler.invite_delete(event).await,
+        Event::MessageCreate(message) => handler.message_create(*message).await,
+        Event::MessageUpdate(event) => handler.message_update(*event).await,
+        Event::MessageDelete(event) => handler.message_delete(*event).await,
+        Event::MessageDeleteBulk(event) => handler.message_delete_bulk(event).await,
+        Event::MessageReactionAdd(event) => handler.message_reaction_add(*event).await,
+        Event::MessageReactionRemove(event) => handler.message_reaction_remove(event).await,
+        Event::MessageReactionRemoveAll(event) => handler.message_reaction_remove_all(event).await,
+        Event::MessageReactionRemoveEmoji(event) => handler.message_reaction_remove_emoji(event).await,
+        Event::PresenceUpdate(presence) => handler.presence_update(*presence).await,
+        Event::StageInstanceCreate(stage_instance) => handler.stage_instance_create(stage_instance).await,
+        Event::StageInstanceUpdate(stage_instance) => handler.stage_instance_update(stage_instance).await,
+        Event::StageInstanceDelete(stage_instance) => handler.stage_instance_delete(stage_instance).await,
+        Event::TypingStart(event) => handler.typing_start(*event).await,
+        Event::UserUpdate(event) => handler.user_update(*event).await,
+        Event::VoiceStateUpdate(voice_state) => handler.voice_state_update(*voice_state).await,
+        Event::VoiceServerUpdate(event) => handler.voice_server_update(event).await,
+        Event::WebhooksUpdate(event) => handler.webhooks_update(event).await,
+        Event::Unknown { name, raw } => handler.unknown(name, raw).await,
+    }
+}
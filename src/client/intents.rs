@@ -0,0 +1,173 @@
+use bitflags::bitflags;
+
+use super::types::{GatewayIntentBits, ReceiveEvent};
+
+bitflags! {
+    /// The set of intents sent when identifying, which determines what events your bot will receive
+    /// https://discord.com/developers/docs/topics/gateway#gateway-intents
+    ///
+    /// # Example
+    /// ```
+    /// use discord_rs::client::{GatewayIntentBits, Intents};
+    ///
+    /// let intents = Intents::GUILDS | Intents::GUILD_MESSAGES | Intents::MESSAGE_CONTENT;
+    ///
+    /// assert_eq!(intents.bits(), 0b1000_0010_0000_0001);
+    /// assert_eq!(intents, Intents::from(&[
+    ///     GatewayIntentBits::Guilds,
+    ///     GatewayIntentBits::GuildMessages,
+    ///     GatewayIntentBits::MessageContent,
+    /// ]));
+    /// assert_eq!(intents & Intents::privileged(), Intents::MESSAGE_CONTENT);
+    /// ```
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub struct Intents: u32 {
+        const GUILDS = 1 << 0;
+        /// Privileged
+        const GUILD_MEMBERS = 1 << 1;
+        const GUILD_MODERATION = 1 << 2;
+        const GUILD_EMOJIS_AND_STICKERS = 1 << 3;
+        const GUILD_INTEGRATIONS = 1 << 4;
+        const GUILD_WEBHOOKS = 1 << 5;
+        const GUILD_INVITES = 1 << 6;
+        const GUILD_VOICE_STATES = 1 << 7;
+        /// Privileged
+        const GUILD_PRESENCES = 1 << 8;
+        const GUILD_MESSAGES = 1 << 9;
+        const GUILD_MESSAGE_REACTIONS = 1 << 10;
+        const GUILD_MESSAGE_TYPING = 1 << 11;
+        const DIRECT_MESSAGES = 1 << 12;
+        const DIRECT_MESSAGE_REACTIONS = 1 << 13;
+        const DIRECT_MESSAGE_TYPING = 1 << 14;
+        /// Privileged
+        const MESSAGE_CONTENT = 1 << 15;
+        const GUILD_SCHEDULED_EVENTS = 1 << 16;
+        // 17 through 19 are not used
+        const AUTO_MODERATION_CONFIGURATION = 1 << 20;
+        const AUTO_MODERATION_EXECUTION = 1 << 21;
+    }
+}
+
+impl Intents {
+    /// The intents which must be enabled for the bot in the developer portal
+    /// before they can be sent, or Discord closes the connection with 4014
+    pub const fn privileged() -> Self {
+        Self::GUILD_MEMBERS
+            .union(Self::GUILD_PRESENCES)
+            .union(Self::MESSAGE_CONTENT)
+    }
+
+    /// Every intent which can be sent without approval
+    pub const fn non_privileged() -> Self {
+        Self::all().difference(Self::privileged())
+    }
+
+    /// Whether any of these intents is privileged
+    pub const fn is_privileged(&self) -> bool {
+        self.intersects(Self::privileged())
+    }
+
+    /// Whether an event can be received with these intents
+    pub fn receives(&self, event: ReceiveEvent) -> bool {
+        let required = event.required_intents();
+        required.is_empty() || self.intersects(required)
+    }
+}
+
+impl From<GatewayIntentBits> for Intents {
+    fn from(intent: GatewayIntentBits) -> Self {
+        Self::from_bits_retain(1 << intent as u32)
+    }
+}
+
+impl From<&[GatewayIntentBits]> for Intents {
+    fn from(intents: &[GatewayIntentBits]) -> Self {
+        intents.iter().copied().map(Intents::from).collect()
+    }
+}
+
+impl<const N: usize> From<&[GatewayIntentBits; N]> for Intents {
+    fn from(intents: &[GatewayIntentBits; N]) -> Self {
+        Self::from(intents.as_slice())
+    }
+}
+
+impl From<Vec<GatewayIntentBits>> for Intents {
+    fn from(intents: Vec<GatewayIntentBits>) -> Self {
+        Self::from(intents.as_slice())
+    }
+}
+
+impl GatewayIntentBits {
+    /// Whether this intent must be enabled in the developer portal before it can be sent
+    pub fn is_privileged(&self) -> bool {
+        Intents::from(*self).is_privileged()
+    }
+}
+
+impl ReceiveEvent {
+    /// The intents which deliver this event. Having any one of them is enough,
+    /// and an empty set means the event is always sent
+    ///
+    /// Message, reaction and typing events are sent for guilds and direct
+    /// messages by two different intents
+    pub fn required_intents(&self) -> Intents {
+        match self {
+            ReceiveEvent::GuildCreate
+            | ReceiveEvent::GuildUpdate
+            | ReceiveEvent::GuildDelete
+            | ReceiveEvent::GuildRoleCreate
+            | ReceiveEvent::GuildRoleUpdate
+            | ReceiveEvent::GuildRoleDelete
+            | ReceiveEvent::ChannelCreate
+            | ReceiveEvent::ChannelUpdate
+            | ReceiveEvent::ChannelDelete
+            | ReceiveEvent::ThreadCreate
+            | ReceiveEvent::ThreadUpdate
+            | ReceiveEvent::ThreadDelete
+            | ReceiveEvent::ThreadListSync
+            | ReceiveEvent::ThreadMemberUpdate
+            | ReceiveEvent::ThreadMembersUpdate
+            | ReceiveEvent::StageInstanceCreate
+            | ReceiveEvent::StageInstanceUpdate
+            | ReceiveEvent::StageInstanceDelete => Intents::GUILDS,
+            ReceiveEvent::ChannelPinsUpdate => Intents::GUILDS | Intents::DIRECT_MESSAGES,
+            ReceiveEvent::GuildMemberAdd
+            | ReceiveEvent::GuildMemberUpdate
+            | ReceiveEvent::GuildMemberRemove => Intents::GUILD_MEMBERS,
+            ReceiveEvent::GuildAuditLogEntryCreate
+            | ReceiveEvent::GuildBanAdd
+            | ReceiveEvent::GuildBanRemove => Intents::GUILD_MODERATION,
+            ReceiveEvent::GuildEmojisUpdate
+            | ReceiveEvent::GuildStickersUpdate => Intents::GUILD_EMOJIS_AND_STICKERS,
+            ReceiveEvent::GuildIntegrationsUpdate
+            | ReceiveEvent::IntegrationCreate
+            | ReceiveEvent::IntegrationUpdate
+            | ReceiveEvent::IntegrationDelete => Intents::GUILD_INTEGRATIONS,
+            ReceiveEvent::WebhooksUpdate => Intents::GUILD_WEBHOOKS,
+            ReceiveEvent::InviteCreate
+            | ReceiveEvent::InviteDelete => Intents::GUILD_INVITES,
+            ReceiveEvent::VoiceStateUpdate => Intents::GUILD_VOICE_STATES,
+            ReceiveEvent::PresenceUpdate => Intents::GUILD_PRESENCES,
+            ReceiveEvent::MessageCreate
+            | ReceiveEvent::MessageUpdate
+            | ReceiveEvent::MessageDelete => Intents::GUILD_MESSAGES | Intents::DIRECT_MESSAGES,
+            ReceiveEvent::MessageDeleteBulk => Intents::GUILD_MESSAGES,
+            ReceiveEvent::MessageReactionAdd
+            | ReceiveEvent::MessageReactionRemove
+            | ReceiveEvent::MessageReactionRemoveAll
+            | ReceiveEvent::MessageReactionRemoveEmoji => Intents::GUILD_MESSAGE_REACTIONS | Intents::DIRECT_MESSAGE_REACTIONS,
+            ReceiveEvent::TypingStart => Intents::GUILD_MESSAGE_TYPING | Intents::DIRECT_MESSAGE_TYPING,
+            ReceiveEvent::GuildScheduledEventCreate
+            | ReceiveEvent::GuildScheduledEventUpdate
+            | ReceiveEvent::GuildScheduledEventDelete
+            | ReceiveEvent::GuildScheduledEventUserAdd
+            | ReceiveEvent::GuildScheduledEventUserRemove => Intents::GUILD_SCHEDULED_EVENTS,
+            ReceiveEvent::AutoModerationRuleCreate
+            | ReceiveEvent::AutoModerationRuleUpdate
+            | ReceiveEvent::AutoModerationRuleDelete => Intents::AUTO_MODERATION_CONFIGURATION,
+            ReceiveEvent::AutoModerationActionExecution => Intents::AUTO_MODERATION_EXECUTION,
+            _ => Intents::empty(),
+        }
+    }
+}
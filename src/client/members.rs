@@ -0,0 +1,97 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::oneshot;
+
+use crate::models::{GuildMember, Presence, Snowflake};
+use super::events::GuildMembersChunk;
+
+/// Which members of a guild a [super::GatewayOpCode::RequestGuildMembers] should return
+/// https://discord.com/developers/docs/topics/gateway-events#request-guild-members
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemberQuery {
+    /// Members whose username starts with the string. An empty string
+    /// together with a limit of 0 returns every member of the guild
+    Query(String),
+    /// Members with these ids, up to 100 at a time
+    UserIds(Vec<Snowflake>)
+}
+
+/// Every member returned for a single request, collected across all of its
+/// GUILD_MEMBERS_CHUNK events
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuildMembers {
+    pub guild_id: Snowflake,
+    pub members: Vec<GuildMember>,
+    /// Only filled when presences were requested
+    pub presences: Vec<Presence>,
+    /// Ids which were requested but did not match any member
+    pub not_found: Vec<Snowflake>
+}
+
+/// The requests of a single gateway connection which are still waiting for chunks
+#[derive(Debug, Default)]
+pub(crate) struct MemberRequests {
+    nonce: AtomicU64,
+    pending: Mutex<HashMap<String, Pending>>
+}
+
+#[derive(Debug)]
+struct Pending {
+    members: GuildMembers,
+    sender: oneshot::Sender<GuildMembers>
+}
+
+impl MemberRequests {
+    /// Starts waiting for the chunks of a new request, returning the nonce to send it with
+    pub fn register(&self, guild_id: &str) -> (String, oneshot::Receiver<GuildMembers>) {
+        let nonce = self.nonce.fetch_add(1, Ordering::Relaxed).to_string();
+        let (sender, receiver) = oneshot::channel();
+
+        let members = GuildMembers {
+            guild_id: guild_id.to_string(),
+            members: Vec::new(),
+            presences: Vec::new(),
+            not_found: Vec::new()
+        };
+
+        self.pending.lock().unwrap().insert(nonce.clone(), Pending { members, sender });
+        (nonce, receiver)
+    }
+
+    /// Stops waiting for a request which could not be sent
+    pub fn cancel(&self, nonce: &str) {
+        self.pending.lock().unwrap().remove(nonce);
+    }
+
+    /// Adds a chunk to the request it answers, completing the request on its last chunk
+    pub fn on_chunk(&self, chunk: &GuildMembersChunk) {
+        let Some(nonce) = &chunk.nonce else {
+            return;
+        };
+
+        let mut pending = self.pending.lock().unwrap();
+        let Some(request) = pending.get_mut(nonce) else {
+            return;
+        };
+
+        request.members.members.extend(chunk.members.iter().cloned());
+        request.members.presences.extend(chunk.presences.iter().flatten().cloned());
+        request.members.not_found.extend(chunk.not_found.iter().flatten().map(|id| match id {
+            Value::String(id) => id.to_owned(),
+            id => id.to_string(),
+        }));
+
+        if chunk.chunk_index + 1 >= chunk.chunk_count {
+            if let Some(request) = pending.remove(nonce) {
+                let _ = request.sender.send(request.members);
+            }
+        }
+    }
+
+    /// Fails every pending request, as their chunks will never arrive
+    pub fn clear(&self) {
+        self.pending.lock().unwrap().clear();
+    }
+}
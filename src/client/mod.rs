@@ -1,75 +1,366 @@
-#[allow(dead_code, unused_imports)]
-use futures_util::sink::SinkExt;
-use futures_util::stream::{StreamExt, SplitSink};
-use rand::Rng;
 use reqwest::{Client as ReqwestClient};
-use serde_json::json;
-use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio_tungstenite::{connect_async, WebSocketStream, MaybeTlsStream};
-use tokio_tungstenite::tungstenite::Message;
-use tokio::net::TcpStream;
 use tokio::sync::{Mutex, mpsc};
-use serde_json::Value;
-//use crate::util::log_message;
+
+use crate::cache::{Cache, CacheConfig};
+use crate::models::{Activity, Status};
+use crate::util::{log_message, API_URL};
+
+mod codec;
+pub use codec::{EtfCodec, GatewayCodec, JsonCodec};
+
+mod etf;
+
+mod compression;
+pub use compression::{Compression, ZlibInflater};
+
+pub mod errors;
+pub use errors::GatewayError;
+
+mod gateway;
+use gateway::Gateway;
+
+pub mod events;
+pub use events::Event;
+
+mod intents;
+pub use intents::Intents;
+
+mod handler;
+pub use handler::EventHandler;
+pub use async_trait::async_trait;
+
+mod stream;
+pub use stream::{Backpressure, EventStream};
+use stream::{EventProducer, EventQueue};
+
+mod shard;
+pub use shard::{ShardHandle, ShardManager};
+
+mod members;
+pub use members::{GuildMembers, MemberQuery};
+use members::MemberRequests;
+
+mod recording;
+pub use recording::{Direction, RecordedEvent, Recorder, Recording, RecordingTransport};
+
+mod shutdown;
+pub use shutdown::ShutdownHandle;
+use shutdown::HandlerTasks;
+
+mod stats;
+pub use stats::GatewayStats;
+use stats::ConnectionStats;
+
+mod transport;
+pub use transport::{ChannelListener, ChannelSocket, ChannelTransport, Frame, GatewaySocket, GatewayTransport, TungsteniteTransport};
+
+mod ratelimit;
+use ratelimit::SessionStartLimiter;
 
 pub mod types;
 pub use types::{
     Client,
+    ClientBuilder,
+    GatewayBot,
     GatewayEvent,
     GatewayIntentBits,
     GatewayOpCode,
-    GatewayOpCodeIndexer,
     WebsocketConnection,
     ReceiveEvent,
-    ReceiveEventIndexer
+    ReceiveEventIndexer,
+    Session,
+    SessionStartLimitObject,
+    UpdatePresence
 };
+#[allow(deprecated)]
+pub use types::GatewayOpCodeIndexer;
+
+/// The default URL used to open new gateway sessions
+pub const GATEWAY_URL: &str = "wss://gateway.discord.gg";
+/// The default version of the gateway and REST APIs
+pub const API_VERSION: u8 = 10;
+/// How many commands may be waiting to be sent through a gateway connection
+const COMMAND_QUEUE_SIZE: usize = 128;
+
+impl ClientBuilder {
+    /// Creates a builder for a [Client] which connects to Discord
+    ///
+    /// # Arguments
+    /// * `token` - A string slice for the bot's token
+    /// * `intents` - The [Intents], or an array of [GatewayIntentBits], which determines what events your bot will receive
+    ///
+    /// # Example
+    /// ```
+    /// use discord_rs::client::{ClientBuilder, GatewayIntentBits};
+    ///
+    /// let client = ClientBuilder::new("YOUR_TOKEN", &[GatewayIntentBits::Guilds])
+    ///     .with_gateway_url("ws://127.0.0.1:8080")
+    ///     .with_api_url("http://127.0.0.1:8081/api")
+    ///     .build();
+    ///
+    /// assert_eq!(client.api_endpoint("/gateway/bot"), "http://127.0.0.1:8081/api/v10/gateway/bot");
+    /// ```
+    pub fn new(token: &str, intents: impl Into<Intents>) -> Self {
+        Self {
+            intents: intents.into(),
+            token: token.to_string(),
+            gateway_url: GATEWAY_URL.to_string(),
+            api_url: API_URL.to_string(),
+            api_version: API_VERSION,
+            compression: Compression::None,
+            codec: Arc::new(JsonCodec),
+            transport: Arc::new(TungsteniteTransport),
+            cache: Cache::new(),
+            handler: None,
+            presence: None,
+            required_events: Vec::new(),
+            recorder: None
+        }
+    }
+
+    /// Sets the URL new gateway sessions connect to. Useful for local stand-in
+    /// servers or proxies
+    pub fn with_gateway_url(&mut self, url: &str) -> &mut Self {
+        self.gateway_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the base URL of the REST API, without a version. Defaults to https://discord.com/api
+    pub fn with_api_url(&mut self, url: &str) -> &mut Self {
+        self.api_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the version of the gateway and REST APIs to use
+    pub fn with_api_version(&mut self, version: u8) -> &mut Self {
+        self.api_version = version;
+        self
+    }
+
+    /// Sets how the gateway compresses the payloads it sends. Compression
+    /// greatly reduces bandwidth at the cost of some CPU time
+    pub fn with_compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets how payloads are encoded on the gateway. Defaults to [JsonCodec]
+    ///
+    /// # Example
+    /// ```
+    /// use discord_rs::client::{ClientBuilder, EtfCodec, GatewayIntentBits};
+    ///
+    /// let client = ClientBuilder::new("YOUR_TOKEN", &[GatewayIntentBits::Guilds])
+    ///     .with_codec(EtfCodec)
+    ///     .build();
+    ///
+    /// assert_eq!(client.codec.encoding(), "etf");
+    /// ```
+    pub fn with_codec<C: GatewayCodec + 'static>(&mut self, codec: C) -> &mut Self {
+        self.codec = Arc::new(codec);
+        self
+    }
+
+    /// Sets how connections to the gateway are opened. Defaults to
+    /// [TungsteniteTransport], see [GatewayTransport]
+    pub fn with_transport<T: GatewayTransport + 'static>(&mut self, transport: T) -> &mut Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// Sets the [Cache] kept up to date by the client. Handlers which read the
+    /// cache should be given a clone of it, since they are created before the
+    /// [Client]. A new cache is used by default
+    pub fn with_cache(&mut self, cache: Cache) -> &mut Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Uses a new [Cache] which only keeps some resources or is only updated
+    /// by some events. Handlers can be given a clone of [Client::cache]
+    ///
+    /// # Example
+    /// ```
+    /// use discord_rs::cache::{CacheConfig, CacheResources};
+    /// use discord_rs::client::{ClientBuilder, GatewayIntentBits};
+    ///
+    /// let client = ClientBuilder::new("YOUR_TOKEN", &[GatewayIntentBits::Guilds])
+    ///     .with_cache_config(CacheConfig {
+    ///         resources: CacheResources::GUILDS | CacheResources::CHANNELS | CacheResources::ROLES,
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// assert_eq!(client.cache.stats().total().count, 0);
+    /// ```
+    pub fn with_cache_config(&mut self, config: CacheConfig) -> &mut Self {
+        self.cache = Cache::with_config(config);
+        self
+    }
+
+    /// Sets the [EventHandler] which receives the events sent by the gateway
+    pub fn with_event_handler<H: EventHandler + 'static>(&mut self, handler: H) -> &mut Self {
+        self.handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Sets the presence the bot appears with as soon as it connects
+    ///
+    /// # Arguments
+    /// * `status` - The status of the bot, such as [Status::Online]
+    /// * `activities` - What the bot is doing, such as [Activity::playing]
+    /// * `afk` - Whether the bot is away from keyboard
+    ///
+    /// # Example
+    /// ```
+    /// use discord_rs::client::{ClientBuilder, GatewayIntentBits};
+    /// use discord_rs::models::{Activity, Status};
+    ///
+    /// let client = ClientBuilder::new("YOUR_TOKEN", &[GatewayIntentBits::Guilds])
+    ///     .with_presence(Status::Dnd, &[Activity::watching("the logs")], false)
+    ///     .build();
+    ///
+    /// assert_eq!(client.presence.unwrap().activities[0].name, "the logs");
+    /// ```
+    pub fn with_presence(&mut self, status: Status, activities: &[Activity], afk: bool) -> &mut Self {
+        self.presence = Some(UpdatePresence::new(status, activities, afk));
+        self
+    }
+
+    /// Sets the events the bot relies on. Connecting fails right away if the
+    /// intents do not allow receiving all of them, instead of the events
+    /// silently never arriving
+    ///
+    /// # Example
+    /// ```
+    /// use discord_rs::client::{ClientBuilder, Intents, ReceiveEvent};
+    ///
+    /// let client = ClientBuilder::new("YOUR_TOKEN", Intents::GUILDS)
+    ///     .with_required_events(&[ReceiveEvent::GuildCreate, ReceiveEvent::MessageCreate])
+    ///     .build();
+    ///
+    /// assert!(client.check_intents().is_err());
+    /// ```
+    pub fn with_required_events(&mut self, events: &[ReceiveEvent]) -> &mut Self {
+        self.required_events = events.to_vec();
+        self
+    }
+
+    /// Records every payload received from and sent to the gateway, see [Recorder]
+    pub fn with_recorder(&mut self, recorder: Recorder) -> &mut Self {
+        self.recorder = Some(Arc::new(recorder));
+        self
+    }
+
+    /// Creates the configured [Client]
+    pub fn build(&self) -> Client {
+        Client {
+            intents: self.intents,
+            token: self.token.to_string(),
+            cache: self.cache.clone(),
+            session: Arc::new(Mutex::new(None)),
+            gateway_url: self.gateway_url.to_string(),
+            api_url: self.api_url.to_string(),
+            api_version: self.api_version,
+            compression: self.compression,
+            codec: Arc::clone(&self.codec),
+            transport: Arc::clone(&self.transport),
+            handler: self.handler.clone(),
+            presence: self.presence.clone(),
+            required_events: self.required_events.clone(),
+            recorder: self.recorder.clone(),
+            shutdown: ShutdownHandle::new(),
+            ws: WebsocketConnection {
+                shard: None,
+                task: None,
+                events: None,
+                client: ReqwestClient::new()
+            },
+        }
+    }
+}
 
 impl Client {
     /// Creates a new Discord Bot Client
     /// 
     /// # Arguments
     /// * `token` - A string slice for the bot's token provided by https://discord.com/developers/applications/{YourApplicationId}/bot
-    /// * `intents` - An array of [GatewayIntentBits]. This represents a bitfield
-    /// which determines what events your bot will receive. [GatewayIntentBits] directly
-    /// maps to https://discord.com/developers/docs/topics/gateway#gateway-intents
+    /// * `intents` - The [Intents], or an array of [GatewayIntentBits]. This represents a bitfield
+    ///   which determines what events your bot will receive. [Intents] directly
+    ///   maps to https://discord.com/developers/docs/topics/gateway#gateway-intents
     /// 
     /// # Example
-    /// ```
+    /// ```no_run
     /// use discord_rs::client::{Client, GatewayIntentBits};
     /// 
     /// #[tokio::main]
     /// async fn main() {
-    ///     let mut client = Client::new(&[
+    ///     let token = "YOUR_TOKEN";
+    ///     let mut client = Client::new(token, &[
     ///         GatewayIntentBits::Guilds,
     ///         GatewayIntentBits::GuildMessages,
     ///         GatewayIntentBits::DirectMessages,
     ///     ]);
     /// 
-    ///     let token = "YOUR_TOKEN";
-    ///     client.login(token)
+    ///     client.login()
     ///         .await
     ///         .expect("Failed to login");
     /// }
     /// ```
-    pub fn new(token: &str, intents: &[GatewayIntentBits]) -> Self {
-        let bits = intents
+    pub fn new(token: &str, intents: impl Into<Intents>) -> Self {
+        ClientBuilder::new(token, intents).build()
+    }
+
+    /// Makes sure every event set through [ClientBuilder::with_required_events]
+    /// can be received with the intents of the client
+    ///
+    /// # Errors
+    /// * If any required event is not delivered by the intents
+    pub fn check_intents(&self) -> Result<(), GatewayError> {
+        let missing: Vec<_> = self.required_events
             .iter()
-            .fold(0, |acc, intent| {
-                acc | (1 << *intent as usize)
-            });
-        
-        Self {
-            intents: (bits, intents.to_vec()),
-            token: token.to_string(),
-            cache: HashMap::new(),
-            ws: WebsocketConnection {
-                keepalive: None,
-                receiver: None,
-                client: ReqwestClient::new()
-            },
+            .filter(|event| !self.intents.receives(**event))
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(());
         }
+
+        for event in missing {
+            log_message("error", &format!(
+                "{:?} requires one of the intents {:?}",
+                event,
+                event.required_intents()
+            ));
+        }
+
+        Err(GatewayError::Configuration("The intents do not allow receiving every required event"))
+    }
+
+    /// Returns the full URL of a versioned REST API endpoint
+    ///
+    /// # Arguments
+    /// * `path` - The path of the endpoint, such as `/gateway/bot`
+    pub fn api_endpoint(&self, path: &str) -> String {
+        format!("{}/v{}{}", self.api_url, self.api_version, path)
+    }
+
+    /// Creates a [EventStream] which yields every event sent by the gateway.
+    /// This is an alternative to registering an [EventHandler], although both
+    /// can be used at the same time
+    ///
+    /// Only one stream exists per client, so calling this again replaces the
+    /// previous stream. It must be called before [Client::login]
+    ///
+    /// # Arguments
+    /// * `capacity` - How many events may be buffered before `backpressure` applies
+    /// * `backpressure` - What to do when the stream is not consumed fast enough
+    pub fn events(&mut self, capacity: usize, backpressure: Backpressure) -> EventStream {
+        let queue = Arc::new(EventQueue::new(capacity, backpressure));
+        self.ws.events = Some(Arc::clone(&queue));
+        EventStream::new(queue)
     }
 
     /// This function should only be called once per process
@@ -78,288 +369,231 @@ impl Client {
     /// which includes the bot's token. This initiates the websocket
     /// connection from discord to the user and kickstarts all websocket
     /// events essentially making your bot 'online'
-    /// 
-    /// # Panics
-    /// * If a connection to wss://gateway.discord.gg cannot be established
-    /// * If an initial identify message cannot be sent through the websocket
-    /// * If incoming data through the socket cannot be deserialized
+    ///
+    /// Before identifying, the session start limit of the bot is fetched from
+    /// GET /gateway/bot so the client waits instead of exceeding it
+    ///
+    /// Once connected, the session is kept alive in the background. If the
+    /// connection drops or Discord asks us to reconnect, the session is
+    /// resumed through [GatewayOpCode::Resume], falling back to a fresh
+    /// [GatewayOpCode::Identify] when it can no longer be resumed
     /// 
     /// # Errors
-    /// * Can error if contained websocket handler events fail
-    pub async fn login(&mut self) -> Result<(), &'static str> {
-        // Establish a connection to the Discord event socket
-        let (socket, _) = connect_async("wss://gateway.discord.gg/?v=10&encoding=json")
-            .await
-            .expect("Failed to connect to gateway");
-
-        let (mut writer, mut reader) = socket.split();
-
-        // Structure the initial identify request
-        let identify = GatewayEvent {
-            op: GatewayOpCode::Identify as usize,
-            d: Some(json!({
-                "token": self.token.to_owned(),
-                "intents": self.intents.0.to_owned(),
-                "properties": {
-                    "os": std::env::consts::OS,
-                    "browser": "discord-rs",
-                    "device": "discord_rs"
-                }
-            })),
-            s: None,
-            t: None,
+    /// * If the intents do not allow receiving a required event, see [Client::check_intents]
+    /// * If a connection to the gateway cannot be established
+    /// * If the initial handshake with the gateway fails
+    pub async fn login(&mut self) -> Result<(), GatewayError> {
+        self.check_intents()?;
+
+        let limiter = Arc::new(self.session_start_limiter().await);
+        let (gateway, handle) = self.gateway(None, Arc::clone(&self.session), limiter);
+
+        // Connect once up front so handshake failures are reported to the caller
+        let connection = gateway.connect().await?;
+        self.ws.task = Some(tokio::spawn(gateway.run(connection)));
+        self.ws.shard = Some(handle);
+
+        Ok(())
+    }
+
+    /// Logs in if needed, then drives the gateway connection until it is
+    /// stopped through a [ShutdownHandle] or fails for good
+    ///
+    /// Unlike [Client::login], this only returns once the bot goes offline,
+    /// which makes it the natural last call of a `main` function
+    ///
+    /// # Errors
+    /// * If logging in fails, see [Client::login]
+    /// * If Discord closes the connection with a code that cannot be recovered from
+    pub async fn run(&mut self) -> Result<(), GatewayError> {
+        if self.ws.shard.is_none() {
+            self.login().await?;
+        }
+
+        let Some(task) = self.ws.task.take() else {
+            return Ok(());
         };
 
-        // Serialize the identify request into JSON
-        let identify = serde_json::to_string(&identify).unwrap();
+        task.await
+            .map_err(|_| GatewayError::Connection("The gateway connection stopped unexpectedly"))?
+    }
 
-        // Send the initial identify payload
-        writer.send(Message::text(identify))
-            .await
-            .expect("Failed to identify with gateway");
-        
-        // Create an Arc-wrapped Mutex to share the writer across threads
-        let writer_mutex = Arc::new(Mutex::new(writer));
-        // Create a clone of the writer_mutex for the heartbeat loop
-        let heartbeat_writer = Arc::clone(&writer_mutex);
-        // Create the keep alive channel
-        let (_tx, mut rx) = mpsc::channel(10);
-        
-        // Assign the keep alive channel
-        //self.ws.keepalive = Some(tx);
-        //self.ws.receiver = Some(rx);
-
-        // Catch the initial hello event to kickstart the heartbeating process on the background
-        if let Some(Ok(maybe_hello)) = reader.next().await {
-            match maybe_hello {
-                Message::Text(text_message) => {
-                    let event = serde_json::from_str::<GatewayEvent>(&text_message)
-                        .expect("Failed to deserialize incoming data JSON at handshake");
-
-                    // Ensure this is the right operation code
-                    let operation_code = GatewayOpCodeIndexer[event.op];
-                    if operation_code != GatewayOpCode::Hello {
-                        return Err("Received first operation that was not Hello");
-                    }
-
-                    // Check that there is data within the d object of the gateway event
-                    // Ideally never happens unless there is a change on the gateway api
-                    if event.d.is_none() { return Err("Received JSON at hanshake") }
-                    let data = event.d.unwrap();
-                    let heartbeat_interval = data["heartbeat_interval"].as_u64().unwrap();
-
-                    // We need to apply a jitter before our first heartbeat
-                    let mut rng = rand::thread_rng();
-                    let jitter_duration = Duration::from_millis(rng.gen_range(0..=1000));
-
-                    // Wait for the jitter
-                    let _ = std::thread::sleep(jitter_duration);
-
-                    tokio::spawn(async move {
-                        on_heartbeat(heartbeat_interval, heartbeat_writer, &mut rx).await;
-                    });
-                },
-                _ => return Err("Got unknown event when attempting to handshake")
-            }  
-        } else {
-            return Err("Failed to handshake with gateway");
+    /// Plays back a [Recording] through a [RecordingTransport] instead of
+    /// connecting to Discord. The client identifies, heartbeats and resumes as
+    /// it would on a live connection, so recorded events reach the
+    /// [EventHandler] and [EventStream] exactly as they did back then
+    ///
+    /// Only the shard which received the first recorded event is played.
+    /// Recordings of several shards can be played through a [ShardManager]
+    /// with a [RecordingTransport] instead
+    ///
+    /// Returns once every event was handled and the stream has ended. Commands
+    /// sent by handlers in the meantime are ignored
+    ///
+    /// # Errors
+    /// * If the [EventStream] is full and set to [Backpressure::Error]
+    /// * If the recording ends the session with a close code that cannot be recovered from
+    pub async fn replay(&mut self, mut recording: Recording) -> Result<(), GatewayError> {
+        if let Some(shard_id) = recording.events.first().map(|recorded| recorded.shard_id) {
+            recording.events.retain(|recorded| recorded.shard_id == shard_id);
         }
 
-        tokio::spawn(async move {
-            while let Some(Ok(packet )) = reader.next().await {
-                let mut writer = writer_mutex.lock().await;
-
-                match packet {
-                    Message::Text(text_message) => {
-                        let event = serde_json::from_str::<GatewayEvent>(&text_message)
-                            .expect("Failed to deserialize incoming data JSON");
-    
-                        let operation_code = GatewayOpCodeIndexer[event.op];
-                        let res = match operation_code {
-                            GatewayOpCode::Dispatch => on_dispatch(event),
-                            GatewayOpCode::Heartbeat => Ok(None),
-                            GatewayOpCode::Identify => todo!(),
-                            GatewayOpCode::PresenceUpdate => todo!(),
-                            GatewayOpCode::VoiceStateUpdate => todo!(),
-                            GatewayOpCode::Resume => todo!(),
-                            GatewayOpCode::Reconnect => todo!(),
-                            GatewayOpCode::RequestGuildMembers => todo!(),
-                            GatewayOpCode::InvalidSession => Err("Invalid session. Make sure your token is correct"),
-                            GatewayOpCode::Hello => todo!(),
-                            GatewayOpCode::HeartbeatAcknowledge => {
-                                println!("Got heartbeat acknowledgement!");
-                                Ok(None)
-                            },
-                        };
-
-                        // If any of the arms returned a message, send it through the socket
-                        if let Some(response) = res.unwrap() {
-                            let _ = writer.send(response).await;
-                        }
-                    },
-                    Message::Binary(_) => todo!(),
-                    Message::Close(close_message) => {
-                        println!("Received close message: {:?}", close_message);
-                    },
-                    Message::Frame(_) => todo!(),
-                    Message::Ping(_) => todo!(),
-                    Message::Pong(_) => todo!(),
-                }
-    
-                // match packet {
-                //     Message::Text(text_message) => {
-                //         
-                //         println!("Operation code: {:?}", operation_code);
-    
-                //         let _ = tx.send(event.to_owned());
-    
-                //         let res = match operation_code {
-                //             GatewayOpCode::Dispatch => self.on_dispatch(event),
-                //             GatewayOpCode::Heartbeat => {
-                //                 println!("Called heartbeat");
-                //                 //let _ = tx.send(event.to_owned());
-                //                 Ok(None)
-                //             },
-                //             GatewayOpCode::Identify => {
-                //                 println!("Called identify");
-                //                 Ok(None)
-                //             },
-                //             GatewayOpCode::PresenceUpdate => {
-                //                 println!("Called presence update");
-                //                 Ok(None)
-                //             },
-                //             GatewayOpCode::VoiceStateUpdate => {
-                //                 println!("Called state update");
-                //                 Ok(None)
-                //             },
-                //             GatewayOpCode::Resume => {
-                //                 println!("Called resume");
-                //                 Ok(None)
-                //             },
-                //             GatewayOpCode::Reconnect => {
-                //                 println!("Called reconnect");
-                //                 Ok(None)
-                //             },
-                //             GatewayOpCode::RequestGuildMembers => {
-                //                 println!("Called request guild members");
-                //                 Ok(None)
-                //             },
-                //             GatewayOpCode::InvalidSession => {
-                //                 return Err("Invalid session. Make sure your token is correct")
-                //             },
-                //             GatewayOpCode::Hello => {
-                //                 println!("Called hello");
-                //                 Ok(None)
-                //             },
-                //             GatewayOpCode::HeartbeatAcknowledge => {
-                //                 //let _ = tx.send(event.to_owned());
-                //                 println!("Got heartbeat ack. Sending through channel...");
-                //                 Ok(None)
-                //             }
-                //         };
-            
-                //         if let Some(response) = res.unwrap() {
-                //             let _ = writer.send(response).await;
-                //         }
-                //     },
-                //     Message::Close(close_message) => {
-                //         println!("Received close message: {:?}", close_message);
-                //     },
-                //     Message::Binary(binary_message) => {
-                //         println!("Received binary message: {:?}", binary_message);
-                //     },
-                //     Message::Ping(ping_message) => {
-                //         println!("Received ping message: {:?}", ping_message);
-                //     },
-                //     Message::Pong(pong_message) => {
-                //         println!("Received pong message: {:?}", pong_message);
-                //     },
-                //     Message::Frame(frame) => {
-                //         println!("Received frame message: {:?}", frame);
-                //     }
-                // }
-            }
-        });
-    
-        Ok(())
+        let limiter = Arc::new(SessionStartLimiter::unknown());
+        let (mut gateway, handle) = self.gateway(None, Arc::clone(&self.session), limiter);
+
+        // Only this gateway is stopped once the recording was played, not the whole client
+        let transport = RecordingTransport::new(recording);
+        transport.shutdown_when_finished(gateway.shutdown.clone());
+        gateway.transport = Arc::new(transport);
+
+        self.ws.shard = Some(handle);
+        let connection = gateway.connect().await?;
+        gateway.run(connection).await
     }
-}
 
-fn on_heartbeat_ack(event: GatewayEvent) -> Result<Option<Message>, &'static str> {
-    println!("Recieved heartbeat acknowledgement! {:#?}", event);
-    Ok(None)
-}
+    /// The latency, reconnect count and other stats of the gateway connection.
+    /// Only available after [Client::login]
+    pub fn stats(&self) -> Option<GatewayStats> {
+        self.ws.shard.as_ref().map(ShardHandle::stats)
+    }
 
-/// Receives regular events from the socket
-fn on_dispatch(event: GatewayEvent) -> Result<Option<Message>, &'static str> {
-    if event.t.is_none() || event.d.is_none() {
-        return Err("Received unidentified event type/data");
+    /// A handle used to stop the client gracefully, see [ShutdownHandle]
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
     }
 
-    let event_data = event.d.unwrap();
-    let event_type = event.t.unwrap();
-    let event_code = ReceiveEventIndexer[&event_type];
-
-    // match event_code {
-    //     ReceiveEvent::Ready => {
-    //         self.cache.insert("ready".to_string(), event_data);
-    //     }
-    //     _ => {
-    //         println!("Receieved dispatch event. Event name: {}. Data: {:#?}", event_type, event_data);
-    //     }
-    // }
-    
-    Ok(None)
-}
+    /// A handle to send commands through the gateway connection. Only
+    /// available after [Client::login]
+    pub fn shard(&self) -> Option<ShardHandle> {
+        self.ws.shard.clone()
+    }
 
-async fn on_heartbeat(
-    interval: u64,
-    writer: Arc<Mutex<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>>,
-    channel: &mut mpsc::Receiver<GatewayEvent>
-) {
-    let mut writer = writer.lock().await;
-    let mut last_sequence: u32 = 0;
-
-    println!("Initiating heartbeat loop...");
-
-    loop {
-        // Structure the heartbeat message
-        let heartbeat = GatewayEvent {
-            op: GatewayOpCode::Heartbeat as usize,
-            d: Some(if last_sequence == 0 { Value::Null } else { Value::Number(last_sequence.into()) }),
-            s: None,
-            t: None,
-        };
+    /// Updates the presence of the bot through a [GatewayOpCode::PresenceUpdate]
+    ///
+    /// # Arguments
+    /// * `status` - The status of the bot, such as [Status::Online]
+    /// * `activities` - What the bot is doing, such as [Activity::playing]
+    /// * `afk` - Whether the bot is away from keyboard
+    ///
+    /// # Errors
+    /// * If the client has not logged in
+    /// * If the gateway connection has stopped
+    pub async fn set_presence(&self, status: Status, activities: &[Activity], afk: bool) -> Result<(), GatewayError> {
+        match &self.ws.shard {
+            Some(shard) => shard.set_presence(status, activities, afk).await,
+            None => Err(GatewayError::ShardNotRunning),
+        }
+    }
+
+    /// Requests members of a guild through a [GatewayOpCode::RequestGuildMembers],
+    /// see [ShardHandle::request_guild_members]
+    ///
+    /// # Errors
+    /// * If the client has not logged in
+    /// * If the request is invalid or the connection stops before every chunk arrives
+    pub async fn request_guild_members(
+        &self,
+        guild_id: &str,
+        query: MemberQuery,
+        limit: u32,
+        presences: bool
+    ) -> Result<GuildMembers, GatewayError> {
+        match &self.ws.shard {
+            Some(shard) => shard.request_guild_members(guild_id, query, limit, presences).await,
+            None => Err(GatewayError::ShardNotRunning),
+        }
+    }
 
-        // Serialize the heartbeat request into JSON
-        let heartbeat = serde_json::to_string(&heartbeat).unwrap();
-        writer.send(Message::text(heartbeat))
+    /// Fetches the gateway URL, the recommended amount of shards and the
+    /// session start limits of the bot through GET /gateway/bot
+    ///
+    /// # Errors
+    /// * If the request fails or the response cannot be deserialized
+    pub async fn gateway_bot(&self) -> Result<GatewayBot, GatewayError> {
+        self.ws.client
+            .get(self.api_endpoint("/gateway/bot"))
+            .header("Authorization", format!("Bot {}", self.token))
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|_| GatewayError::Request("Failed to fetch gateway information"))?
+            .json::<GatewayBot>()
             .await
-            .expect("Failed tosend heartbeat");
+            .map_err(|_| GatewayError::Request("Failed to deserialize gateway information"))
+    }
 
-        println!("Sent heart beat. Current sequence: {}. Sleeping {}ms before next beat...", last_sequence, interval);
+    /// Creates a limiter from the session start limit of the bot. If the limit
+    /// cannot be fetched, identifies are only spaced out
+    pub(crate) async fn session_start_limiter(&self) -> SessionStartLimiter {
+        match self.gateway_bot().await {
+            Ok(gateway_bot) => SessionStartLimiter::new(&gateway_bot.session_start_limit),
+            Err(error) => {
+                log_message("warning", &format!("{}. Session start limits will not be enforced", error));
+                SessionStartLimiter::unknown()
+            }
+        }
+    }
 
-        // let event = channel.recv().await;
+    /// Creates a gateway session for this client, optionally as one of many
+    /// shards, along with a handle to send commands through it
+    pub(crate) fn gateway(
+        &self,
+        shard: Option<[u32; 2]>,
+        session: Arc<Mutex<Option<Session>>>,
+        limiter: Arc<SessionStartLimiter>
+    ) -> (Gateway, ShardHandle) {
+        let (sender, receiver) = mpsc::channel(COMMAND_QUEUE_SIZE);
+        let members = Arc::new(MemberRequests::default());
+        let stats = Arc::new(ConnectionStats::default());
+        let handle = ShardHandle {
+            shard_id: shard.map_or(0, |[id, _]| id),
+            codec: Arc::clone(&self.codec),
+            commands: sender,
+            members: Arc::clone(&members),
+            stats: Arc::clone(&stats)
+        };
 
-        // if event.is_none() {
-        //     println!("Errored out of heartbeat loop");
-        //     continue;
-        // }
-        // let event = event.unwrap();
+        let gateway = Gateway {
+            token: self.token.to_owned(),
+            intents: self.intents,
+            url: self.gateway_url.to_owned(),
+            api_version: self.api_version,
+            compression: self.compression,
+            codec: Arc::clone(&self.codec),
+            transport: Arc::clone(&self.transport),
+            cache: self.cache.clone(),
+            shard,
+            session,
+            handler: self.handler.clone(),
+            events: self.ws.events.clone().map(EventProducer::new),
+            limiter,
+            commands: Mutex::new(receiver),
+            presence: std::sync::Mutex::new(self.presence.clone()),
+            members,
+            shutdown: self.shutdown.child(),
+            tasks: Arc::new(HandlerTasks::default()),
+            stats,
+            recorder: self.recorder.clone(),
+        };
 
-        // let operation_code = GatewayOpCodeIndexer[event.op];
-        // println!("Operation code: {:?}", operation_code);
+        (gateway, handle)
+    }
+}
 
-        // match operation_code {
-        //     GatewayOpCode::Heartbeat => {
-        //         println!("Incoming request inside heartbeat {:#?}", event);
-        //     },
-        //     GatewayOpCode::Resume => todo!(),
-        //     GatewayOpCode::Reconnect => todo!(),
-        //     _ => println!("Got unexpected event inside heartbeat loop")
-        // }
+impl UpdatePresence {
+    /// Creates a presence, marking the bot as idle since now if `status` is [Status::Idle]
+    pub fn new(status: Status, activities: &[Activity], afk: bool) -> Self {
+        let since = match status {
+            Status::Idle => Some(chrono::Utc::now().timestamp_millis() as u64),
+            _ => None,
+        };
 
-        tokio::time::sleep(Duration::from_millis(interval)).await;
-        last_sequence += 1;
+        Self {
+            since,
+            activities: activities.to_vec(),
+            status,
+            afk
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,163 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::util::log_message;
+use super::types::SessionStartLimitObject;
+
+/// How long Discord asks us to wait between two identifies in the same bucket
+const IDENTIFY_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the session start limit resets when Discord did not tell us
+const SESSION_START_RESET: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Schedules identifies so they respect the session start limit of the bot
+/// https://discord.com/developers/docs/topics/gateway#session-start-limit-object
+///
+/// Shards are split into `max_concurrency` buckets by `shard_id % max_concurrency`.
+/// Shards in different buckets may identify at the same time, while shards in
+/// the same bucket identify one after another
+pub(crate) struct SessionStartLimiter {
+    state: Mutex<SessionStartState>
+}
+
+struct SessionStartState {
+    total: u32,
+    /// `None` when the limit is unknown, in which case it is not enforced
+    remaining: Option<u32>,
+    reset_at: Instant,
+    /// The earliest time each bucket may identify again
+    buckets: Vec<Instant>
+}
+
+impl SessionStartLimiter {
+    pub fn new(limit: &SessionStartLimitObject) -> Self {
+        let now = Instant::now();
+
+        Self {
+            state: Mutex::new(SessionStartState {
+                total: limit.total.max(0) as u32,
+                remaining: Some(limit.remaining.max(0) as u32),
+                reset_at: now + Duration::from_millis(limit.reset_after as u64),
+                buckets: vec![now; limit.max_concurrency.max(1) as usize]
+            })
+        }
+    }
+
+    /// A limiter for when the session start limit could not be fetched. Only
+    /// the delay between identifies is enforced
+    pub fn unknown() -> Self {
+        let now = Instant::now();
+
+        Self {
+            state: Mutex::new(SessionStartState {
+                total: 0,
+                remaining: None,
+                reset_at: now,
+                buckets: vec![now]
+            })
+        }
+    }
+
+    /// Waits until the shard is allowed to identify, consuming one session start
+    pub async fn acquire(&self, shard_id: u32) {
+        let slot = {
+            let mut state = self.state.lock().unwrap();
+            let now = Instant::now();
+            let mut earliest = now;
+
+            if let Some(remaining) = state.remaining {
+                if now >= state.reset_at {
+                    state.remaining = Some(state.total);
+                    state.reset_at = now + SESSION_START_RESET;
+                } else if remaining == 0 {
+                    log_message("warning", &format!(
+                        "Session start limit reached. Waiting {}s before identifying",
+                        (state.reset_at - now).as_secs()
+                    ));
+
+                    earliest = state.reset_at;
+                    state.remaining = Some(state.total);
+                    state.reset_at += SESSION_START_RESET;
+                }
+
+                state.remaining = state.remaining.map(|remaining| remaining.saturating_sub(1));
+            }
+
+            // Reserve the next free slot of this shard's bucket
+            let bucket = shard_id as usize % state.buckets.len();
+            let slot = state.buckets[bucket].max(earliest);
+            state.buckets[bucket] = slot + IDENTIFY_INTERVAL;
+            slot
+        };
+
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// The largest payload Discord accepts through the gateway, in bytes
+pub const MAX_PAYLOAD_SIZE: usize = 4096;
+/// How many commands Discord accepts per connection in each window
+const COMMANDS_PER_WINDOW: u32 = 120;
+/// The length of a command rate limit window
+const COMMAND_WINDOW: Duration = Duration::from_secs(60);
+
+/// Keeps a single connection under the gateway's limit of 120 commands per
+/// minute, which Discord enforces by closing the connection with 4008
+/// https://discord.com/developers/docs/topics/gateway#rate-limiting
+///
+/// Part of every window is reserved for heartbeats so that a burst of
+/// commands can never starve the connection of them
+pub(crate) struct CommandLimiter {
+    window_start: Instant,
+    sent: u32,
+    /// How many commands of each window only heartbeats may use
+    reserved: u32
+}
+
+impl CommandLimiter {
+    /// Creates a limiter for a connection which has just identified or resumed
+    ///
+    /// # Arguments
+    /// * `heartbeat_interval` - The interval sent in Hello, in milliseconds
+    pub fn new(heartbeat_interval: u64) -> Self {
+        // Enough heartbeats for a window, plus one to answer a heartbeat request
+        let heartbeats = COMMAND_WINDOW.as_millis() as u64 / heartbeat_interval.max(1) + 1;
+
+        Self {
+            window_start: Instant::now(),
+            // The identify or resume that opened the connection counts towards the limit
+            sent: 1,
+            reserved: (heartbeats as u32 + 1).min(COMMANDS_PER_WINDOW / 2)
+        }
+    }
+
+    /// Whether a command may be sent right now
+    pub fn available(&mut self) -> bool {
+        self.refresh();
+        self.sent < COMMANDS_PER_WINDOW - self.reserved
+    }
+
+    /// When the current window ends and the budget is restored
+    pub fn reset_at(&self) -> Instant {
+        self.window_start + COMMAND_WINDOW
+    }
+
+    /// Records a command. Callers must check [CommandLimiter::available] first
+    pub fn command(&mut self) {
+        self.refresh();
+        self.sent += 1;
+    }
+
+    /// Records a heartbeat, which may use the reserved part of the window
+    pub fn heartbeat(&mut self) {
+        self.refresh();
+        self.sent += 1;
+    }
+
+    fn refresh(&mut self) {
+        if Instant::now() >= self.reset_at() {
+            self.window_start = Instant::now();
+            self.sent = 0;
+        }
+    }
+}
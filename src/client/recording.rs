@@ -0,0 +1,472 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use flate2::write::ZlibEncoder;
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::util::{log_message, BackgroundWriter, Sink};
+use super::codec::{EtfCodec, GatewayCodec, JsonCodec};
+use super::errors::GatewayError;
+use super::shutdown::ShutdownHandle;
+use super::transport::{Frame, GatewaySocket, GatewayTransport};
+use super::types::{GatewayEvent, GatewayOpCode};
+
+/// The heartbeat interval sent to replayed clients when the recording has no Hello
+const DEFAULT_HEARTBEAT_INTERVAL: u64 = 41250;
+
+/// Whether a recorded payload was received from or sent to the gateway
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Inbound,
+    Outbound
+}
+
+/// A single line of a recording
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedEvent {
+    /// When the payload was received or sent
+    pub timestamp: DateTime<Utc>,
+    /// The shard whose connection carried the payload
+    pub shard_id: u32,
+    pub direction: Direction,
+    pub event: GatewayEvent
+}
+
+/// Writes every payload received from and sent to the gateway as JSON lines,
+/// so a session can later be played back through a [Recording]
+///
+/// Tokens sent in Identify and Resume payloads are redacted, which makes
+/// recordings safe to keep next to the code as test fixtures
+///
+/// Payloads are written from a thread of their own, and flushed whenever
+/// the thread catches up and when the client stops
+///
+/// # Example
+/// ```no_run
+/// use discord_rs::client::{ClientBuilder, GatewayIntentBits, Recorder};
+///
+/// let recorder = Recorder::create("session.jsonl").expect("Failed to create recording");
+/// let client = ClientBuilder::new("YOUR_TOKEN", &[GatewayIntentBits::Guilds])
+///     .with_recorder(recorder)
+///     .build();
+/// ```
+pub struct Recorder {
+    /// Writes the lines, so the gateway never waits on the writer
+    writer: BackgroundWriter<Vec<u8>>
+}
+
+/// Writes recorded lines, flushing once every line queued so far is written.
+/// This keeps the recording usable if the bot crashes, without a flush per line
+struct LineSink<W>(W);
+
+impl<W: Write + Send + 'static> Sink for LineSink<W> {
+    type Item = Vec<u8>;
+
+    fn write(&mut self, line: Vec<u8>) {
+        if self.0.write_all(&line).is_err() {
+            log_message("warning", "Failed to record gateway event");
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.0.flush().is_err() {
+            log_message("warning", "Failed to record gateway event");
+        }
+    }
+}
+
+impl Recorder {
+    /// Records to a file, replacing it if it already exists
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Records to any writer
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Self { writer: BackgroundWriter::spawn("discord-rs-recorder", LineSink(writer)) }
+    }
+
+    pub(crate) fn record(&self, shard_id: u32, direction: Direction, event: &GatewayEvent) {
+        let recorded = RecordedEvent {
+            timestamp: Utc::now(),
+            shard_id,
+            direction,
+            event: redact(event)
+        };
+
+        let Ok(mut line) = serde_json::to_vec(&recorded) else {
+            return;
+        };
+        line.push(b'\n');
+
+        self.writer.write(line);
+    }
+
+    /// Waits until every payload recorded so far has been written and flushed
+    pub(crate) async fn flush(&self) {
+        self.writer.flush().await;
+    }
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder").finish_non_exhaustive()
+    }
+}
+
+/// Replaces the token of Identify and Resume payloads
+fn redact(event: &GatewayEvent) -> GatewayEvent {
+    let mut event = event.clone();
+    let carries_token = matches!(
+        GatewayOpCode::from_code(event.op),
+        Some(GatewayOpCode::Identify | GatewayOpCode::Resume)
+    );
+
+    if let Some(Value::Object(data)) = event.d.as_mut().filter(|_| carries_token) {
+        if data.contains_key("token") {
+            data.insert("token".to_string(), Value::from("REDACTED"));
+        }
+    }
+
+    event
+}
+
+/// A session written by a [Recorder], which can be played back through
+/// [super::Client::replay] to reproduce how handlers behaved without
+/// connecting to Discord
+///
+/// # Example
+/// ```no_run
+/// use discord_rs::client::{Backpressure, Client, GatewayIntentBits, Recording};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let recording = Recording::open("session.jsonl").expect("Failed to read recording");
+///     let mut client = Client::new("YOUR_TOKEN", &[GatewayIntentBits::Guilds]);
+///     let events = client.events(64, Backpressure::Block);
+///
+///     client.replay(recording).await.expect("Failed to replay recording");
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    pub events: Vec<RecordedEvent>
+}
+
+impl Recording {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a recording line by line. Blank lines are skipped
+    ///
+    /// # Errors
+    /// * If reading fails, or a line is not a [RecordedEvent]
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut events = Vec::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let event = serde_json::from_str(&line).map_err(|error| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid recorded event on line {}: {}", index + 1, error)
+            ))?;
+
+            events.push(event);
+        }
+
+        Ok(Self { events })
+    }
+
+    /// The payloads which were received from the gateway, in order
+    pub fn inbound(&self) -> impl Iterator<Item = &GatewayEvent> {
+        self.events
+            .iter()
+            .filter(|recorded| recorded.direction == Direction::Inbound)
+            .map(|recorded| &recorded.event)
+    }
+}
+
+/// Plays a [Recording] back in place of Discord, so a [super::Client] or a
+/// [super::ShardManager] runs against it unchanged
+///
+/// Every connection is greeted with the first recorded Hello. Once the client
+/// identifies, it receives what the recording's connection of that shard
+/// received, a connection lasting up to the next recorded Hello. Resumes are
+/// matched to their shard through the session id of the recorded READY
+///
+/// Connections end when their recorded one did, which makes the client resume
+/// as it did back then. Heartbeats are acknowledged, and every other command
+/// is ignored. The connections stay open once the recording has been played,
+/// unless a handle was given to [RecordingTransport::shutdown_when_finished]
+///
+/// # Example
+/// ```no_run
+/// use discord_rs::client::{ClientBuilder, GatewayIntentBits, Recording, RecordingTransport, ShardManager};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let recording = Recording::open("session.jsonl").expect("Failed to read recording");
+///     let transport = RecordingTransport::new(recording);
+///     let client = ClientBuilder::new("YOUR_TOKEN", &[GatewayIntentBits::Guilds])
+///         .with_transport(transport.clone())
+///         .build();
+///
+///     transport.shutdown_when_finished(client.shutdown_handle());
+///
+///     let mut manager = ShardManager::new(client);
+///     manager.with_total_shards(2);
+///     manager.run().await.expect("Failed to replay recording");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecordingTransport {
+    playback: Arc<Mutex<Playback>>
+}
+
+#[derive(Debug)]
+struct Playback {
+    /// Sent before the client identifies, when its shard is not known yet
+    hello: GatewayEvent,
+    /// The recorded connections of every shard which were not played yet
+    connections: HashMap<u32, VecDeque<VecDeque<GatewayEvent>>>,
+    /// The shard which received each recorded READY
+    sessions: HashMap<String, u32>,
+    /// Played to clients which identify without sharding
+    default_shard: u32,
+    /// Shut down once every recorded connection was played
+    shutdown: Option<ShutdownHandle>
+}
+
+impl RecordingTransport {
+    pub fn new(recording: Recording) -> Self {
+        let hello = recording.inbound()
+            .find(|event| GatewayOpCode::from_code(event.op) == Some(GatewayOpCode::Hello))
+            .cloned()
+            .unwrap_or_else(|| GatewayEvent {
+                op: GatewayOpCode::Hello as usize,
+                d: Some(json!({ "heartbeat_interval": DEFAULT_HEARTBEAT_INTERVAL })),
+                s: None,
+                t: None
+            });
+
+        let mut connections: HashMap<u32, VecDeque<VecDeque<GatewayEvent>>> = HashMap::new();
+        for recorded in recording.events.iter().filter(|recorded| recorded.direction == Direction::Inbound) {
+            let shard = connections.entry(recorded.shard_id).or_default();
+
+            // Every connection starts with a Hello, which the transport sends by itself
+            if GatewayOpCode::from_code(recorded.event.op) == Some(GatewayOpCode::Hello) {
+                shard.push_back(VecDeque::new());
+                continue;
+            }
+
+            match shard.back_mut() {
+                Some(connection) => connection.push_back(recorded.event.clone()),
+                None => shard.push_back(VecDeque::from([recorded.event.clone()])),
+            }
+        }
+
+        let playback = Playback {
+            hello,
+            connections,
+            sessions: HashMap::new(),
+            default_shard: recording.events.first().map_or(0, |recorded| recorded.shard_id),
+            shutdown: None
+        };
+
+        Self { playback: Arc::new(Mutex::new(playback)) }
+    }
+
+    /// Shuts a client down once every recorded connection was played, so that
+    /// [super::Client::run] or [super::ShardManager::run] returns
+    pub fn shutdown_when_finished(&self, shutdown: ShutdownHandle) {
+        let mut playback = self.playback();
+
+        if playback.connections.is_empty() {
+            shutdown.shutdown();
+        }
+
+        playback.shutdown = Some(shutdown);
+    }
+
+    fn playback(&self) -> MutexGuard<'_, Playback> {
+        self.playback.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+#[async_trait]
+impl GatewayTransport for RecordingTransport {
+    async fn connect(&self, url: &str) -> Result<Box<dyn GatewaySocket>, GatewayError> {
+        let query = url.split_once('?').map_or("", |(_, query)| query);
+        let codec: Box<dyn GatewayCodec> = match query.split('&').any(|parameter| parameter == "encoding=etf") {
+            true => Box::new(EtfCodec),
+            false => Box::new(JsonCodec),
+        };
+
+        let compressor = query
+            .split('&')
+            .any(|parameter| parameter == "compress=zlib-stream")
+            .then(|| ZlibEncoder::new(Vec::new(), flate2::Compression::default()));
+
+        Ok(Box::new(RecordingSocket {
+            transport: self.clone(),
+            codec,
+            compressor,
+            queued: VecDeque::from([self.playback().hello.clone()]),
+            connection: None
+        }))
+    }
+}
+
+/// A connection to a [RecordingTransport]
+struct RecordingSocket {
+    transport: RecordingTransport,
+    codec: Box<dyn GatewayCodec>,
+    /// Compresses the whole connection as one zlib stream, if requested
+    compressor: Option<ZlibEncoder<Vec<u8>>>,
+    /// Answers to the client, sent before the next recorded event
+    queued: VecDeque<GatewayEvent>,
+    /// The shard being played and its remaining events, once the client identified or resumed
+    connection: Option<(u32, VecDeque<GatewayEvent>)>
+}
+
+impl RecordingSocket {
+    /// Picks the next recorded connection of a shard
+    fn play(&mut self, shard_id: u32) {
+        let events = self.transport
+            .playback()
+            .connections
+            .get_mut(&shard_id)
+            .and_then(VecDeque::pop_front)
+            .unwrap_or_default();
+
+        self.connection = Some((shard_id, events));
+    }
+
+    /// Called once the recorded connection was played. Returns whether the
+    /// shard has another one, in which case this connection is dropped
+    fn finish(&mut self, shard_id: u32) -> bool {
+        let mut playback = self.transport.playback();
+
+        if playback.connections.get(&shard_id).is_some_and(|connections| !connections.is_empty()) {
+            return true;
+        }
+
+        if playback.connections.remove(&shard_id).is_some() && playback.connections.is_empty() {
+            if let Some(shutdown) = &playback.shutdown {
+                shutdown.shutdown();
+            }
+        }
+
+        false
+    }
+
+    fn encode(&mut self, event: &GatewayEvent) -> Option<Frame> {
+        let payload = self.codec.encode(event).ok()?;
+
+        let Some(compressor) = self.compressor.as_mut() else {
+            return match self.codec.is_binary() {
+                true => Some(Frame::Binary(payload)),
+                false => String::from_utf8(payload).ok().map(Frame::Text),
+            };
+        };
+
+        // Flushing ends the payload with the suffix of a Z_SYNC_FLUSH, as Discord does
+        compressor.write_all(&payload).ok()?;
+        compressor.flush().ok()?;
+        Some(Frame::Binary(std::mem::take(compressor.get_mut())))
+    }
+}
+
+#[async_trait]
+impl GatewaySocket for RecordingSocket {
+    async fn send(&mut self, frame: Frame) -> Result<(), GatewayError> {
+        let payload = match frame {
+            Frame::Text(text) => text.into_bytes(),
+            Frame::Binary(payload) => payload,
+            Frame::Close(_) => return Ok(()),
+        };
+
+        let Ok(command) = self.codec.decode(&payload) else {
+            return Ok(());
+        };
+
+        match GatewayOpCode::from_code(command.op) {
+            Some(GatewayOpCode::Heartbeat) => self.queued.push_back(GatewayEvent {
+                op: GatewayOpCode::HeartbeatAcknowledge as usize,
+                d: None,
+                s: None,
+                t: None
+            }),
+            Some(GatewayOpCode::Identify) => {
+                let shard_id = command.d
+                    .as_ref()
+                    .and_then(|data| data["shard"][0].as_u64())
+                    .map_or(self.transport.playback().default_shard, |id| id as u32);
+
+                self.play(shard_id);
+            },
+            Some(GatewayOpCode::Resume) => {
+                let playback = self.transport.playback();
+                let shard_id = command.d
+                    .as_ref()
+                    .and_then(|data| data["session_id"].as_str())
+                    .and_then(|session_id| playback.sessions.get(session_id).copied())
+                    .unwrap_or(playback.default_shard);
+
+                drop(playback);
+                self.play(shard_id);
+            },
+            _ => {},
+        }
+
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Option<Frame> {
+        loop {
+            let next = match self.queued.pop_front() {
+                Some(event) => Some(event),
+                None => self.connection.as_mut().and_then(|(_, events)| events.pop_front()),
+            };
+
+            if let Some(event) = next {
+                if let (Some((shard_id, _)), Some("READY")) = (&self.connection, event.t.as_deref()) {
+                    if let Some(session_id) = event.d.as_ref().and_then(|data| data["session_id"].as_str()) {
+                        self.transport.playback().sessions.insert(session_id.to_string(), *shard_id);
+                    }
+                }
+
+                match self.encode(&event) {
+                    Some(frame) => return Some(frame),
+                    None => continue,
+                }
+            }
+
+            // Nothing is sent before the client identifies or resumes
+            let Some((shard_id, _)) = self.connection else {
+                return std::future::pending().await;
+            };
+
+            if self.finish(shard_id) {
+                return None;
+            }
+
+            // The connection stays open to answer heartbeats until the client stops
+            return std::future::pending().await;
+        }
+    }
+
+    async fn close(&mut self, _code: u16) {}
+}
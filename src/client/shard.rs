@@ -0,0 +1,390 @@
+use futures_util::future::join_all;
+use serde_json::json;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
+
+use crate::models::{Activity, Status};
+use crate::util::log_message;
+use super::codec::GatewayCodec;
+use super::errors::GatewayError;
+use super::events::Event;
+use super::gateway::{Connection, Gateway};
+use super::members::{GuildMembers, MemberQuery, MemberRequests};
+use super::stats::{ConnectionStats, GatewayStats};
+use super::ratelimit::{SessionStartLimiter, MAX_PAYLOAD_SIZE};
+use super::shutdown::ShutdownHandle;
+use super::stream::{Backpressure, EventQueue, EventStream};
+use super::types::{Client, GatewayEvent, GatewayOpCode, Session, UpdatePresence};
+
+/// Runs a bot across several gateway connections, one per shard
+/// https://discord.com/developers/docs/topics/gateway#sharding
+///
+/// # Example
+/// ```no_run
+/// use discord_rs::client::{Client, GatewayIntentBits, ShardManager};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = Client::new("YOUR_TOKEN", &[GatewayIntentBits::Guilds]);
+///
+///     let mut manager = ShardManager::new(client);
+///     manager.start().await.expect("Failed to start shards");
+///
+///     // Later on, a single misbehaving shard can be restarted on its own
+///     manager.restart(0).await.expect("Failed to restart shard");
+///
+///     // Keep running until the client's ShutdownHandle is used
+///     manager.run().await.expect("A shard failed");
+/// }
+/// ```
+pub struct ShardManager {
+    /// The client whose configuration every shard is created from
+    pub client: Client,
+    /// The total amount of shards. Fetched from Discord when not set
+    pub total: Option<u32>,
+    /// The ids of the shards this process runs. Defaults to every shard
+    pub range: Option<Range<u32>>,
+    shards: HashMap<u32, Shard>,
+    /// Created on start, and shared by every shard afterwards
+    limiter: Option<Arc<SessionStartLimiter>>
+}
+
+/// A shard which is currently running
+struct Shard {
+    session: Arc<Mutex<Option<Session>>>,
+    handle: ShardHandle,
+    /// Stops this shard without stopping the others
+    shutdown: ShutdownHandle,
+    task: JoinHandle<Result<(), GatewayError>>
+}
+
+impl ShardManager {
+    /// Creates a manager for the shards of a client. The client's handler,
+    /// intents and URLs are shared by every shard
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            total: None,
+            range: None,
+            shards: HashMap::new(),
+            limiter: None
+        }
+    }
+
+    /// Sets the total amount of shards instead of using the amount recommended by Discord
+    pub fn with_total_shards(&mut self, total: u32) -> &mut Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// Only runs the shards with an id in the range. Useful to split a bot
+    /// across several processes
+    pub fn with_shard_range(&mut self, range: Range<u32>) -> &mut Self {
+        self.range = Some(range);
+        self
+    }
+
+    /// Creates a [EventStream] which yields every event sent to any of the
+    /// shards along with the id of the shard which received it. It must be
+    /// called before [ShardManager::start]
+    ///
+    /// # Arguments
+    /// * `capacity` - How many events may be buffered before `backpressure` applies
+    /// * `backpressure` - What to do when the stream is not consumed fast enough
+    pub fn events(&mut self, capacity: usize, backpressure: Backpressure) -> EventStream<(u32, Event)> {
+        let queue = Arc::new(EventQueue::new(capacity, backpressure));
+        self.client.ws.events = Some(Arc::clone(&queue));
+        EventStream::sharded(queue)
+    }
+
+    /// The ids of the shards which are currently running
+    pub fn shard_ids(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = self.shards.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Connects every shard in the configured range
+    ///
+    /// The session start limit of the bot is fetched from GET /gateway/bot,
+    /// and shards are identified in `max_concurrency` buckets so that large
+    /// bots never identify faster than Discord allows
+    ///
+    /// # Errors
+    /// * If the intents do not allow receiving a required event, see [Client::check_intents]
+    /// * If the recommended amount of shards cannot be fetched
+    /// * If the shard range is empty or goes past the total amount of shards
+    /// * If any of the shards fails its initial handshake
+    pub async fn start(&mut self) -> Result<(), GatewayError> {
+        self.client.check_intents()?;
+
+        let (total, limiter) = match (self.total, self.client.gateway_bot().await) {
+            (total, Ok(gateway_bot)) => (
+                total.unwrap_or(gateway_bot.shards),
+                SessionStartLimiter::new(&gateway_bot.session_start_limit)
+            ),
+            (Some(total), Err(error)) => {
+                log_message("warning", &format!("{}. Session start limits will not be enforced", error));
+                (total, SessionStartLimiter::unknown())
+            },
+            (None, Err(error)) => return Err(error),
+        };
+
+        let limiter = Arc::new(limiter);
+        self.total = Some(total);
+        self.limiter = Some(Arc::clone(&limiter));
+
+        let range = self.range.clone().unwrap_or(0..total);
+        if range.is_empty() || range.end > total {
+            return Err(GatewayError::Configuration("Shard range must be within the total amount of shards"));
+        }
+
+        // Every shard waits for its own turn to identify, so they can all connect at once
+        let connections = join_all(range.map(|id| {
+            let session = Arc::new(Mutex::new(None));
+            let (gateway, handle) = self.client.gateway(Some([id, total]), Arc::clone(&session), Arc::clone(&limiter));
+
+            async move {
+                let connection = gateway.connect().await;
+                (id, session, handle, gateway, connection)
+            }
+        })).await;
+
+        let mut shards = Vec::new();
+        let mut failure = None;
+
+        for (id, session, handle, gateway, connection) in connections {
+            match connection {
+                Ok(connection) => shards.push((id, Shard::spawn(session, handle, gateway, connection))),
+                Err(error) => { failure.get_or_insert(error); },
+            }
+        }
+
+        // Shards which did connect are closed properly rather than left running unmanaged
+        if let Some(error) = failure {
+            join_all(shards.into_iter().map(|(_, shard)| shard.stop())).await;
+            return Err(error);
+        }
+
+        for (id, shard) in shards {
+            self.insert(id, total, shard);
+        }
+
+        Ok(())
+    }
+
+    /// Starts the shards if needed, then waits until every shard has stopped,
+    /// either through the [super::ShutdownHandle] of the client or because
+    /// of an unrecoverable error
+    ///
+    /// # Errors
+    /// * If starting the shards fails, see [ShardManager::start]
+    /// * The first unrecoverable error of any shard
+    pub async fn run(&mut self) -> Result<(), GatewayError> {
+        if self.shards.is_empty() {
+            self.start().await?;
+        }
+
+        // The shards are done for once their tasks return, so they are not kept around
+        let shards = std::mem::take(&mut self.shards);
+        let results = join_all(shards.into_values().map(|shard| shard.task)).await;
+
+        results
+            .into_iter()
+            .try_for_each(|result| result.map_err(|_| GatewayError::Connection("A shard stopped unexpectedly"))?)
+    }
+
+    /// Disconnects a shard and connects it again with a new session. The
+    /// previous connection is closed once its running handlers have finished
+    ///
+    /// # Errors
+    /// * If the shard is not running
+    /// * If the shard fails its initial handshake
+    pub async fn restart(&mut self, shard_id: u32) -> Result<(), GatewayError> {
+        let (Some(total), Some(limiter)) = (self.total, self.limiter.clone()) else {
+            return Err(GatewayError::Configuration("Shards have not been started"));
+        };
+
+        let Some(shard) = self.shards.remove(&shard_id) else {
+            return Err(GatewayError::Configuration("Shard is not running"));
+        };
+
+        // The new gateway is created first so the shared EventStream never runs out of producers
+        let session = Arc::new(Mutex::new(None));
+        let (gateway, handle) = self.client.gateway(Some([shard_id, total]), Arc::clone(&session), limiter);
+        shard.stop().await;
+
+        let connection = gateway.connect().await?;
+        self.insert(shard_id, total, Shard::spawn(session, handle, gateway, connection));
+
+        Ok(())
+    }
+
+    /// The current session of a shard, if it is running and has received READY
+    pub async fn session(&self, shard_id: u32) -> Option<Session> {
+        let shard = self.shards.get(&shard_id)?;
+        let session = shard.session.lock().await;
+        session.clone()
+    }
+
+    /// A handle to send commands through the connection of a running shard
+    pub fn shard(&self, shard_id: u32) -> Option<ShardHandle> {
+        self.shards.get(&shard_id).map(|shard| shard.handle.clone())
+    }
+
+    /// Updates the presence of the bot on every running shard
+    ///
+    /// # Errors
+    /// * If the connection of any shard has stopped
+    pub async fn set_presence(&self, status: Status, activities: &[Activity], afk: bool) -> Result<(), GatewayError> {
+        for shard in self.shards.values() {
+            shard.handle.set_presence(status, activities, afk).await?;
+        }
+
+        Ok(())
+    }
+
+    fn insert(&mut self, id: u32, total: u32, shard: Shard) {
+        log_message("success", &format!("Shard {} of {} connected", id, total));
+        self.shards.insert(id, shard);
+    }
+}
+
+impl Shard {
+    fn spawn(session: Arc<Mutex<Option<Session>>>, handle: ShardHandle, gateway: Gateway, connection: Connection) -> Self {
+        Self {
+            session,
+            handle,
+            shutdown: gateway.shutdown.clone(),
+            task: tokio::spawn(gateway.run(connection))
+        }
+    }
+
+    /// Closes the connection of the shard and waits for its handlers to finish
+    async fn stop(self) {
+        self.shutdown.shutdown();
+        let _ = self.task.await;
+    }
+}
+
+/// A cheap, cloneable handle used to send commands through the gateway
+/// connection of a single shard, or of a [Client] which is not sharded
+///
+/// Commands are queued while the connection is being resumed, and are sent
+/// as fast as the gateway's rate limit allows
+#[derive(Debug, Clone)]
+pub struct ShardHandle {
+    /// The id of the shard, or 0 for a client which is not sharded
+    pub shard_id: u32,
+    pub(crate) codec: Arc<dyn GatewayCodec>,
+    pub(crate) commands: Sender<GatewayEvent>,
+    pub(crate) members: Arc<MemberRequests>,
+    pub(crate) stats: Arc<ConnectionStats>
+}
+
+impl ShardHandle {
+    /// Sends a raw command, such as a [GatewayOpCode::VoiceStateUpdate], through the gateway
+    ///
+    /// # Errors
+    /// * If the command is larger than the 4096 bytes accepted by the gateway
+    /// * If the connection of the shard has stopped
+    pub async fn send(&self, command: GatewayEvent) -> Result<(), GatewayError> {
+        let size = self.codec
+            .encode(&command)
+            .map_err(|_| GatewayError::InvalidCommand("Failed to encode gateway command"))?
+            .len();
+
+        if size > MAX_PAYLOAD_SIZE {
+            return Err(GatewayError::InvalidCommand("Gateway commands cannot be larger than 4096 bytes"));
+        }
+
+        self.commands
+            .send(command)
+            .await
+            .map_err(|_| GatewayError::ShardNotRunning)
+    }
+
+    /// A snapshot of the latency, reconnect count and other stats of the connection
+    pub fn stats(&self) -> GatewayStats {
+        self.stats.snapshot()
+    }
+
+    /// Updates the presence of the bot on this shard through a [GatewayOpCode::PresenceUpdate]
+    ///
+    /// # Arguments
+    /// * `status` - The status of the bot, such as [Status::Online]
+    /// * `activities` - What the bot is doing, such as [Activity::playing]
+    /// * `afk` - Whether the bot is away from keyboard
+    ///
+    /// # Errors
+    /// * If the connection of the shard has stopped
+    pub async fn set_presence(&self, status: Status, activities: &[Activity], afk: bool) -> Result<(), GatewayError> {
+        let presence = UpdatePresence::new(status, activities, afk);
+
+        self.send(GatewayEvent {
+            op: GatewayOpCode::PresenceUpdate as usize,
+            d: Some(serde_json::to_value(presence).map_err(|_| GatewayError::InvalidCommand("Failed to serialize presence"))?),
+            s: None,
+            t: None
+        }).await
+    }
+
+    /// Requests members of a guild through a [GatewayOpCode::RequestGuildMembers]
+    /// and waits for every GUILD_MEMBERS_CHUNK sent in response
+    ///
+    /// The chunks are still delivered as regular events as well
+    ///
+    /// # Arguments
+    /// * `guild_id` - The guild to request members from, which must be on this shard
+    /// * `query` - Which members to return
+    /// * `limit` - The maximum amount of members to return, or 0 for no limit
+    /// * `presences` - Whether to return the presences of the members as well
+    ///
+    /// # Errors
+    /// * If more than 100 user ids are requested
+    /// * If the connection of the shard stops before every chunk arrives
+    pub async fn request_guild_members(
+        &self,
+        guild_id: &str,
+        query: MemberQuery,
+        limit: u32,
+        presences: bool
+    ) -> Result<GuildMembers, GatewayError> {
+        let mut data = json!({
+            "guild_id": guild_id,
+            "limit": limit,
+            "presences": presences
+        });
+
+        match query {
+            MemberQuery::Query(query) => data["query"] = json!(query),
+            MemberQuery::UserIds(ids) if ids.len() > 100 => {
+                return Err(GatewayError::InvalidCommand("Cannot request more than 100 members by id"));
+            },
+            MemberQuery::UserIds(ids) => data["user_ids"] = json!(ids),
+        }
+
+        let (nonce, receiver) = self.members.register(guild_id);
+        data["nonce"] = json!(nonce);
+
+        let command = GatewayEvent {
+            op: GatewayOpCode::RequestGuildMembers as usize,
+            d: Some(data),
+            s: None,
+            t: None
+        };
+
+        if let Err(error) = self.send(command).await {
+            self.members.cancel(&nonce);
+            return Err(error);
+        }
+
+        receiver
+            .await
+            .map_err(|_| GatewayError::Connection("The gateway session ended before every member was received"))
+    }
+}
@@ -0,0 +1,141 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::{watch, Notify};
+
+/// A cheap, cloneable handle used to stop a [super::Client] gracefully, for
+/// example when the process receives SIGTERM
+///
+/// Shutting down closes the gateway connection with code 1000, stops
+/// heartbeating, waits for every [super::EventHandler] call which is still
+/// running and ends the [super::EventStream]. [super::Client::run] then returns
+///
+/// # Example
+/// ```no_run
+/// use discord_rs::client::{Client, GatewayIntentBits};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut client = Client::new("YOUR_TOKEN", &[GatewayIntentBits::Guilds]);
+///     let shutdown = client.shutdown_handle();
+///
+///     tokio::spawn(async move {
+///         tokio::signal::ctrl_c().await.expect("Failed to listen for ctrl-c");
+///         shutdown.shutdown();
+///     });
+///
+///     client.run().await.expect("Gateway connection failed");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    sender: Arc<watch::Sender<bool>>,
+    /// The handle of the whole client, for the handle of a single shard
+    parent: Option<Arc<watch::Sender<bool>>>
+}
+
+impl ShutdownHandle {
+    pub(crate) fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+
+        Self {
+            sender: Arc::new(sender),
+            parent: None
+        }
+    }
+
+    /// Creates a handle which stops a single shard, and which is also shut
+    /// down along with this one
+    pub(crate) fn child(&self) -> Self {
+        let (sender, _) = watch::channel(false);
+
+        Self {
+            sender: Arc::new(sender),
+            parent: Some(Arc::clone(&self.sender))
+        }
+    }
+
+    /// Asks every gateway connection of the client to stop. Calling this
+    /// more than once has no further effect
+    pub fn shutdown(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// Whether a shutdown was requested
+    pub fn is_shutdown(&self) -> bool {
+        *self.sender.borrow() || self.parent.as_ref().is_some_and(|parent| *parent.borrow())
+    }
+
+    /// Resolves once a shutdown is requested
+    pub(crate) fn requested(&self) -> impl Future<Output = ()> + Send + 'static {
+        let own = requested(self.sender.subscribe());
+        let parent = self.parent.as_ref().map(|parent| requested(parent.subscribe()));
+
+        async move {
+            match parent {
+                Some(parent) => tokio::select! {
+                    _ = own => {},
+                    _ = parent => {},
+                },
+                None => own.await,
+            }
+        }
+    }
+}
+
+async fn requested(mut receiver: watch::Receiver<bool>) {
+    while !*receiver.borrow_and_update() {
+        if receiver.changed().await.is_err() {
+            // The handle is gone, so a shutdown can never be requested
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Counts the [super::EventHandler] calls which are still running so they
+/// can be waited for before shutting down
+#[derive(Debug, Default)]
+pub(crate) struct HandlerTasks {
+    running: AtomicUsize,
+    finished: Notify
+}
+
+impl HandlerTasks {
+    /// Runs a handler call on its own task
+    pub fn spawn<F>(self: &Arc<Self>, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static
+    {
+        self.running.fetch_add(1, Ordering::AcqRel);
+        let guard = HandlerGuard(Arc::clone(self));
+
+        tokio::spawn(async move {
+            // Dropped even if the handler panics
+            let _guard = guard;
+            future.await;
+        });
+    }
+
+    /// Waits until every handler call has returned
+    pub async fn flush(&self) {
+        loop {
+            let finished = self.finished.notified();
+
+            if self.running.load(Ordering::Acquire) == 0 {
+                return;
+            }
+
+            finished.await;
+        }
+    }
+}
+
+struct HandlerGuard(Arc<HandlerTasks>);
+
+impl Drop for HandlerGuard {
+    fn drop(&mut self) {
+        if self.0.running.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.finished.notify_waiters();
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::types::ReceiveEvent;
+
+/// How many heartbeat round trips the average latency is computed over
+const LATENCY_SAMPLES: usize = 10;
+
+/// A snapshot of the health of a single gateway connection, taken through
+/// [super::ShardHandle::stats] or [super::Client::stats]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GatewayStats {
+    /// The time between the last heartbeat and its acknowledgement
+    pub latency: Option<Duration>,
+    /// The average latency of the last 10 heartbeats
+    pub average_latency: Option<Duration>,
+    /// How many times the connection was opened again after being lost
+    pub reconnects: u32,
+    /// How many events of each type were received
+    pub events: HashMap<ReceiveEvent, u64>,
+    /// The sequence number of the last dispatch received
+    pub last_sequence: Option<u32>,
+    /// How long the current connection has been open, or `None` while reconnecting
+    pub uptime: Option<Duration>
+}
+
+/// Collects the stats of a connection as it runs
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionStats {
+    state: Mutex<StatsState>
+}
+
+#[derive(Debug, Default)]
+struct StatsState {
+    /// When the heartbeat which has not been acknowledged yet was sent
+    heartbeat_sent_at: Option<Instant>,
+    latencies: VecDeque<Duration>,
+    reconnects: u32,
+    events: HashMap<ReceiveEvent, u64>,
+    last_sequence: Option<u32>,
+    connected_at: Option<Instant>
+}
+
+impl ConnectionStats {
+    pub fn connected(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.connected_at = Some(Instant::now());
+        state.heartbeat_sent_at = None;
+    }
+
+    pub fn disconnected(&self) {
+        self.state.lock().unwrap().connected_at = None;
+    }
+
+    pub fn reconnected(&self) {
+        self.state.lock().unwrap().reconnects += 1;
+    }
+
+    pub fn heartbeat_sent(&self) {
+        self.state.lock().unwrap().heartbeat_sent_at = Some(Instant::now());
+    }
+
+    pub fn heartbeat_acknowledged(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        let Some(sent_at) = state.heartbeat_sent_at.take() else {
+            return;
+        };
+
+        if state.latencies.len() == LATENCY_SAMPLES {
+            state.latencies.pop_front();
+        }
+
+        state.latencies.push_back(sent_at.elapsed());
+    }
+
+    pub fn event(&self, kind: ReceiveEvent) {
+        *self.state.lock().unwrap().events.entry(kind).or_default() += 1;
+    }
+
+    pub fn sequence(&self, sequence: u32) {
+        self.state.lock().unwrap().last_sequence = Some(sequence);
+    }
+
+    pub fn snapshot(&self) -> GatewayStats {
+        let state = self.state.lock().unwrap();
+        let average_latency = match state.latencies.len() {
+            0 => None,
+            samples => Some(state.latencies.iter().sum::<Duration>() / samples as u32),
+        };
+
+        GatewayStats {
+            latency: state.latencies.back().copied(),
+            average_latency,
+            reconnects: state.reconnects,
+            events: state.events.clone(),
+            last_sequence: state.last_sequence,
+            uptime: state.connected_at.map(|connected_at| connected_at.elapsed())
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use futures_util::stream::Stream;
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::Notify;
+
+use super::errors::GatewayError;
+use super::events::Event;
+
+/// What the gateway should do when the buffer of an [EventStream] is full
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Stop reading from the gateway until the stream catches up. Reading is
+    /// also what keeps the heartbeat going, so a stream which stalls for too
+    /// long will cause the connection to be dropped and resumed
+    Block,
+    /// Discard the oldest buffered event to make room for the new one
+    DropOldest,
+    /// Treat a full buffer as a fatal error, which stops the client and ends the stream
+    Error
+}
+
+/// A bounded buffer of events shared between the gateways and an [EventStream].
+/// Every event is buffered along with the id of the shard which received it
+pub(crate) struct EventQueue {
+    events: Mutex<VecDeque<(u32, Event)>>,
+    capacity: usize,
+    backpressure: Backpressure,
+    /// Signaled whenever an event is pushed or the queue is closed
+    readable: Notify,
+    /// Signaled whenever an event is taken out of the queue or the stream is dropped
+    writable: Notify,
+    /// How many gateways may still push events, see [EventProducer]
+    producers: AtomicUsize,
+    /// Set once every gateway stops, so the stream ends after the buffered events
+    closed: AtomicBool,
+    /// Set once the stream is dropped, so events are no longer buffered
+    abandoned: AtomicBool
+}
+
+impl EventQueue {
+    pub fn new(capacity: usize, backpressure: Backpressure) -> Self {
+        Self {
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity: capacity.max(1),
+            backpressure,
+            readable: Notify::new(),
+            writable: Notify::new(),
+            producers: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            abandoned: AtomicBool::new(false)
+        }
+    }
+
+    /// Buffers an event, applying the configured [Backpressure] if the buffer is full
+    pub async fn push(&self, event: (u32, Event)) -> Result<(), GatewayError> {
+        loop {
+            if self.abandoned.load(Ordering::Acquire) {
+                return Ok(());
+            }
+
+            {
+                let mut events = self.events.lock().unwrap();
+
+                if events.len() < self.capacity {
+                    events.push_back(event);
+                    self.readable.notify_one();
+                    return Ok(());
+                }
+
+                match self.backpressure {
+                    Backpressure::Block => {},
+                    Backpressure::DropOldest => {
+                        events.pop_front();
+                        events.push_back(event);
+                        self.readable.notify_one();
+                        return Ok(());
+                    },
+                    Backpressure::Error => return Err(GatewayError::EventStreamFull),
+                }
+            }
+
+            self.writable.notified().await;
+        }
+    }
+
+    /// Ends the stream once the events already buffered have been consumed
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.readable.notify_one();
+    }
+
+    async fn next(&self) -> Option<(u32, Event)> {
+        loop {
+            if let Some(event) = self.events.lock().unwrap().pop_front() {
+                self.writable.notify_one();
+                return Some(event);
+            }
+
+            if self.closed.load(Ordering::Acquire) {
+                return None;
+            }
+
+            self.readable.notified().await;
+        }
+    }
+}
+
+/// A gateway's share of an [EventQueue]. Every shard pushes into the same
+/// queue, so the stream only ends once the last producer is dropped
+pub(crate) struct EventProducer(Arc<EventQueue>);
+
+impl EventProducer {
+    pub fn new(queue: Arc<EventQueue>) -> Self {
+        queue.producers.fetch_add(1, Ordering::AcqRel);
+        Self(queue)
+    }
+}
+
+impl Deref for EventProducer {
+    type Target = EventQueue;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for EventProducer {
+    fn drop(&mut self) {
+        if self.0.producers.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.close();
+        }
+    }
+}
+
+/// A [Stream] of the events sent by the gateway, created through [super::Client::events]
+/// or [super::ShardManager::events]. The latter yields each event along with the
+/// id of the shard which received it
+///
+/// # Example
+/// ```no_run
+/// use discord_rs::client::{Backpressure, Client, Event, GatewayIntentBits};
+/// use futures_util::StreamExt;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut client = Client::new("YOUR_TOKEN", &[GatewayIntentBits::GuildMessages]);
+///     let mut events = client.events(256, Backpressure::Block);
+///
+///     client.login().await.expect("Failed to login");
+///
+///     while let Some(event) = events.next().await {
+///         if let Event::MessageCreate(message) = event {
+///             println!("{:?}", message.content);
+///         }
+///     }
+/// }
+/// ```
+pub struct EventStream<T = Event> {
+    queue: Arc<EventQueue>,
+    inner: Pin<Box<dyn Stream<Item = T> + Send>>
+}
+
+impl EventStream<Event> {
+    pub(crate) fn new(queue: Arc<EventQueue>) -> Self {
+        Self::from_queue(queue, |(_, event)| event)
+    }
+}
+
+impl EventStream<(u32, Event)> {
+    pub(crate) fn sharded(queue: Arc<EventQueue>) -> Self {
+        Self::from_queue(queue, |item| item)
+    }
+}
+
+impl<T: Send + 'static> EventStream<T> {
+    fn from_queue(queue: Arc<EventQueue>, map: fn((u32, Event)) -> T) -> Self {
+        let inner = futures_util::stream::unfold(Arc::clone(&queue), move |queue| async move {
+            queue.next().await.map(|item| (map(item), queue))
+        });
+
+        Self { queue, inner: Box::pin(inner) }
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl<T> Drop for EventStream<T> {
+    fn drop(&mut self) {
+        // Make sure a blocked gateway does not wait on a stream that no longer exists
+        self.queue.abandoned.store(true, Ordering::Release);
+        self.queue.events.lock().unwrap().clear();
+        self.queue.writable.notify_one();
+    }
+}
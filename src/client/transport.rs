@@ -0,0 +1,223 @@
+use async_trait::async_trait;
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, WebSocketStream, MaybeTlsStream};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+use super::errors::GatewayError;
+
+/// A websocket message exchanged with the gateway
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+    /// The connection was closed, with a close code if one was sent
+    Close(Option<u16>)
+}
+
+/// Opens connections to the gateway. The default is [TungsteniteTransport],
+/// but any other can be set through [super::ClientBuilder::with_transport],
+/// for example to go through a proxy or to test a bot without a network
+///
+/// # Example
+/// ```
+/// use discord_rs::client::{async_trait, GatewayError, GatewaySocket, GatewayTransport, TungsteniteTransport};
+///
+/// /// Connects through a local proxy instead of connecting to Discord directly
+/// #[derive(Debug)]
+/// struct ProxyTransport {
+///     proxy: String
+/// }
+///
+/// #[async_trait]
+/// impl GatewayTransport for ProxyTransport {
+///     async fn connect(&self, url: &str) -> Result<Box<dyn GatewaySocket>, GatewayError> {
+///         let query = url.split_once('?').map_or("", |(_, query)| query);
+///         TungsteniteTransport.connect(&format!("{}/?{}", self.proxy, query)).await
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait GatewayTransport: std::fmt::Debug + Send + Sync {
+    /// Opens a connection to a gateway URL, which already contains the version,
+    /// encoding and compression query parameters
+    async fn connect(&self, url: &str) -> Result<Box<dyn GatewaySocket>, GatewayError>;
+}
+
+/// A single connection opened by a [GatewayTransport]
+#[async_trait]
+pub trait GatewaySocket: Send {
+    /// Sends a text or binary frame
+    async fn send(&mut self, frame: Frame) -> Result<(), GatewayError>;
+
+    /// Waits for the next frame, or `None` if the connection was lost without
+    /// a close frame. The client may cancel this in between frames, so no
+    /// frame may be lost when the future is dropped
+    async fn receive(&mut self) -> Option<Frame>;
+
+    /// Closes the connection with a close code
+    async fn close(&mut self, code: u16);
+}
+
+/// Connects to the gateway over a websocket through tungstenite
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TungsteniteTransport;
+
+#[async_trait]
+impl GatewayTransport for TungsteniteTransport {
+    async fn connect(&self, url: &str) -> Result<Box<dyn GatewaySocket>, GatewayError> {
+        let (socket, _) = connect_async(url)
+            .await
+            .map_err(|_| GatewayError::Connection("Failed to connect to gateway"))?;
+
+        Ok(Box::new(socket))
+    }
+}
+
+#[async_trait]
+impl GatewaySocket for WebSocketStream<MaybeTlsStream<TcpStream>> {
+    async fn send(&mut self, frame: Frame) -> Result<(), GatewayError> {
+        let message = match frame {
+            Frame::Text(text) => Message::Text(text),
+            Frame::Binary(payload) => Message::Binary(payload),
+            Frame::Close(code) => Message::Close(code.map(|code| CloseFrame { code: CloseCode::from(code), reason: "".into() })),
+        };
+
+        SinkExt::send(self, message)
+            .await
+            .map_err(|_| GatewayError::Connection("Failed to write to the gateway connection"))
+    }
+
+    async fn receive(&mut self) -> Option<Frame> {
+        loop {
+            match self.next().await? {
+                Ok(Message::Text(text)) => return Some(Frame::Text(text)),
+                Ok(Message::Binary(payload)) => return Some(Frame::Binary(payload)),
+                Ok(Message::Close(frame)) => return Some(Frame::Close(frame.map(|frame| u16::from(frame.code)))),
+                // Pings are answered by tungstenite itself
+                Ok(_) => {},
+                Err(_) => return None,
+            }
+        }
+    }
+
+    async fn close(&mut self, code: u16) {
+        let frame = CloseFrame { code: CloseCode::from(code), reason: "".into() };
+        let _ = WebSocketStream::close(self, Some(frame)).await;
+    }
+}
+
+/// Connects to the gateway through in-memory channels, so tests can play
+/// the part of Discord without opening any socket
+///
+/// Every connection the client opens is handed to the [ChannelListener]
+/// created along with the transport
+///
+/// # Example
+/// ```
+/// use discord_rs::client::{ChannelTransport, ClientBuilder, Frame, GatewayIntentBits, GatewaySocket};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let (transport, mut listener) = ChannelTransport::new();
+///     let mut client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+///         .with_api_url("http://127.0.0.1:9")
+///         .with_transport(transport)
+///         .build();
+///
+///     tokio::spawn(async move {
+///         let (_url, mut socket) = listener.accept().await.unwrap();
+///         socket.send(Frame::Text(r#"{"op":10,"d":{"heartbeat_interval":45000}}"#.into())).await.unwrap();
+///
+///         // The identify
+///         assert!(matches!(socket.receive().await, Some(Frame::Text(_))));
+///         socket.send(Frame::Close(Some(4004))).await.unwrap();
+///     });
+///
+///     client.login().await.unwrap();
+///     assert!(client.run().await.is_err());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChannelTransport {
+    connections: mpsc::UnboundedSender<(String, ChannelSocket)>
+}
+
+/// Receives the connections opened through a [ChannelTransport]
+#[derive(Debug)]
+pub struct ChannelListener {
+    connections: mpsc::UnboundedReceiver<(String, ChannelSocket)>
+}
+
+/// One end of an in-memory connection. The client gets one end through
+/// [ChannelTransport], and the [ChannelListener] the other
+#[derive(Debug)]
+pub struct ChannelSocket {
+    sender: mpsc::UnboundedSender<Frame>,
+    receiver: mpsc::UnboundedReceiver<Frame>
+}
+
+impl ChannelTransport {
+    /// Creates a transport along with the listener which receives its connections
+    pub fn new() -> (Self, ChannelListener) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { connections: sender }, ChannelListener { connections: receiver })
+    }
+}
+
+impl ChannelListener {
+    /// Waits for the client to connect, returning the URL it connected to
+    /// along with the other end of the connection. Returns `None` once every
+    /// [ChannelTransport] was dropped
+    pub async fn accept(&mut self) -> Option<(String, ChannelSocket)> {
+        self.connections.recv().await
+    }
+}
+
+impl ChannelSocket {
+    /// Creates both ends of an in-memory connection
+    pub fn pair() -> (Self, Self) {
+        let (client_sender, server_receiver) = mpsc::unbounded_channel();
+        let (server_sender, client_receiver) = mpsc::unbounded_channel();
+
+        (
+            Self { sender: client_sender, receiver: client_receiver },
+            Self { sender: server_sender, receiver: server_receiver }
+        )
+    }
+}
+
+#[async_trait]
+impl GatewayTransport for ChannelTransport {
+    async fn connect(&self, url: &str) -> Result<Box<dyn GatewaySocket>, GatewayError> {
+        let (client, server) = ChannelSocket::pair();
+
+        self.connections
+            .send((url.to_string(), server))
+            .map_err(|_| GatewayError::Connection("Failed to connect to gateway"))?;
+
+        Ok(Box::new(client))
+    }
+}
+
+#[async_trait]
+impl GatewaySocket for ChannelSocket {
+    async fn send(&mut self, frame: Frame) -> Result<(), GatewayError> {
+        self.sender
+            .send(frame)
+            .map_err(|_| GatewayError::Connection("Failed to write to the gateway connection"))
+    }
+
+    async fn receive(&mut self) -> Option<Frame> {
+        self.receiver.recv().await
+    }
+
+    async fn close(&mut self, code: u16) {
+        let _ = self.sender.send(Frame::Close(Some(code)));
+        self.receiver.close();
+    }
+}
@@ -1,26 +1,111 @@
 use reqwest::Client as ReqwestClient;
 use serde::{Serialize, Deserialize};
 use std::ops::Index;
-use std::collections::HashMap;
-use tokio::sync::mpsc::{Sender, Receiver};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::cache::Cache;
+use crate::models::{Activity, Status};
+use super::codec::GatewayCodec;
+use super::compression::Compression;
+use super::errors::GatewayError;
+use super::handler::EventHandler;
+use super::intents::Intents;
+use super::recording::Recorder;
+use super::shard::ShardHandle;
+use super::shutdown::ShutdownHandle;
+use super::stream::EventQueue;
+use super::transport::GatewayTransport;
 
 pub struct Client {
-    /// A tuple of intents. First element is a bitfield equivalent to the bits
-    /// of the second element
-    pub intents: (u32, Vec<GatewayIntentBits>),
+    /// The intents sent when identifying
+    pub intents: Intents,
     /// A string representing the token used to connect to an applications's bot
     pub token: String,
-    pub cache: HashMap<String, serde_json::Value>,
+    /// The entities received from the gateway, kept up to date as events arrive
+    pub cache: Cache,
+    /// The current gateway session, populated once Discord sends READY
+    pub session: Arc<Mutex<Option<Session>>>,
+    /// The URL used to open new gateway sessions
+    pub gateway_url: String,
+    /// The base URL of the REST API, without a version
+    pub api_url: String,
+    /// The version of the gateway and REST APIs to use
+    pub api_version: u8,
+    /// How the gateway compresses the payloads it sends
+    pub compression: Compression,
+    /// How payloads are encoded on the gateway
+    pub codec: Arc<dyn GatewayCodec>,
+    /// Opens the connections to the gateway
+    pub transport: Arc<dyn GatewayTransport>,
+    /// Receives the events sent by the gateway
+    pub handler: Option<Arc<dyn EventHandler>>,
+    /// The presence the bot appears with as soon as it identifies
+    pub presence: Option<UpdatePresence>,
+    /// Events the bot relies on, checked against the intents when connecting
+    pub required_events: Vec<ReceiveEvent>,
+    /// Writes every payload received and sent, if recording
+    pub recorder: Option<Arc<Recorder>>,
+    /// Stops every gateway connection of the client
+    pub shutdown: ShutdownHandle,
     pub ws: WebsocketConnection
 }
 
+/// Used to configure a [Client] before it is created
+#[derive(Clone)]
+pub struct ClientBuilder {
+    pub intents: Intents,
+    pub token: String,
+    pub gateway_url: String,
+    pub api_url: String,
+    pub api_version: u8,
+    pub compression: Compression,
+    pub codec: Arc<dyn GatewayCodec>,
+    pub transport: Arc<dyn GatewayTransport>,
+    pub cache: Cache,
+    pub handler: Option<Arc<dyn EventHandler>>,
+    /// The presence sent along with every Identify
+    pub presence: Option<UpdatePresence>,
+    /// Events the bot relies on, checked against the intents when connecting
+    pub required_events: Vec<ReceiveEvent>,
+    pub recorder: Option<Arc<Recorder>>
+}
+
+/// The state needed to resume a gateway session after a disconnect
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// The id of the session given by the READY event
+    pub id: String,
+    /// The URL that must be used to resume this session
+    pub resume_gateway_url: String,
+    /// The sequence number of the last dispatch event received
+    pub sequence: Option<u32>
+}
+
 pub struct WebsocketConnection {
-    pub keepalive: Option<Sender<GatewayEvent>>,
-    pub receiver: Option<Receiver<GatewayEvent>>,
+    /// Sends commands through the gateway connection once logged in
+    pub shard: Option<ShardHandle>,
+    /// The task driving the gateway connection once logged in
+    pub(crate) task: Option<JoinHandle<Result<(), GatewayError>>>,
+    /// The buffer behind the [super::EventStream] handed out by [Client::events]
+    pub(crate) events: Option<Arc<EventQueue>>,
     /// Used to create HTTP requests to the discord API
     pub client: ReqwestClient
 }
 
+/// The response of GET /gateway/bot
+/// https://discord.com/developers/docs/topics/gateway#get-gateway-bot
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GatewayBot {
+    /// The URL to connect to the gateway with
+    pub url: String,
+    /// The recommended amount of shards to connect with
+    pub shards: u32,
+    pub session_start_limit: SessionStartLimitObject
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SessionStartLimitObject {
     pub total: i32,
     pub remaining: i32,
@@ -28,6 +113,18 @@ pub struct SessionStartLimitObject {
     pub max_concurrency: u16
 }
 
+/// The payload of a [GatewayOpCode::PresenceUpdate] command, also used as the
+/// initial presence of an Identify
+/// https://discord.com/developers/docs/topics/gateway-events#update-presence
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UpdatePresence {
+    /// Unix time in milliseconds of when the client went idle
+    pub since: Option<u64>,
+    pub activities: Vec<Activity>,
+    pub status: Status,
+    pub afk: bool
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GatewayEvent {
     pub op: usize,
@@ -36,69 +133,91 @@ pub struct GatewayEvent {
     pub t: Option<String>
 }
 
-//https://discord.com/developers/docs/topics/gateway#gateway-intents
-#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+/// A single intent, where the discriminant is the bit it sets. Use [super::Intents]
+/// to combine several of them
+/// https://discord.com/developers/docs/topics/gateway#gateway-intents
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum GatewayIntentBits {
-    Guilds,
-    GuildMembers,
-    GuildModeration,
-    GuildEmojisAndStickers,
-    GuildIntegrations,
-    GuildWebhooks,
-    GuildInvites,
-    GuildVoiceStates,
-    GuildPresences,
-    GuildMessages,
-    GuildMessageReactions,
-    GuildMessageTyping,
-    DirectMessages,
-    DirectmessageReactions,
-    MessageContent,
-    GuildScheduledEvents,
-    AutoModerationConfiguration,
-    AutoModerationExecution
+    Guilds = 0,
+    /// Privileged
+    GuildMembers = 1,
+    GuildModeration = 2,
+    GuildEmojisAndStickers = 3,
+    GuildIntegrations = 4,
+    GuildWebhooks = 5,
+    GuildInvites = 6,
+    GuildVoiceStates = 7,
+    /// Privileged
+    GuildPresences = 8,
+    GuildMessages = 9,
+    GuildMessageReactions = 10,
+    GuildMessageTyping = 11,
+    DirectMessages = 12,
+    DirectMessageReactions = 13,
+    DirectMessageTyping = 14,
+    /// Privileged
+    MessageContent = 15,
+    GuildScheduledEvents = 16,
+    // 17 through 19 are not used
+    AutoModerationConfiguration = 20,
+    AutoModerationExecution = 21
 }
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
 pub enum GatewayOpCode {
-    Dispatch,
-    Heartbeat,
-    Identify,
-    PresenceUpdate,
-    VoiceStateUpdate,
-    Resume,
-    Reconnect,
-    RequestGuildMembers,
-    InvalidSession,
-    Hello,
-    HeartbeatAcknowledge
+    Dispatch = 0,
+    Heartbeat = 1,
+    Identify = 2,
+    PresenceUpdate = 3,
+    VoiceStateUpdate = 4,
+    // 5 is purposefully skipped
+    Resume = 6,
+    Reconnect = 7,
+    RequestGuildMembers = 8,
+    InvalidSession = 9,
+    Hello = 10,
+    HeartbeatAcknowledge = 11
 }
 
+/// Every opcode by its value. 5 is purposefully skipped
+/// https://discord.com/developers/docs/topics/opcodes-and-status-codes#gateway-gateway-opcodes
+static GATEWAY_OPCODES: [Option<GatewayOpCode>; 12] = [
+    Some(GatewayOpCode::Dispatch),
+    Some(GatewayOpCode::Heartbeat),
+    Some(GatewayOpCode::Identify),
+    Some(GatewayOpCode::PresenceUpdate),
+    Some(GatewayOpCode::VoiceStateUpdate),
+    None,
+    Some(GatewayOpCode::Resume),
+    Some(GatewayOpCode::Reconnect),
+    Some(GatewayOpCode::RequestGuildMembers),
+    Some(GatewayOpCode::InvalidSession),
+    Some(GatewayOpCode::Hello),
+    Some(GatewayOpCode::HeartbeatAcknowledge)
+];
+
+impl GatewayOpCode {
+    /// The opcode with the given value, if it is one Discord documents
+    pub fn from_code(code: usize) -> Option<Self> {
+        GATEWAY_OPCODES.get(code).copied().flatten()
+    }
+}
+
+#[deprecated(note = "Use GatewayOpCode::from_code, which does not panic on unknown opcodes")]
 pub struct GatewayOpCodeIndexer;
+#[allow(deprecated)]
 impl Index<usize> for GatewayOpCodeIndexer {
     type Output = GatewayOpCode;
 
     fn index(&self, index: usize) -> &Self::Output {
-        match index {
-            0 => &GatewayOpCode::Dispatch,
-            1 => &GatewayOpCode::Heartbeat,
-            2 => &GatewayOpCode::Identify,
-            3 => &GatewayOpCode::PresenceUpdate,
-            4 => &GatewayOpCode::VoiceStateUpdate,
-            // 5 is purposefully skipped
-            // https://discord.com/developers/docs/topics/opcodes-and-status-codes#gateway-gateway-opcodes
-            6 => &GatewayOpCode::Resume,
-            7 => &GatewayOpCode::Reconnect,
-            8 => &GatewayOpCode::RequestGuildMembers,
-            9 => &GatewayOpCode::InvalidSession,
-            10 => &GatewayOpCode::Hello,
-            11 => &GatewayOpCode::HeartbeatAcknowledge,
-            _ => panic!("Index out of bounds"),
-        }
+        GATEWAY_OPCODES
+            .get(index)
+            .and_then(Option::as_ref)
+            .expect("Index out of bounds")
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum ReceiveEvent {
     Hello,
     Ready,
@@ -163,7 +282,9 @@ pub enum ReceiveEvent {
     UserUpdate,
     VoiceStateUpdate,
     VoiceServerUpdate,
-    WebhooksUpdate
+    WebhooksUpdate,
+    /// A dispatch this library does not know about yet
+    Unknown
 }
 
 pub struct ReceiveEventIndexer;
@@ -236,7 +357,7 @@ impl Index<&str> for ReceiveEventIndexer {
             "VOICE_STATE_UPDATE" => &ReceiveEvent::VoiceStateUpdate,
             "VOICE_SERVER_UPDATE" => &ReceiveEvent::VoiceServerUpdate,
             "WEBHOOKS_UPDATE" => &ReceiveEvent::WebhooksUpdate,
-            _ => panic!("Index out of bounds"),
+            _ => &ReceiveEvent::Unknown,
         }
     }
 }
\ No newline at end of file
@@ -1,6 +1,4 @@
 #![allow(dead_code)]
-use chrono::{Utc, DateTime, TimeZone};
-
 pub mod types;
 pub use types::{
     Embed,
@@ -14,16 +12,22 @@ pub use types::{
     EmbedVideo,
 };
 
+impl Default for Embed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Embed {
     /// Creates a rich Embed object
     /// # Example
     /// ```
-    /// use discord-rs::embed::Embed;
+    /// use discord_rs::embed::Embed;
     /// 
-    /// let embed = Embed::new();
+    /// let mut embed = Embed::new();
     /// embed.set_author("Discord-rs", None, None, None)
     ///     .set_title("A new rich embed")
-    ///     .set_description("A new rich embed has appeared")
+    ///     .set_description("A new rich embed has appeared");
     /// ```
     pub fn new() -> Self {
         Self {
@@ -154,6 +158,8 @@ impl Embed {
     /// # Examples
     ///
     /// ```
+    /// use discord_rs::embed::{Embed, EmbedField};
+    ///
     /// let mut embed = Embed::new();
     /// let field = EmbedField {
     ///     name: "Field Name".to_string(),
@@ -189,6 +195,8 @@ impl Embed {
     /// # Examples
     ///
     /// ```
+    /// use discord_rs::embed::{Embed, EmbedField};
+    ///
     /// let mut embed = Embed::new();
     /// let field1 = EmbedField {
     ///     name: "Field 1".to_string(),
@@ -229,6 +237,8 @@ impl Embed {
     /// # Examples
     ///
     /// ```
+    /// use discord_rs::embed::{Embed, EmbedField};
+    ///
     /// let mut embed = Embed::new();
     /// let field1 = EmbedField {
     ///     name: "Field 1".to_string(),
@@ -265,6 +275,8 @@ impl Embed {
     /// # Examples
     ///
     /// ```
+    /// use discord_rs::embed::{Embed, EmbedField};
+    ///
     /// let mut embed = Embed::new();
     /// let field1 = EmbedField {
     ///     name: "Field 1".to_string(),
@@ -277,10 +289,11 @@ impl Embed {
     ///     inline: Some(false),
     /// };
     /// embed.add_fields(&[field1, field2]);
-    /// embed.remove_field(1);
+    /// embed.remove_field(0);
     /// 
-    /// assert_eq!(embed.fields.len(), 0)
-    /// assert_eq!(embed.fields[0].name, "Field 2".to_string())
+    /// let fields = embed.fields.unwrap();
+    /// assert_eq!(fields.len(), 1);
+    /// assert_eq!(fields[0].name, "Field 2".to_string());
     /// ```
     pub fn remove_field(&mut self, index: usize) -> &mut Self {
         if let Some(ref mut fields) = self.fields {
@@ -353,9 +366,9 @@ impl Embed {
     /// # Examples
     ///
     /// ```
-    /// use discord-rs::embed::Embed;
+    /// use discord_rs::embed::Embed;
     ///
-    /// let source_embed = Embed::new()
+    /// let mut source_embed = Embed::new();
     /// source_embed
     ///     .set_title("Original Embed")
     ///     .set_description("This is the original embed.");
@@ -379,10 +392,10 @@ impl Embed {
     ///
     /// * `pretty` - Whether to include indenting and line-breaking the make the output more human friendly.
     pub fn to_json(&self, pretty: bool) -> String {
-        if pretty == true {
+        if pretty {
             return serde_json::to_string_pretty(self).expect("Could not stringify embed");
         }
 
-        return serde_json::to_string(self).expect("Could not stringify embed");
+        serde_json::to_string(self).expect("Could not stringify embed")
     }
 }
\ No newline at end of file
@@ -10,14 +10,21 @@
 //!
 //! ## Modules
 //!
+//! - `cache`: A typed cache of the guilds, channels, members and other entities sent by the gateway.
 //! - `client`: Provides a client implementation for connecting to the Discord API and handling events.
 //! - `embed`: Defines structures and utilities for creating and manipulating rich embeds.
+//! - `models`: Typed representations of the objects sent by the Discord API, such as guilds and messages.
+//! - `testing`: A mock gateway server for integration tests, behind the `testing` feature.
 //! - `util`: Contains utility functions and helpers used throughout the library.
 //! - `webhook`: Offers functionality for managing webhooks, including creation, deletion, and message sending.
 //!
 //! For detailed usage examples, please refer to the documentation of each module.
 
+pub mod cache;
 pub mod client;
 pub mod embed;
+pub mod models;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod util;
 pub mod webhook;
\ No newline at end of file
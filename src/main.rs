@@ -1,12 +1,7 @@
-#[allow(dead_code)]
-pub mod client;
-pub mod embed;
-pub mod util;
-pub mod webhook;
-
+#[path = "../examples/client/mod.rs"]
 mod example;
 
 #[tokio::main]
 async fn main() {
     example::main().await;
-}
\ No newline at end of file
+}
@@ -0,0 +1,91 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use super::{GuildMember, Snowflake, User};
+
+/// https://discord.com/developers/docs/resources/channel#channel-object
+///
+/// Threads are channels too, and deleted threads are only sent with their
+/// `id`, `guild_id`, `parent_id` and `channel_type`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Channel {
+    pub id: Snowflake,
+    #[serde(rename = "type")]
+    pub channel_type: u8,
+    pub guild_id: Option<Snowflake>,
+    pub position: Option<i32>,
+    pub permission_overwrites: Option<Vec<PermissionOverwrite>>,
+    pub name: Option<String>,
+    pub topic: Option<String>,
+    pub nsfw: Option<bool>,
+    pub last_message_id: Option<Snowflake>,
+    pub bitrate: Option<u32>,
+    pub user_limit: Option<u32>,
+    pub rate_limit_per_user: Option<u32>,
+    pub recipients: Option<Vec<User>>,
+    pub icon: Option<String>,
+    pub owner_id: Option<Snowflake>,
+    pub application_id: Option<Snowflake>,
+    pub parent_id: Option<Snowflake>,
+    pub last_pin_timestamp: Option<String>,
+    pub rtc_region: Option<String>,
+    pub video_quality_mode: Option<u8>,
+    pub message_count: Option<u32>,
+    pub member_count: Option<u32>,
+    pub thread_metadata: Option<ThreadMetadata>,
+    pub member: Option<ThreadMember>,
+    pub default_auto_archive_duration: Option<u32>,
+    pub permissions: Option<String>,
+    pub flags: Option<u64>,
+    pub total_message_sent: Option<u32>,
+    pub available_tags: Option<Vec<Value>>,
+    pub applied_tags: Option<Vec<Snowflake>>,
+    pub default_reaction_emoji: Option<Value>,
+    pub default_thread_rate_limit_per_user: Option<u32>,
+    pub default_sort_order: Option<u8>,
+    pub default_forum_layout: Option<u8>
+}
+
+/// https://discord.com/developers/docs/resources/channel#overwrite-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PermissionOverwrite {
+    pub id: Snowflake,
+    /// 0 for a role and 1 for a member
+    #[serde(rename = "type")]
+    pub overwrite_type: u8,
+    pub allow: String,
+    pub deny: String
+}
+
+/// https://discord.com/developers/docs/resources/channel#thread-metadata-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ThreadMetadata {
+    pub archived: bool,
+    pub auto_archive_duration: u32,
+    pub archive_timestamp: String,
+    pub locked: bool,
+    pub invitable: Option<bool>,
+    pub create_timestamp: Option<String>
+}
+
+/// https://discord.com/developers/docs/resources/channel#thread-member-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ThreadMember {
+    pub id: Option<Snowflake>,
+    pub user_id: Option<Snowflake>,
+    pub join_timestamp: String,
+    pub flags: u64,
+    pub member: Option<GuildMember>
+}
+
+/// https://discord.com/developers/docs/resources/stage-instance#stage-instance-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StageInstance {
+    pub id: Snowflake,
+    pub guild_id: Snowflake,
+    pub channel_id: Snowflake,
+    pub topic: String,
+    pub privacy_level: u8,
+    pub discoverable_disabled: Option<bool>,
+    pub guild_scheduled_event_id: Option<Snowflake>
+}
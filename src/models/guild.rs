@@ -0,0 +1,218 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use super::{Channel, Presence, Snowflake, StageInstance, User, VoiceState};
+
+/// https://discord.com/developers/docs/resources/guild#guild-object
+///
+/// The fields after `safety_alerts_channel_id` are only sent with GUILD_CREATE
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Guild {
+    pub id: Snowflake,
+    pub name: String,
+    pub icon: Option<String>,
+    pub icon_hash: Option<String>,
+    pub splash: Option<String>,
+    pub discovery_splash: Option<String>,
+    pub owner: Option<bool>,
+    pub owner_id: Snowflake,
+    pub permissions: Option<String>,
+    pub afk_channel_id: Option<Snowflake>,
+    pub afk_timeout: Option<u32>,
+    pub widget_enabled: Option<bool>,
+    pub widget_channel_id: Option<Snowflake>,
+    pub verification_level: Option<u8>,
+    pub default_message_notifications: Option<u8>,
+    pub explicit_content_filter: Option<u8>,
+    #[serde(default)]
+    pub roles: Vec<Role>,
+    #[serde(default)]
+    pub emojis: Vec<Emoji>,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub mfa_level: Option<u8>,
+    pub application_id: Option<Snowflake>,
+    pub system_channel_id: Option<Snowflake>,
+    pub system_channel_flags: Option<u64>,
+    pub rules_channel_id: Option<Snowflake>,
+    pub max_presences: Option<u32>,
+    pub max_members: Option<u32>,
+    pub vanity_url_code: Option<String>,
+    pub description: Option<String>,
+    pub banner: Option<String>,
+    pub premium_tier: Option<u8>,
+    pub premium_subscription_count: Option<u32>,
+    pub preferred_locale: Option<String>,
+    pub public_updates_channel_id: Option<Snowflake>,
+    pub max_video_channel_users: Option<u32>,
+    pub approximate_member_count: Option<u32>,
+    pub approximate_presence_count: Option<u32>,
+    pub nsfw_level: Option<u8>,
+    pub stickers: Option<Vec<Sticker>>,
+    pub premium_progress_bar_enabled: Option<bool>,
+    pub safety_alerts_channel_id: Option<Snowflake>,
+    pub joined_at: Option<String>,
+    pub large: Option<bool>,
+    pub unavailable: Option<bool>,
+    pub member_count: Option<u32>,
+    pub voice_states: Option<Vec<VoiceState>>,
+    pub members: Option<Vec<GuildMember>>,
+    pub channels: Option<Vec<Channel>>,
+    pub threads: Option<Vec<Channel>>,
+    pub presences: Option<Vec<Presence>>,
+    pub stage_instances: Option<Vec<StageInstance>>,
+    pub guild_scheduled_events: Option<Vec<GuildScheduledEvent>>
+}
+
+/// A guild which is either still loading or affected by an outage
+/// https://discord.com/developers/docs/resources/guild#unavailable-guild-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnavailableGuild {
+    pub id: Snowflake,
+    /// `None` when the bot was removed from the guild
+    pub unavailable: Option<bool>
+}
+
+/// https://discord.com/developers/docs/resources/guild#guild-member-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildMember {
+    pub user: Option<User>,
+    pub nick: Option<String>,
+    pub avatar: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<Snowflake>,
+    pub joined_at: Option<String>,
+    pub premium_since: Option<String>,
+    #[serde(default)]
+    pub deaf: bool,
+    #[serde(default)]
+    pub mute: bool,
+    pub flags: Option<u64>,
+    pub pending: Option<bool>,
+    pub permissions: Option<String>,
+    pub communication_disabled_until: Option<String>
+}
+
+/// https://discord.com/developers/docs/topics/permissions#role-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Role {
+    pub id: Snowflake,
+    pub name: String,
+    pub color: u32,
+    pub hoist: bool,
+    pub icon: Option<String>,
+    pub unicode_emoji: Option<String>,
+    pub position: i32,
+    pub permissions: String,
+    pub managed: bool,
+    pub mentionable: bool,
+    pub tags: Option<Value>,
+    pub flags: Option<u64>
+}
+
+/// https://discord.com/developers/docs/resources/emoji#emoji-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Emoji {
+    /// `None` for standard unicode emojis
+    pub id: Option<Snowflake>,
+    pub name: Option<String>,
+    pub roles: Option<Vec<Snowflake>>,
+    pub user: Option<User>,
+    pub require_colons: Option<bool>,
+    pub managed: Option<bool>,
+    pub animated: Option<bool>,
+    pub available: Option<bool>
+}
+
+/// https://discord.com/developers/docs/resources/sticker#sticker-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Sticker {
+    pub id: Snowflake,
+    pub pack_id: Option<Snowflake>,
+    pub name: String,
+    pub description: Option<String>,
+    pub tags: String,
+    #[serde(rename = "type")]
+    pub sticker_type: u8,
+    pub format_type: u8,
+    pub available: Option<bool>,
+    pub guild_id: Option<Snowflake>,
+    pub user: Option<User>,
+    pub sort_value: Option<u32>
+}
+
+/// https://discord.com/developers/docs/resources/guild#integration-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Integration {
+    pub id: Snowflake,
+    /// Only sent with integration gateway events
+    pub guild_id: Option<Snowflake>,
+    pub name: String,
+    /// Either "twitch", "youtube", "discord" or "guild_subscription"
+    #[serde(rename = "type")]
+    pub integration_type: String,
+    pub enabled: bool,
+    pub syncing: Option<bool>,
+    pub role_id: Option<Snowflake>,
+    pub enable_emoticons: Option<bool>,
+    pub expire_behavior: Option<u8>,
+    pub expire_grace_period: Option<u32>,
+    pub user: Option<User>,
+    pub account: Value,
+    pub synced_at: Option<String>,
+    pub subscriber_count: Option<u32>,
+    pub revoked: Option<bool>,
+    pub application: Option<Value>,
+    pub scopes: Option<Vec<String>>
+}
+
+/// https://discord.com/developers/docs/resources/audit-log#audit-log-entry-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditLogEntry {
+    pub id: Snowflake,
+    /// Only sent with GUILD_AUDIT_LOG_ENTRY_CREATE
+    pub guild_id: Option<Snowflake>,
+    pub target_id: Option<String>,
+    pub changes: Option<Vec<Value>>,
+    pub user_id: Option<Snowflake>,
+    pub action_type: u32,
+    pub options: Option<Value>,
+    pub reason: Option<String>
+}
+
+/// https://discord.com/developers/docs/resources/guild-scheduled-event#guild-scheduled-event-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuildScheduledEvent {
+    pub id: Snowflake,
+    pub guild_id: Snowflake,
+    pub channel_id: Option<Snowflake>,
+    pub creator_id: Option<Snowflake>,
+    pub name: String,
+    pub description: Option<String>,
+    pub scheduled_start_time: String,
+    pub scheduled_end_time: Option<String>,
+    pub privacy_level: u8,
+    pub status: u8,
+    pub entity_type: u8,
+    pub entity_id: Option<Snowflake>,
+    pub entity_metadata: Option<Value>,
+    pub creator: Option<User>,
+    pub user_count: Option<u32>,
+    pub image: Option<String>
+}
+
+/// https://discord.com/developers/docs/resources/auto-moderation#auto-moderation-rule-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AutoModerationRule {
+    pub id: Snowflake,
+    pub guild_id: Snowflake,
+    pub name: String,
+    pub creator_id: Snowflake,
+    pub event_type: u8,
+    pub trigger_type: u8,
+    pub trigger_metadata: Value,
+    pub actions: Vec<Value>,
+    pub enabled: bool,
+    pub exempt_roles: Vec<Snowflake>,
+    pub exempt_channels: Vec<Snowflake>
+}
@@ -0,0 +1,30 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use super::{Channel, GuildMember, Message, Snowflake, User};
+
+/// https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Interaction {
+    pub id: Snowflake,
+    pub application_id: Snowflake,
+    #[serde(rename = "type")]
+    pub interaction_type: u8,
+    /// The command, component or modal data, which depends on the interaction type
+    pub data: Option<Value>,
+    pub guild_id: Option<Snowflake>,
+    pub channel: Option<Channel>,
+    pub channel_id: Option<Snowflake>,
+    /// Sent when the interaction is invoked in a guild
+    pub member: Option<GuildMember>,
+    /// Sent when the interaction is invoked in a direct message
+    pub user: Option<User>,
+    pub token: String,
+    pub version: u8,
+    pub message: Option<Box<Message>>,
+    pub app_permissions: Option<String>,
+    pub locale: Option<String>,
+    pub guild_locale: Option<String>,
+    #[serde(default)]
+    pub entitlements: Vec<Value>
+}
@@ -0,0 +1,63 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use super::{Channel, Emoji, GuildMember, Snowflake, User};
+
+/// https://discord.com/developers/docs/resources/channel#message-object
+///
+/// Message updates may only contain the fields which changed, which is why
+/// everything but the ids is optional
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Message {
+    pub id: Snowflake,
+    pub channel_id: Snowflake,
+    pub guild_id: Option<Snowflake>,
+    pub author: Option<User>,
+    pub member: Option<GuildMember>,
+    pub content: Option<String>,
+    pub timestamp: Option<String>,
+    pub edited_timestamp: Option<String>,
+    pub tts: Option<bool>,
+    pub mention_everyone: Option<bool>,
+    pub mentions: Option<Vec<User>>,
+    pub mention_roles: Option<Vec<Snowflake>>,
+    pub attachments: Option<Vec<Attachment>>,
+    pub embeds: Option<Vec<Value>>,
+    pub reactions: Option<Vec<Reaction>>,
+    pub nonce: Option<Value>,
+    pub pinned: Option<bool>,
+    pub webhook_id: Option<Snowflake>,
+    #[serde(rename = "type")]
+    pub message_type: Option<u8>,
+    pub application_id: Option<Snowflake>,
+    pub flags: Option<u64>,
+    pub message_reference: Option<Value>,
+    pub referenced_message: Option<Box<Message>>,
+    pub thread: Option<Channel>,
+    pub components: Option<Vec<Value>>,
+    pub sticker_items: Option<Vec<Value>>,
+    pub position: Option<u64>
+}
+
+/// https://discord.com/developers/docs/resources/channel#attachment-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub id: Snowflake,
+    pub filename: String,
+    pub description: Option<String>,
+    pub content_type: Option<String>,
+    pub size: u64,
+    pub url: String,
+    pub proxy_url: String,
+    pub height: Option<u32>,
+    pub width: Option<u32>,
+    pub ephemeral: Option<bool>
+}
+
+/// https://discord.com/developers/docs/resources/channel#reaction-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Reaction {
+    pub count: u32,
+    pub me: bool,
+    pub emoji: Emoji
+}
@@ -0,0 +1,36 @@
+//! Typed representations of the objects sent by the Discord API
+//!
+//! Fields which Discord may omit, either because they are optional or because
+//! the object was sent partially, are represented as [Option]s. Nested objects
+//! which are rarely inspected are kept as raw [serde_json::Value]s
+
+pub mod channel;
+pub mod guild;
+pub mod interaction;
+pub mod message;
+pub mod presence;
+pub mod user;
+pub mod voice;
+
+pub use channel::{Channel, PermissionOverwrite, StageInstance, ThreadMember, ThreadMetadata};
+pub use guild::{
+    AuditLogEntry,
+    AutoModerationRule,
+    Emoji,
+    Guild,
+    GuildMember,
+    GuildScheduledEvent,
+    Integration,
+    Role,
+    Sticker,
+    UnavailableGuild
+};
+pub use interaction::Interaction;
+pub use message::{Attachment, Message, Reaction};
+pub use presence::{Activity, ActivityType, ClientStatus, Presence, Status};
+pub use user::User;
+pub use voice::VoiceState;
+
+/// A unique Discord id, sent as a string by the API
+/// https://discord.com/developers/docs/reference#snowflakes
+pub type Snowflake = String;
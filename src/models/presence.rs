@@ -0,0 +1,192 @@
+use serde::{Serialize, Deserialize, Deserializer, Serializer};
+use serde_json::Value;
+
+use super::{Emoji, Snowflake, User};
+
+/// https://discord.com/developers/docs/topics/gateway-events#presence-update
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Presence {
+    /// Only the `id` of the user is guaranteed to be present
+    pub user: User,
+    pub guild_id: Option<Snowflake>,
+    pub status: Status,
+    #[serde(default)]
+    pub activities: Vec<Activity>,
+    pub client_status: Option<ClientStatus>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#client-status-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClientStatus {
+    pub desktop: Option<Status>,
+    pub mobile: Option<Status>,
+    pub web: Option<Status>
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#update-presence-status-types
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Online,
+    /// Do not disturb
+    Dnd,
+    Idle,
+    /// Shown as offline to others. Only ever sent, never received
+    Invisible,
+    Offline
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#activity-object-activity-types
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ActivityType {
+    /// Playing {name}
+    Playing,
+    /// Streaming {details}
+    Streaming,
+    /// Listening to {name}
+    Listening,
+    /// Watching {name}
+    Watching,
+    /// {emoji} {state}
+    Custom,
+    /// Competing in {name}
+    Competing,
+    /// A type this library does not know about yet
+    Unknown(u8)
+}
+
+impl ActivityType {
+    /// The value Discord uses for this type
+    pub fn code(&self) -> u8 {
+        match self {
+            ActivityType::Playing => 0,
+            ActivityType::Streaming => 1,
+            ActivityType::Listening => 2,
+            ActivityType::Watching => 3,
+            ActivityType::Custom => 4,
+            ActivityType::Competing => 5,
+            ActivityType::Unknown(code) => *code,
+        }
+    }
+}
+
+impl From<u8> for ActivityType {
+    fn from(code: u8) -> Self {
+        match code {
+            0 => ActivityType::Playing,
+            1 => ActivityType::Streaming,
+            2 => ActivityType::Listening,
+            3 => ActivityType::Watching,
+            4 => ActivityType::Custom,
+            5 => ActivityType::Competing,
+            _ => ActivityType::Unknown(code),
+        }
+    }
+}
+
+impl Serialize for ActivityType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for ActivityType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u8::deserialize(deserializer).map(ActivityType::from)
+    }
+}
+
+/// https://discord.com/developers/docs/topics/gateway-events#activity-object
+///
+/// Bots may only send the `name`, `activity_type`, `url` and `state` of an
+/// activity. The other fields are only sent by Discord
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Activity {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub activity_type: ActivityType,
+    /// The stream URL, only used with [ActivityType::Streaming]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing)]
+    pub created_at: Option<u64>,
+    #[serde(skip_serializing)]
+    pub timestamps: Option<Value>,
+    #[serde(skip_serializing)]
+    pub application_id: Option<Snowflake>,
+    #[serde(skip_serializing)]
+    pub details: Option<String>,
+    #[serde(skip_serializing)]
+    pub emoji: Option<Emoji>,
+    #[serde(skip_serializing)]
+    pub party: Option<Value>,
+    #[serde(skip_serializing)]
+    pub assets: Option<Value>,
+    #[serde(skip_serializing)]
+    pub secrets: Option<Value>,
+    #[serde(skip_serializing)]
+    pub instance: Option<bool>,
+    #[serde(skip_serializing)]
+    pub flags: Option<u64>,
+    #[serde(skip_serializing)]
+    pub buttons: Option<Vec<Value>>
+}
+
+impl Activity {
+    /// Creates an activity which only has a name and a type
+    pub fn new(activity_type: ActivityType, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            activity_type,
+            url: None,
+            state: None,
+            created_at: None,
+            timestamps: None,
+            application_id: None,
+            details: None,
+            emoji: None,
+            party: None,
+            assets: None,
+            secrets: None,
+            instance: None,
+            flags: None,
+            buttons: None
+        }
+    }
+
+    /// Shown as "Playing {name}"
+    pub fn playing(name: &str) -> Self {
+        Self::new(ActivityType::Playing, name)
+    }
+
+    /// Shown as "Streaming {name}". Only Twitch and YouTube URLs are supported
+    pub fn streaming(name: &str, url: &str) -> Self {
+        let mut activity = Self::new(ActivityType::Streaming, name);
+        activity.url = Some(url.to_string());
+        activity
+    }
+
+    /// Shown as "Listening to {name}"
+    pub fn listening(name: &str) -> Self {
+        Self::new(ActivityType::Listening, name)
+    }
+
+    /// Shown as "Watching {name}"
+    pub fn watching(name: &str) -> Self {
+        Self::new(ActivityType::Watching, name)
+    }
+
+    /// Shown as a custom status with the given text
+    pub fn custom(state: &str) -> Self {
+        let mut activity = Self::new(ActivityType::Custom, "Custom Status");
+        activity.state = Some(state.to_string());
+        activity
+    }
+
+    /// Shown as "Competing in {name}"
+    pub fn competing(name: &str) -> Self {
+        Self::new(ActivityType::Competing, name)
+    }
+}
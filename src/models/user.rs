@@ -0,0 +1,26 @@
+use serde::{Serialize, Deserialize};
+
+use super::Snowflake;
+
+/// https://discord.com/developers/docs/resources/user#user-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct User {
+    pub id: Snowflake,
+    /// Empty when the user was sent partially, such as in presence updates
+    #[serde(default)]
+    pub username: String,
+    pub discriminator: Option<String>,
+    pub global_name: Option<String>,
+    pub avatar: Option<String>,
+    pub bot: Option<bool>,
+    pub system: Option<bool>,
+    pub mfa_enabled: Option<bool>,
+    pub banner: Option<String>,
+    pub accent_color: Option<u32>,
+    pub locale: Option<String>,
+    pub verified: Option<bool>,
+    pub email: Option<String>,
+    pub flags: Option<u64>,
+    pub premium_type: Option<u8>,
+    pub public_flags: Option<u64>
+}
@@ -0,0 +1,22 @@
+use serde::{Serialize, Deserialize};
+
+use super::{GuildMember, Snowflake};
+
+/// https://discord.com/developers/docs/resources/voice#voice-state-object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoiceState {
+    pub guild_id: Option<Snowflake>,
+    /// `None` when the user left the voice channel
+    pub channel_id: Option<Snowflake>,
+    pub user_id: Snowflake,
+    pub member: Option<GuildMember>,
+    pub session_id: String,
+    pub deaf: bool,
+    pub mute: bool,
+    pub self_deaf: bool,
+    pub self_mute: bool,
+    pub self_stream: Option<bool>,
+    pub self_video: bool,
+    pub suppress: bool,
+    pub request_to_speak_timestamp: Option<String>
+}
@@ -0,0 +1,435 @@
+//! A mock of the Discord gateway for integration tests, only available with
+//! the `testing` feature
+//!
+//! # Example
+//! ```
+//! use discord_rs::client::{Backpressure, ClientBuilder, Event, GatewayIntentBits};
+//! use discord_rs::testing::MockGateway;
+//! use futures_util::StreamExt;
+//! use serde_json::json;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mut gateway = MockGateway::start().await;
+//!     let mut client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+//!         .with_gateway_url(gateway.url())
+//!         .with_api_url("http://127.0.0.1:9")
+//!         .build();
+//!
+//!     let mut events = client.events(16, Backpressure::Block);
+//!     client.login().await.unwrap();
+//!
+//!     let connection = gateway.next_connection().await.unwrap();
+//!     connection.dispatch("CHANNEL_PINS_UPDATE", json!({ "channel_id": "1", "last_pin_timestamp": null }));
+//!
+//!     assert!(matches!(events.next().await, Some(Event::Hello(_))));
+//!     assert!(matches!(events.next().await, Some(Event::Ready(_))));
+//!     assert!(matches!(events.next().await, Some(Event::ChannelPinsUpdate(_))));
+//! }
+//! ```
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+use crate::client::{GatewayEvent, GatewayOpCode};
+
+pub mod types;
+pub use types::{
+    Handshake,
+    MockConnection,
+    MockGateway,
+    MockGatewayBuilder
+};
+use types::{Control, MockState};
+
+type Socket = WebSocketStream<TcpStream>;
+
+impl Default for MockGatewayBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockGatewayBuilder {
+    pub fn new() -> Self {
+        Self {
+            token: None,
+            heartbeat_interval: 45000
+        }
+    }
+
+    /// Only accepts connections which identify or resume with this token,
+    /// closing any other with 4004
+    pub fn with_token(&mut self, token: &str) -> &mut Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Sets the heartbeat interval in milliseconds sent to clients. Defaults to 45 seconds
+    pub fn with_heartbeat_interval(&mut self, interval: u64) -> &mut Self {
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    /// Starts listening on a random local port
+    ///
+    /// # Panics
+    /// * If no local port can be bound
+    pub async fn start(&self) -> MockGateway {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock gateway");
+
+        let url = format!("ws://{}", listener.local_addr().expect("Failed to bind mock gateway"));
+        let state = Arc::new(MockState {
+            url: url.to_owned(),
+            token: self.token.clone(),
+            heartbeat_interval: self.heartbeat_interval,
+            sessions: Mutex::new(HashMap::new()),
+            next_session: AtomicU64::new(1)
+        });
+
+        let (sender, connections) = mpsc::unbounded_channel();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, Arc::clone(&state), sender.clone()));
+            }
+        });
+
+        MockGateway { url, connections, task }
+    }
+}
+
+impl MockGateway {
+    /// Starts a mock gateway with the default configuration, see [MockGatewayBuilder]
+    pub async fn start() -> Self {
+        MockGatewayBuilder::new().start().await
+    }
+
+    /// The URL to pass to [crate::client::ClientBuilder::with_gateway_url]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Waits for the next client to complete its handshake. Connections which
+    /// fail the handshake are closed and never handed out
+    pub async fn next_connection(&mut self) -> Option<MockConnection> {
+        self.connections.recv().await
+    }
+}
+
+impl Drop for MockGateway {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl MockConnection {
+    /// Sends a dispatch event, such as `MESSAGE_CREATE`, with the next sequence number
+    pub fn dispatch(&self, event: &str, data: Value) {
+        let _ = self.control.send(Control::Dispatch(event.to_string(), data));
+    }
+
+    /// Sends any payload as is
+    pub fn send(&self, event: GatewayEvent) {
+        let _ = self.control.send(Control::Send(event));
+    }
+
+    /// Asks the client to reconnect and resume through a [GatewayOpCode::Reconnect]
+    pub fn reconnect(&self) {
+        self.send(payload(GatewayOpCode::Reconnect, None));
+    }
+
+    /// Tells the client its session is invalid through a [GatewayOpCode::InvalidSession]
+    pub fn invalid_session(&self, resumable: bool) {
+        self.send(payload(GatewayOpCode::InvalidSession, Some(json!(resumable))));
+    }
+
+    /// Closes the connection with a close code, such as 4004 for a failed authentication
+    pub fn close(&self, code: u16) {
+        let _ = self.control.send(Control::Close(code));
+    }
+
+    /// Whether heartbeats are acknowledged. Without acknowledgements, the
+    /// client will consider the connection zombied and reconnect
+    pub fn acknowledge_heartbeats(&self, acknowledge: bool) {
+        self.acknowledge.store(acknowledge, Ordering::Release);
+    }
+
+    /// How many heartbeats the client has sent on this connection
+    pub fn heartbeats(&self) -> u32 {
+        self.heartbeats.load(Ordering::Acquire)
+    }
+
+    /// Waits for the next payload sent by the client other than a heartbeat,
+    /// such as a presence update. Returns `None` once the client disconnects
+    pub async fn next_command(&mut self) -> Option<GatewayEvent> {
+        self.commands.recv().await
+    }
+
+    /// Waits for the client to disconnect, returning the close code it sent if any
+    pub async fn disconnected(&mut self) -> Option<u16> {
+        match self.closed.take() {
+            Some(closed) => closed.await.ok().flatten(),
+            None => None,
+        }
+    }
+}
+
+fn payload(op: GatewayOpCode, d: Option<Value>) -> GatewayEvent {
+    GatewayEvent { op: op as usize, d, s: None, t: None }
+}
+
+async fn send(socket: &mut Socket, event: &GatewayEvent) -> Result<(), ()> {
+    let text = serde_json::to_string(event).map_err(|_| ())?;
+    socket.send(Message::Text(text)).await.map_err(|_| ())
+}
+
+async fn close(socket: &mut Socket, code: u16) {
+    let frame = CloseFrame { code: CloseCode::from(code), reason: "".into() };
+    let _ = socket.close(Some(frame)).await;
+}
+
+/// Reads the next text payload, skipping pings
+async fn receive(socket: &mut Socket) -> Incoming {
+    loop {
+        match socket.next().await {
+            Some(Ok(Message::Text(text))) => {
+                return serde_json::from_str(&text).map_or(Incoming::Invalid, Incoming::Payload);
+            },
+            Some(Ok(Message::Binary(_))) => return Incoming::Invalid,
+            Some(Ok(Message::Close(frame))) => return Incoming::Closed(frame.map(|frame| u16::from(frame.code))),
+            Some(Ok(_)) => {},
+            _ => return Incoming::Closed(None),
+        }
+    }
+}
+
+enum Incoming {
+    Payload(GatewayEvent),
+    /// The payload could not be decoded
+    Invalid,
+    Closed(Option<u16>)
+}
+
+/// Checks the first payload of a connection, returning the close code to reject it with
+fn validate(state: &MockState, event: &GatewayEvent) -> Result<Handshake, u16> {
+    let data = event.d.clone().unwrap_or(Value::Null);
+    let handshake = match GatewayOpCode::from_code(event.op) {
+        Some(GatewayOpCode::Identify) => {
+            let valid = data["token"].is_string()
+                && data["intents"].is_u64()
+                && data["properties"].is_object();
+
+            if !valid {
+                return Err(4002);
+            }
+
+            if let Some(shard) = data.get("shard") {
+                match (shard[0].as_u64(), shard[1].as_u64()) {
+                    (Some(id), Some(total)) if id < total => {},
+                    _ => return Err(4010),
+                }
+            }
+
+            Handshake::Identify(data)
+        },
+        Some(GatewayOpCode::Resume) => {
+            let valid = data["token"].is_string()
+                && data["session_id"].is_string()
+                && (data["seq"].is_u64() || data["seq"].is_null());
+
+            if !valid {
+                return Err(4002);
+            }
+
+            Handshake::Resume(data)
+        },
+        // Anything sent before identifying
+        _ => return Err(4003),
+    };
+
+    let token = match &handshake {
+        Handshake::Identify(data) | Handshake::Resume(data) => data["token"].as_str(),
+    };
+
+    match (&state.token, token) {
+        (Some(expected), Some(token)) if expected != token => Err(4004),
+        _ => Ok(handshake),
+    }
+}
+
+/// Serves a single client from the Hello until it disconnects
+async fn serve(stream: TcpStream, state: Arc<MockState>, connections: mpsc::UnboundedSender<MockConnection>) {
+    let Ok(mut socket) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+
+    let hello = payload(GatewayOpCode::Hello, Some(json!({ "heartbeat_interval": state.heartbeat_interval })));
+    if send(&mut socket, &hello).await.is_err() {
+        return;
+    }
+
+    let handshake = match receive(&mut socket).await {
+        Incoming::Payload(event) => match validate(&state, &event) {
+            Ok(handshake) => handshake,
+            Err(code) => return close(&mut socket, code).await,
+        },
+        Incoming::Invalid => return close(&mut socket, 4002).await,
+        Incoming::Closed(_) => return,
+    };
+
+    let session_id = match &handshake {
+        Handshake::Identify(data) => {
+            let id = format!("mock-session-{}", state.next_session.fetch_add(1, Ordering::AcqRel));
+            state.sessions.lock().unwrap().insert(id.to_owned(), Vec::new());
+
+            let ready = json!({
+                "v": 10,
+                "user": { "id": "1", "username": "mock", "discriminator": "0", "bot": true },
+                "guilds": [],
+                "session_id": id,
+                "resume_gateway_url": state.url,
+                "shard": data.get("shard"),
+                "application": { "id": "1", "flags": 0 }
+            });
+
+            if dispatch(&mut socket, &state, &id, "READY", ready).await.is_err() {
+                return;
+            }
+
+            id
+        },
+        Handshake::Resume(data) => {
+            let id = data["session_id"].as_str().unwrap_or_default().to_string();
+
+            let missed = state.sessions
+                .lock()
+                .unwrap()
+                .get(&id)
+                .map(|dispatches| missed(dispatches, &data["seq"]));
+
+            let missed = match missed {
+                Some(Ok(missed)) => missed,
+                Some(Err(code)) => return close(&mut socket, code).await,
+                None => {
+                    // The client is expected to identify again on a new connection
+                    let _ = send(&mut socket, &payload(GatewayOpCode::InvalidSession, Some(json!(false)))).await;
+                    return close(&mut socket, 4009).await;
+                },
+            };
+
+            for event in &missed {
+                if send(&mut socket, event).await.is_err() {
+                    return;
+                }
+            }
+
+            if dispatch(&mut socket, &state, &id, "RESUMED", Value::Null).await.is_err() {
+                return;
+            }
+
+            id
+        },
+    };
+
+    let (control, mut controls) = mpsc::unbounded_channel();
+    let (commands, receiver) = mpsc::unbounded_channel();
+    let (closed, closed_receiver) = oneshot::channel();
+    let heartbeats = Arc::new(AtomicU32::new(0));
+    let acknowledge = Arc::new(AtomicBool::new(true));
+
+    let _ = connections.send(MockConnection {
+        handshake,
+        session_id: session_id.to_owned(),
+        control,
+        commands: receiver,
+        closed: Some(closed_receiver),
+        heartbeats: Arc::clone(&heartbeats),
+        acknowledge: Arc::clone(&acknowledge)
+    });
+
+    let mut controls_open = true;
+    let code = loop {
+        tokio::select! {
+            control = controls.recv(), if controls_open => {
+                let sent = match control {
+                    Some(Control::Dispatch(event, data)) => dispatch(&mut socket, &state, &session_id, &event, data).await,
+                    Some(Control::Send(event)) => send(&mut socket, &event).await,
+                    Some(Control::Close(code)) => {
+                        close(&mut socket, code).await;
+                        break None;
+                    },
+                    None => {
+                        controls_open = false;
+                        Ok(())
+                    },
+                };
+
+                if sent.is_err() {
+                    break None;
+                }
+            },
+            incoming = receive(&mut socket) => match incoming {
+                Incoming::Payload(event) if event.op == GatewayOpCode::Heartbeat as usize => {
+                    heartbeats.fetch_add(1, Ordering::AcqRel);
+
+                    if acknowledge.load(Ordering::Acquire) {
+                        let ack = payload(GatewayOpCode::HeartbeatAcknowledge, None);
+                        if send(&mut socket, &ack).await.is_err() {
+                            break None;
+                        }
+                    }
+                },
+                Incoming::Payload(event) => {
+                    let _ = commands.send(event);
+                },
+                Incoming::Invalid => {
+                    close(&mut socket, 4002).await;
+                    break None;
+                },
+                Incoming::Closed(code) => break code,
+            },
+        }
+    };
+
+    let _ = closed.send(code);
+}
+
+/// The dispatches a resume with the given sequence number missed, or the
+/// close code to reject it with if the sequence number was never sent
+fn missed(dispatches: &[GatewayEvent], sequence: &Value) -> Result<Vec<GatewayEvent>, u16> {
+    match sequence.as_u64() {
+        Some(sequence) if sequence <= dispatches.len() as u64 => Ok(dispatches[sequence as usize..].to_vec()),
+        _ => Err(4007),
+    }
+}
+
+/// Sends a dispatch with the next sequence number of a session
+async fn dispatch(socket: &mut Socket, state: &MockState, session_id: &str, event: &str, data: Value) -> Result<(), ()> {
+    let event = {
+        let mut sessions = state.sessions.lock().unwrap();
+        let dispatches = sessions.entry(session_id.to_string()).or_default();
+        let event = GatewayEvent {
+            op: GatewayOpCode::Dispatch as usize,
+            d: Some(data),
+            s: Some(dispatches.len() as u32 + 1),
+            t: Some(event.to_string())
+        };
+
+        dispatches.push(event.clone());
+        event
+    };
+
+    send(socket, &event).await
+}
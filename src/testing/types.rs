@@ -0,0 +1,80 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::client::GatewayEvent;
+
+/// A local websocket server which speaks the gateway protocol, so a
+/// [crate::client::Client] can log in end to end without any network
+///
+/// Every connection is sent a Hello, and must answer with a valid Identify or
+/// Resume. An Identify is answered with a READY for a new session. A Resume of
+/// a known session is sent the dispatches after its sequence number, then
+/// RESUMED, and is closed with 4007 if it has no sequence or one never sent. Heartbeats are acknowledged automatically.
+/// Each connection which completes this handshake is handed out as a
+/// [MockConnection] through [MockGateway::next_connection]
+///
+/// Only JSON payloads without transport compression are supported
+pub struct MockGateway {
+    pub(crate) url: String,
+    pub(crate) connections: mpsc::UnboundedReceiver<MockConnection>,
+    pub(crate) task: JoinHandle<()>
+}
+
+/// Used to configure a [MockGateway] before it starts
+#[derive(Debug, Clone)]
+pub struct MockGatewayBuilder {
+    /// The only token accepted when identifying or resuming. Any token is accepted if `None`
+    pub token: Option<String>,
+    /// The heartbeat interval sent in the Hello of every connection
+    pub heartbeat_interval: u64
+}
+
+/// State shared by every connection of a [MockGateway]
+pub(crate) struct MockState {
+    pub url: String,
+    pub token: Option<String>,
+    pub heartbeat_interval: u64,
+    /// Every dispatch sent in each session, by session id. The last sequence
+    /// number of a session is how many were sent, and a resume replays the missed ones
+    pub sessions: Mutex<HashMap<String, Vec<GatewayEvent>>>,
+    pub next_session: AtomicU64
+}
+
+/// A client connected to a [MockGateway] which has completed its handshake
+///
+/// Dropping this stops nothing, the connection keeps acknowledging heartbeats
+/// until the client disconnects
+pub struct MockConnection {
+    /// The payload the client sent to start the connection
+    pub handshake: Handshake,
+    /// The id of the session this connection belongs to
+    pub session_id: String,
+    pub(crate) control: mpsc::UnboundedSender<Control>,
+    pub(crate) commands: mpsc::UnboundedReceiver<GatewayEvent>,
+    pub(crate) closed: Option<oneshot::Receiver<Option<u16>>>,
+    pub(crate) heartbeats: Arc<AtomicU32>,
+    pub(crate) acknowledge: Arc<AtomicBool>
+}
+
+/// How a client started a connection to a [MockGateway]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Handshake {
+    /// The data of the Identify payload
+    Identify(Value),
+    /// The data of the Resume payload
+    Resume(Value)
+}
+
+/// Sent from a [MockConnection] to the task serving its socket
+pub(crate) enum Control {
+    /// A dispatch, which is given the next sequence number of the session
+    Dispatch(String, Value),
+    /// Any other payload, sent as is
+    Send(GatewayEvent),
+    Close(u16)
+}
@@ -1,6 +1,12 @@
 use colored::*;
 use chrono::Local;
 
+mod writer;
+pub(crate) use writer::{BackgroundWriter, Sink};
+
+/// The base URL of Discord's REST API
+pub const API_URL: &str = "https://discord.com/api";
+
 pub fn log_message(kind: &str, message: &str) {
     let current_time = Local::now().format("%Y-%m-%d %H:%M:%S");
     let colored_message = match kind {
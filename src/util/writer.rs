@@ -0,0 +1,99 @@
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Sender};
+use std::thread::JoinHandle;
+use tokio::sync::oneshot;
+
+/// Where a [BackgroundWriter] writes its items, from the writer's thread
+pub(crate) trait Sink: Send + 'static {
+    type Item: Send + 'static;
+
+    fn write(&mut self, item: Self::Item);
+
+    /// Called whenever the thread catches up with the queue, and before
+    /// answering [BackgroundWriter::flush]
+    fn flush(&mut self) {}
+}
+
+enum Command<T> {
+    Write(T),
+    /// Answered once every item sent before it has been written and flushed
+    Flush(oneshot::Sender<()>)
+}
+
+/// Writes items to a [Sink] from a dedicated thread, so async code never
+/// waits on its I/O. Items are written in the order they were queued
+///
+/// Dropping the writer closes the queue, then waits for the thread to write
+/// what is left. That wait blocks, so async code should await
+/// [BackgroundWriter::flush] before the last reference goes away
+pub(crate) struct BackgroundWriter<T> {
+    sender: Mutex<Option<Sender<Command<T>>>>,
+    thread: Mutex<Option<JoinHandle<()>>>
+}
+
+impl<T: Send + 'static> BackgroundWriter<T> {
+    /// Starts a thread with the given name, writing to the sink
+    pub fn spawn<S: Sink<Item = T>>(name: &str, mut sink: S) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                while let Ok(command) = receiver.recv() {
+                    // Everything already queued is written before flushing once,
+                    // rather than flushing after every single item
+                    let mut next = Some(command);
+                    while let Some(command) = next.take().or_else(|| receiver.try_recv().ok()) {
+                        match command {
+                            Command::Write(item) => sink.write(item),
+                            Command::Flush(done) => {
+                                sink.flush();
+                                let _ = done.send(());
+                            },
+                        }
+                    }
+
+                    sink.flush();
+                }
+            })
+            .unwrap_or_else(|_| panic!("Failed to spawn the {name} thread"));
+
+        Self {
+            sender: Mutex::new(Some(sender)),
+            thread: Mutex::new(Some(thread))
+        }
+    }
+
+    /// A writer without a thread, which drops every item
+    pub fn disabled() -> Self {
+        Self { sender: Mutex::new(None), thread: Mutex::new(None) }
+    }
+
+    /// Queues an item to be written
+    pub fn write(&self, item: T) {
+        self.send(Command::Write(item));
+    }
+
+    /// Waits until every item queued so far has been written and flushed
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.send(Command::Flush(done)) {
+            let _ = wait.await;
+        }
+    }
+
+    fn send(&self, command: Command<T>) -> bool {
+        let sender = self.sender.lock().unwrap_or_else(|error| error.into_inner());
+        sender.as_ref().is_some_and(|sender| sender.send(command).is_ok())
+    }
+}
+
+impl<T> Drop for BackgroundWriter<T> {
+    fn drop(&mut self) {
+        // Closing the channel lets the thread write what is left and return
+        self.sender.lock().unwrap_or_else(|error| error.into_inner()).take();
+
+        if let Some(thread) = self.thread.lock().unwrap_or_else(|error| error.into_inner()).take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use reqwest::Client;
 
 use crate::embed::Embed;
+use crate::util::API_URL;
 
 pub mod types;
 pub use types::{
@@ -17,20 +18,57 @@ pub use errors::{
     ExtractionError
 };
 
+impl Default for WebhookClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl WebhookClient {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
             id: None,
             token: None,
-            url: None
+            url: None,
+            api_url: API_URL.to_string()
         }
     }
 
     pub fn with_credentials(&mut self, id: &str, token: &str) -> &mut Self {
         self.id = Some(id.to_string());
         self.token = Some(token.to_string());
-        self.url = Some(format!("https://discord.com/api/webhooks/{}/{}", id, token));
+        self.url = Some(format!("{}/webhooks/{}/{}", self.api_url, id, token));
+        self
+    }
+
+    /// Sets the base URL of the REST API the webhook is sent through. Useful for
+    /// local stand-in servers or proxies
+    ///
+    /// # Arguments
+    ///
+    /// * `api_url` - The base URL, such as "http://127.0.0.1:8080/api"
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use discord_rs::webhook::WebhookClient;
+    ///
+    /// let mut client = WebhookClient::new();
+    /// client
+    ///     .with_api_url("http://127.0.0.1:8080/api")
+    ///     .with_credentials("1234567890", "abcdefghijklmnopqrstuvwxyz");
+    ///
+    /// assert_eq!(client.url.as_deref(), Some("http://127.0.0.1:8080/api/webhooks/1234567890/abcdefghijklmnopqrstuvwxyz"));
+    /// ```
+    pub fn with_api_url(&mut self, api_url: &str) -> &mut Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+
+        // Keep an already configured webhook pointed at the new host
+        if let (Some(id), Some(token)) = (&self.id, &self.token) {
+            self.url = Some(format!("{}/webhooks/{}/{}", self.api_url, id, token));
+        }
+
         self
     }
 
@@ -38,7 +76,8 @@ impl WebhookClient {
     ///
     /// # Arguments
     ///
-    /// * `url` - The webhook URL in the format: "https://discord.com/api/webhooks/{ID}/{TOKEN}"
+    /// * `url` - The webhook URL in the format: "https://discord.com/api/webhooks/{ID}/{TOKEN}",
+    ///   where "https://discord.com/api" is the configured API URL
     ///
     /// # Errors
     ///
@@ -58,8 +97,8 @@ impl WebhookClient {
     /// }
     /// ```
     pub fn with_url(&mut self, url: &str) -> Result<&mut Self, Box<dyn std::error::Error>> {
-        let prefix = "https://discord.com/api/webhooks/";
-        if let Some(rest) = url.strip_prefix(prefix) {
+        let prefix = format!("{}/webhooks/", self.api_url);
+        if let Some(rest) = url.strip_prefix(&prefix) {
             if let Some(index) = rest.find('/') {
                 let id = &rest[..index];
                 let token = &rest[index + 1..];
@@ -81,17 +120,24 @@ impl WebhookClient {
     /// `payload` - A reference to a payload object
     /// 
     /// # Example
-    /// ```
+    /// ```no_run
     /// use discord_rs::webhook::{WebhookClient, MessagePayload};
     /// 
-    /// let webhook = WebhookClient::new("YOUR_ID", "YOUR_TOKEN");
-    /// let message_payload = MessagePayload::new()
-    ///     .set_username("Captain Hook")
-    ///     .set_content("Hello World!");
+    /// # async fn run() {
+    /// let mut webhook = WebhookClient::new();
+    /// webhook.with_credentials("YOUR_ID", "YOUR_TOKEN");
+    ///
+    /// let message_payload = MessagePayload {
+    ///     content: Some("Hello World!".to_string()),
+    ///     embeds: None,
+    ///     username: Some("Captain Hook".to_string()),
+    ///     avatar_url: None,
+    ///     tts: None
+    /// };
     /// 
     /// webhook.send(message_payload).await.expect("Failed to send webhook");
+    /// # }
     /// ```
-
     pub async fn send(&self, payload: MessagePayload) -> Result<(), &'static str> {
         if self.url.is_none() {
             return Err("No URL for webhook. Consider using WebhookClient::with_credentials() or WebhookClient::with_url()");
@@ -126,13 +172,13 @@ impl WebhookClient {
             let mut total_len: u32 = 0;
             for (i, embed) in embeds.iter().enumerate() {
                 total_len += check_field_length(
-                    Some(&embed.title.as_ref().unwrap()),
+                    Some(embed.title.as_ref().unwrap()),
                     256,
                     &format!("Embed title length exceeded for {}nth embed", i)
                 ).unwrap_or_default() as u32;
     
                 total_len += check_field_length(
-                    Some(&embed.description.as_ref().unwrap()),
+                    Some(embed.description.as_ref().unwrap()),
                     4096,
                     &format!("Embed description length exceeded for {}nth embed", i)
                 ).unwrap_or_default() as u32;
@@ -181,7 +227,7 @@ impl WebhookClient {
             return Ok(());
         }
 
-        return Err("An unexpected error occured");
+        Err("An unexpected error occured")
     }
 }
 
@@ -252,11 +298,12 @@ impl MessagePayload {
     /// # Examples
     ///
     /// ```
-    /// use discord-rs::webhook::MessagePayload;
+    /// use discord_rs::embed::Embed;
+    /// use discord_rs::webhook::MessagePayload;
     /// 
-    /// let embeds = vec![embed1, embed2];
+    /// let embeds = vec![Embed::new(), Embed::new()];
     /// 
-    /// let message = MessagePayload::new()
+    /// let message = MessagePayload::new();
     /// message.set_embeds(&embeds).expect("Failed to set embeds");
     /// ```
     pub fn set_embeds(&mut self, embeds: &[Embed]) -> Result<&mut Self, &'static str> {
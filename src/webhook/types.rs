@@ -6,6 +6,8 @@ pub struct WebhookClient {
     pub id: Option<String>,
     pub token: Option<String>,
     pub url: Option<String>,
+    /// The base URL of the REST API. Defaults to https://discord.com/api
+    pub api_url: String,
     pub client: Client
 }
 
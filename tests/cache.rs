@@ -0,0 +1,556 @@
+use discord_rs::cache::{
+    Cache,
+    CacheChange,
+    CacheConfig,
+    CacheEntry,
+    CacheResources,
+    FieldChange,
+    FileBackend,
+    MessageCacheConfig,
+    ResourceUsage
+};
+use discord_rs::client::{Backpressure, ClientBuilder, Event, GatewayIntentBits, ReceiveEvent, Recording};
+use discord_rs::models::{Message, Status};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+
+fn user(id: &str, username: &str) -> Value {
+    json!({ "id": id, "username": username, "discriminator": "0" })
+}
+
+fn role(id: &str, name: &str, position: i32) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "color": 0,
+        "hoist": false,
+        "position": position,
+        "permissions": "0",
+        "managed": false,
+        "mentionable": false
+    })
+}
+
+fn guild() -> Value {
+    json!({
+        "id": "100",
+        "name": "Guild",
+        "owner_id": "1",
+        "roles": [role("100", "@everyone", 0), role("101", "Moderator", 2), role("102", "Member", 1)],
+        "emojis": [{ "id": "110", "name": "wave" }],
+        "features": [],
+        "joined_at": "2023-06-01T12:00:00.000000+00:00",
+        "member_count": 2,
+        "channels": [
+            { "id": "120", "type": 0, "name": "general" },
+            { "id": "121", "type": 2, "name": "voice" }
+        ],
+        "threads": [],
+        "members": [
+            { "user": user("1", "bot"), "roles": [], "deaf": false, "mute": false },
+            { "user": user("2", "alice"), "roles": ["101", "102"], "deaf": true, "mute": false }
+        ],
+        "voice_states": [{
+            "channel_id": "121",
+            "user_id": "2",
+            "session_id": "voice",
+            "deaf": true,
+            "mute": false,
+            "self_deaf": false,
+            "self_mute": false,
+            "self_video": false,
+            "suppress": false
+        }]
+    })
+}
+
+/// Builds a recording of dispatches as they would be received
+fn recording(dispatches: Vec<(&str, Value)>) -> Recording {
+    let lines: Vec<_> = dispatches
+        .into_iter()
+        .enumerate()
+        .map(|(sequence, (name, data))| json!({
+            "timestamp": "2023-06-01T12:00:00Z",
+            "shard_id": 0,
+            "direction": "inbound",
+            "event": { "op": 0, "s": sequence + 1, "t": name, "d": data }
+        }).to_string())
+        .collect();
+
+    Recording::from_reader(lines.join("\n").as_bytes()).unwrap()
+}
+
+/// Replays dispatches into a cache with the given config, returning every event received
+async fn replay(config: CacheConfig, dispatches: Vec<(&str, Value)>) -> (Cache, Vec<Event>) {
+    let cache = Cache::with_config(config);
+    let events = replay_into(&cache, dispatches).await;
+
+    (cache, events)
+}
+
+/// Replays dispatches into an existing cache, such as one kept by a backend
+async fn replay_into(cache: &Cache, dispatches: Vec<(&str, Value)>) -> Vec<Event> {
+    let mut client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+        .with_cache(cache.clone())
+        .build();
+
+    let events = client.events(64, Backpressure::Block);
+    let (replayed, events) = tokio::join!(client.replay(recording(dispatches)), events.collect::<Vec<_>>());
+    replayed.unwrap();
+
+    // The Hello of the replayed connection is not one of the dispatches
+    events.into_iter().filter(|event| !matches!(event, Event::Hello(_))).collect()
+}
+
+/// The default config, keeping messages as well
+fn with_messages(messages: MessageCacheConfig) -> CacheConfig {
+    CacheConfig {
+        messages: Some(messages),
+        ..Default::default()
+    }
+}
+
+fn ready() -> (&'static str, Value) {
+    ("READY", json!({
+        "v": 10,
+        "user": user("1", "bot"),
+        "guilds": [{ "id": "100", "unavailable": true }],
+        "session_id": "SESSION",
+        "resume_gateway_url": "wss://resume.discord.gg",
+        "application": { "id": "1", "flags": 0 }
+    }))
+}
+
+#[tokio::test]
+async fn caches_guild_create() {
+    let (cache, _) = replay(CacheConfig::default(), vec![ready()]).await;
+    assert_eq!(cache.current_user().unwrap().username, "bot");
+    assert!(cache.is_unavailable("100"));
+    assert!(cache.guild("100").is_none());
+
+    let (cache, _) = replay(CacheConfig::default(), vec![ready(), ("GUILD_CREATE", guild())]).await;
+    assert!(!cache.is_unavailable("100"));
+    assert_eq!(cache.guild_ids(), vec!["100".to_string()]);
+
+    let guild = cache.guild("100").unwrap();
+    let roles: Vec<_> = guild.roles.iter().map(|role| role.name.as_str()).collect();
+    assert_eq!(roles, vec!["@everyone", "Member", "Moderator"]);
+    assert_eq!(guild.emojis.len(), 1);
+    assert!(guild.members.is_none() && guild.channels.is_none());
+
+    let channel = cache.channel("120").unwrap();
+    assert_eq!(channel.guild_id.as_deref(), Some("100"));
+    assert_eq!(cache.guild_channels("100").len(), 2);
+
+    let member = cache.member("100", "2").unwrap();
+    assert_eq!(member.user.unwrap().username, "alice");
+    assert_eq!(member.roles, vec!["101", "102"]);
+    assert_eq!(cache.members("100").len(), 2);
+
+    assert_eq!(cache.user("2").unwrap().username, "alice");
+    assert_eq!(cache.role("100", "101").unwrap().name, "Moderator");
+    assert_eq!(cache.emoji("100", "110").unwrap().name.as_deref(), Some("wave"));
+    assert_eq!(cache.voice_state("100", "2").unwrap().channel_id.as_deref(), Some("121"));
+}
+
+#[tokio::test]
+async fn applies_updates() {
+    let (cache, _) = replay(CacheConfig::default(), vec![
+        ready(),
+        ("GUILD_CREATE", guild()),
+        ("GUILD_MEMBER_UPDATE", json!({ "guild_id": "100", "user": user("2", "alice"), "nick": "Al", "roles": ["101"] })),
+        ("GUILD_ROLE_UPDATE", json!({ "guild_id": "100", "role": role("102", "Regular", 1) })),
+        ("GUILD_ROLE_DELETE", json!({ "guild_id": "100", "role_id": "101" })),
+        ("CHANNEL_UPDATE", json!({ "id": "120", "type": 0, "guild_id": "100", "name": "lobby" })),
+        ("CHANNEL_DELETE", json!({ "id": "121", "type": 2, "guild_id": "100" })),
+        ("VOICE_STATE_UPDATE", json!({
+            "guild_id": "100",
+            "channel_id": null,
+            "user_id": "2",
+            "session_id": "voice",
+            "deaf": false,
+            "mute": false,
+            "self_deaf": false,
+            "self_mute": false,
+            "self_video": false,
+            "suppress": false
+        })),
+        ("GUILD_EMOJIS_UPDATE", json!({ "guild_id": "100", "emojis": [] })),
+        ("GUILD_MEMBER_ADD", json!({ "guild_id": "100", "user": user("3", "bob"), "roles": [], "deaf": false, "mute": false })),
+        ("GUILD_UPDATE", json!({ "id": "100", "name": "Renamed", "owner_id": "1", "roles": [role("100", "@everyone", 0)] })),
+        ("USER_UPDATE", user("1", "renamed-bot")),
+    ]).await;
+
+    let member = cache.member("100", "2").unwrap();
+    assert_eq!(member.nick.as_deref(), Some("Al"));
+    // Deleted roles are removed from members, and fields missing from the update are kept
+    assert!(member.roles.is_empty());
+    assert!(member.deaf);
+
+    assert_eq!(cache.channel("120").unwrap().name.as_deref(), Some("lobby"));
+    assert!(cache.channel("121").is_none());
+    assert_eq!(cache.guild_channels("100").len(), 1);
+    assert!(cache.voice_states("100").is_empty());
+    assert!(cache.emojis("100").is_empty());
+    assert_eq!(cache.member("100", "3").unwrap().user.unwrap().username, "bob");
+
+    let guild = cache.guild("100").unwrap();
+    assert_eq!(guild.name, "Renamed");
+    assert_eq!(guild.roles.len(), 1);
+    // Only sent with GUILD_CREATE, so kept from it
+    assert_eq!(guild.member_count, Some(3));
+    assert!(guild.joined_at.is_some());
+
+    assert_eq!(cache.current_user().unwrap().username, "renamed-bot");
+}
+
+#[tokio::test]
+async fn removes_deleted_guilds() {
+    let (cache, _) = replay(CacheConfig::default(), vec![
+        ready(),
+        ("GUILD_CREATE", guild()),
+        ("GUILD_MEMBER_REMOVE", json!({ "guild_id": "100", "user": user("2", "alice") })),
+    ]).await;
+
+    assert!(cache.member("100", "2").is_none());
+    assert_eq!(cache.guild("100").unwrap().member_count, Some(1));
+
+    let (cache, _) = replay(CacheConfig::default(), vec![
+        ready(),
+        ("GUILD_CREATE", guild()),
+        ("GUILD_DELETE", json!({ "id": "100", "unavailable": true })),
+    ]).await;
+
+    assert!(cache.guild("100").is_none());
+    assert!(cache.channel("120").is_none());
+    assert!(cache.members("100").is_empty());
+    assert!(cache.is_unavailable("100"));
+
+    let (cache, _) = replay(CacheConfig::default(), vec![
+        ready(),
+        ("GUILD_CREATE", guild()),
+        ("GUILD_DELETE", json!({ "id": "100" })),
+    ]).await;
+
+    assert!(!cache.is_unavailable("100"));
+}
+
+fn message(channel_id: &str, id: &str, content: &str) -> Value {
+    json!({
+        "id": id,
+        "channel_id": channel_id,
+        "guild_id": "100",
+        "author": user("2", "alice"),
+        "content": content
+    })
+}
+
+#[tokio::test]
+async fn delivers_cached_messages() {
+    let (cache, events) = replay(with_messages(MessageCacheConfig::default()), vec![
+        ("MESSAGE_CREATE", message("120", "1", "first")),
+        ("MESSAGE_CREATE", message("120", "2", "second")),
+        ("MESSAGE_CREATE", message("120", "3", "third")),
+        ("MESSAGE_UPDATE", message("120", "1", "edited")),
+        ("MESSAGE_DELETE", json!({ "id": "2", "channel_id": "120", "guild_id": "100" })),
+        ("MESSAGE_DELETE", json!({ "id": "404", "channel_id": "120", "guild_id": "100" })),
+        ("MESSAGE_DELETE_BULK", json!({ "ids": ["3", "405"], "channel_id": "120", "guild_id": "100" })),
+    ]).await;
+
+    let Event::MessageUpdate(update) = &events[3] else {
+        panic!("Expected a message update, got {:?}", events[3]);
+    };
+    assert_eq!(update.message.content.as_deref(), Some("edited"));
+    assert_eq!(update.cached.as_ref().unwrap().content.as_deref(), Some("first"));
+
+    assert!(matches!(&events[4], Event::MessageDelete(delete) if delete.cached.as_ref().unwrap().content.as_deref() == Some("second")));
+    assert!(matches!(&events[5], Event::MessageDelete(delete) if delete.cached.is_none()));
+    assert!(matches!(&events[6], Event::MessageDeleteBulk(bulk) if bulk.cached.len() == 1 && bulk.cached[0].id == "3"));
+
+    assert_eq!(cache.message("120", "1").unwrap().content.as_deref(), Some("edited"));
+    assert_eq!(cache.messages("120").len(), 1);
+}
+
+#[tokio::test]
+async fn merges_partial_message_updates() {
+    let (cache, events) = replay(with_messages(MessageCacheConfig::default()), vec![
+        ("MESSAGE_CREATE", message("120", "1", "first")),
+        ("MESSAGE_UPDATE", json!({ "id": "1", "channel_id": "120", "pinned": true })),
+        ("MESSAGE_UPDATE", json!({ "id": "2", "channel_id": "120", "pinned": true })),
+        ("MESSAGE_UPDATE", message("120", "3", "third")),
+    ]).await;
+
+    let Event::MessageUpdate(update) = &events[1] else {
+        panic!("Expected a message update, got {:?}", events[1]);
+    };
+    assert!(update.message.content.is_none());
+    assert_eq!(update.cached.as_ref().unwrap().content.as_deref(), Some("first"));
+
+    // Fields missing from the update are kept from the cached message
+    let cached = cache.message("120", "1").unwrap();
+    assert_eq!(cached.content.as_deref(), Some("first"));
+    assert_eq!(cached.author.unwrap().username, "alice");
+    assert_eq!(cached.pinned, Some(true));
+
+    // Without a cached version a partial message is not mistaken for the message
+    assert!(cache.message("120", "2").is_none());
+    assert_eq!(cache.message("120", "3").unwrap().content.as_deref(), Some("third"));
+}
+
+#[tokio::test]
+async fn evicts_least_recent_messages() {
+    let (cache, _) = replay(with_messages(MessageCacheConfig::new(2)), vec![
+        ("MESSAGE_CREATE", message("120", "1", "first")),
+        ("MESSAGE_CREATE", message("120", "2", "second")),
+        ("MESSAGE_UPDATE", message("120", "1", "edited")),
+        ("MESSAGE_CREATE", message("120", "3", "third")),
+        ("MESSAGE_CREATE", message("121", "4", "elsewhere")),
+    ]).await;
+
+    // The edit made the first message more recent than the second
+    let ids: Vec<_> = cache.messages("120").into_iter().map(|message| message.id).collect();
+    assert_eq!(ids, vec!["1", "3"]);
+    assert!(cache.message("121", "4").is_some());
+
+    let config = MessageCacheConfig { per_channel: 10, max_messages: Some(3), max_bytes: None };
+    let (cache, _) = replay(with_messages(config), vec![
+        ("MESSAGE_CREATE", message("120", "1", "first")),
+        ("MESSAGE_CREATE", message("121", "2", "second")),
+        ("MESSAGE_CREATE", message("122", "3", "third")),
+        ("MESSAGE_CREATE", message("120", "4", "fourth")),
+    ]).await;
+
+    assert!(cache.message("120", "1").is_none());
+    assert_eq!(cache.messages("120").len(), 1);
+    assert!(cache.message("121", "2").is_some());
+
+    // Room for two messages of this size
+    let size = serde_json::to_vec(&serde_json::from_value::<Message>(message("120", "1", "first")).unwrap()).unwrap().len();
+    let config = MessageCacheConfig { per_channel: 10, max_messages: None, max_bytes: Some(size * 5 / 2) };
+    let (cache, _) = replay(with_messages(config), vec![
+        ("MESSAGE_CREATE", message("120", "1", "first")),
+        ("MESSAGE_CREATE", message("120", "2", "second")),
+        ("MESSAGE_CREATE", message("120", "3", "third")),
+    ]).await;
+
+    assert!(cache.message("120", "1").is_none());
+    assert_eq!(cache.messages("120").len(), 2);
+}
+
+#[tokio::test]
+async fn drops_messages_of_deleted_channels() {
+    let (cache, _) = replay(with_messages(MessageCacheConfig::default()), vec![
+        ("GUILD_CREATE", guild()),
+        ("MESSAGE_CREATE", message("120", "1", "first")),
+        ("MESSAGE_CREATE", message("121", "2", "second")),
+        ("CHANNEL_DELETE", json!({ "id": "120", "type": 0, "guild_id": "100" })),
+    ]).await;
+
+    assert!(cache.messages("120").is_empty());
+    assert_eq!(cache.messages("121").len(), 1);
+
+    // Messages are not kept unless enabled
+    let (cache, _) = replay(CacheConfig::default(), vec![("MESSAGE_CREATE", message("120", "1", "first"))]).await;
+    assert!(cache.message("120", "1").is_none());
+}
+
+#[tokio::test]
+async fn reports_update_changes() {
+    let (_, events) = replay(with_messages(MessageCacheConfig::default()), vec![
+        ready(),
+        ("GUILD_CREATE", guild()),
+        ("GUILD_MEMBER_UPDATE", json!({ "guild_id": "100", "user": user("2", "alice"), "nick": "Al", "roles": ["101", "102"] })),
+        ("GUILD_ROLE_UPDATE", json!({ "guild_id": "100", "role": role("102", "Regular", 1) })),
+        ("CHANNEL_UPDATE", json!({ "id": "120", "type": 0, "guild_id": "100", "name": "lobby" })),
+        ("GUILD_UPDATE", json!({ "id": "100", "name": "Renamed", "owner_id": "1", "roles": [], "emojis": [], "features": [] })),
+        ("USER_UPDATE", user("1", "robot")),
+        ("CHANNEL_UPDATE", json!({ "id": "404", "type": 0, "guild_id": "100", "name": "unknown" })),
+    ]).await;
+
+    let fields = |changes: &[FieldChange]| changes.iter().map(|change| change.field.to_owned()).collect::<Vec<_>>();
+
+    let Event::GuildMemberUpdate(update) = &events[2] else {
+        panic!("Expected a member update, got {:?}", events[2]);
+    };
+    assert!(update.cached.as_ref().unwrap().nick.is_none());
+    assert_eq!(update.changes, vec![FieldChange { field: "nick".to_string(), before: Value::Null, after: json!("Al") }]);
+
+    let Event::GuildRoleUpdate(update) = &events[3] else {
+        panic!("Expected a role update, got {:?}", events[3]);
+    };
+    assert_eq!(update.cached.as_ref().unwrap().name, "Member");
+    assert_eq!(fields(&update.changes), vec!["name"]);
+
+    let Event::ChannelUpdate(update) = &events[4] else {
+        panic!("Expected a channel update, got {:?}", events[4]);
+    };
+    assert_eq!(update.cached.as_ref().unwrap().name.as_deref(), Some("general"));
+    assert_eq!(fields(&update.changes), vec!["name"]);
+
+    // Fields only sent in GUILD_CREATE are kept, so only the name and the
+    // removed roles and emojis changed
+    let Event::GuildUpdate(update) = &events[5] else {
+        panic!("Expected a guild update, got {:?}", events[5]);
+    };
+    assert_eq!(update.cached.as_ref().unwrap().name, "Guild");
+    assert_eq!(fields(&update.changes), vec!["emojis", "name", "roles"]);
+
+    let Event::UserUpdate(update) = &events[6] else {
+        panic!("Expected a user update, got {:?}", events[6]);
+    };
+    assert_eq!(update.cached.as_ref().unwrap().username, "bot");
+    assert_eq!(update.changes[0].after, json!("robot"));
+
+    assert!(matches!(&events[7], Event::ChannelUpdate(update) if update.cached.is_none() && update.changes.is_empty()));
+}
+
+#[tokio::test]
+async fn persists_to_file() {
+    let path = std::env::temp_dir().join(format!("discord-rs-cache-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let cache = Cache::with_backend(FileBackend::open(&path).unwrap()).unwrap();
+    replay_into(&cache, vec![
+        ready(),
+        ("GUILD_CREATE", guild()),
+        ("GUILD_MEMBER_UPDATE", json!({ "guild_id": "100", "user": user("2", "alice"), "nick": "Al", "roles": ["101"] })),
+        ("GUILD_ROLE_DELETE", json!({ "guild_id": "100", "role_id": "101" })),
+        ("CHANNEL_DELETE", json!({ "id": "121", "type": 2, "guild_id": "100" })),
+    ]).await;
+    drop(cache);
+
+    // Every change is written as it happens, then compacted once loaded
+    let cache = Cache::with_backend(FileBackend::open(&path).unwrap()).unwrap();
+    let log = std::fs::read_to_string(&path).unwrap();
+    assert!(log.lines().all(|line| serde_json::from_str::<CacheChange>(line).is_ok()));
+
+    assert_eq!(cache.current_user().unwrap().username, "bot");
+    assert_eq!(cache.guild("100").unwrap().roles.len(), 2);
+    assert!(cache.channel("120").is_some() && cache.channel("121").is_none());
+    assert_eq!(cache.guild_channels("100").len(), 1);
+
+    let member = cache.member("100", "2").unwrap();
+    assert_eq!(member.nick.as_deref(), Some("Al"));
+    assert!(member.roles.is_empty() && member.deaf);
+    assert!(!cache.is_unavailable("100"));
+
+    // Guilds the bot left while offline are dropped by the next READY
+    let (name, mut ready) = ready();
+    ready["guilds"] = json!([]);
+    replay_into(&cache, vec![(name, ready)]).await;
+    cache.flush().await;
+
+    assert!(cache.guild("100").is_none() && cache.channel("120").is_none());
+    assert!(FileBackend::open(&path).unwrap().read().unwrap().iter().all(|entry| !matches!(entry, CacheEntry::Guild { .. })));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn compacts_growing_file() {
+    let path = std::env::temp_dir().join(format!("discord-rs-compaction-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut backend = FileBackend::open(&path).unwrap();
+    backend.with_compaction_threshold(10);
+
+    let cache = Cache::with_backend(backend).unwrap();
+    let updates = (0..50).map(|index| ("USER_UPDATE", user("1", &format!("bot{index}"))));
+    replay_into(&cache, std::iter::once(ready()).chain(updates).collect()).await;
+    cache.flush().await;
+
+    // Every update rewrites the same entities, so the log never grows far past them
+    let log = std::fs::read_to_string(&path).unwrap();
+    assert!(log.lines().count() < 20, "The log was not compacted: {} lines", log.lines().count());
+
+    drop(cache);
+    let cache = Cache::with_backend(FileBackend::open(&path).unwrap()).unwrap();
+    assert_eq!(cache.current_user().unwrap().username, "bot49");
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+fn presence(user_id: &str, status: &str) -> Value {
+    json!({ "user": { "id": user_id }, "guild_id": "100", "status": status, "activities": [] })
+}
+
+#[tokio::test]
+async fn follows_cache_config() {
+    let mut guild = guild();
+    guild["presences"] = json!([presence("2", "online")]);
+
+    let (cache, _) = replay(CacheConfig {
+        resources: CacheResources::all() - CacheResources::EMOJIS - CacheResources::PRESENCES,
+        events: Some([
+            ReceiveEvent::Ready,
+            ReceiveEvent::GuildCreate,
+            ReceiveEvent::MessageCreate,
+            ReceiveEvent::VoiceStateUpdate,
+            ReceiveEvent::GuildMemberUpdate
+        ].into()),
+        active_members_only: true,
+        ..Default::default()
+    }, vec![
+        ready(),
+        ("GUILD_CREATE", guild),
+        ("CHANNEL_UPDATE", json!({ "id": "120", "type": 0, "guild_id": "100", "name": "lobby" })),
+        ("MESSAGE_CREATE", json!({
+            "id": "1",
+            "channel_id": "120",
+            "guild_id": "100",
+            "author": user("3", "bob"),
+            "member": { "nick": "Bobby", "roles": [] },
+            "content": "hi"
+        })),
+        ("VOICE_STATE_UPDATE", json!({
+            "guild_id": "100",
+            "channel_id": "121",
+            "user_id": "4",
+            "member": { "user": user("4", "carol"), "roles": [], "deaf": false, "mute": true },
+            "session_id": "voice",
+            "deaf": false,
+            "mute": true,
+            "self_deaf": false,
+            "self_mute": false,
+            "self_video": false,
+            "suppress": false
+        })),
+        ("GUILD_MEMBER_UPDATE", json!({ "guild_id": "100", "user": user("5", "dave"), "roles": [] })),
+    ]).await;
+
+    assert_eq!(cache.channel("120").unwrap().name.as_deref(), Some("general"));
+    assert!(cache.emojis("100").is_empty() && cache.presences("100").is_empty());
+
+    // Only the bot and the members seen active are kept
+    let mut members: Vec<_> = cache.members("100").into_iter().map(|member| member.user.unwrap().username).collect();
+    members.sort();
+    assert_eq!(members, vec!["bob", "bot", "carol"]);
+    assert_eq!(cache.member("100", "3").unwrap().nick.as_deref(), Some("Bobby"));
+    assert!(cache.user("2").is_none() && cache.user("5").is_none());
+
+    let stats = cache.stats();
+    assert_eq!(stats.members.count, 3);
+    assert_eq!((stats.emojis, stats.presences), (ResourceUsage::default(), ResourceUsage::default()));
+}
+
+#[tokio::test]
+async fn caches_presences_and_reports_usage() {
+    let mut guild = guild();
+    guild["presences"] = json!([presence("2", "online")]);
+
+    let (cache, _) = replay(CacheConfig::default(), vec![
+        ready(),
+        ("GUILD_CREATE", guild),
+        ("PRESENCE_UPDATE", presence("1", "idle")),
+        ("PRESENCE_UPDATE", presence("2", "offline")),
+    ]).await;
+
+    assert!(cache.presence("100", "2").is_none());
+    assert_eq!(cache.presence("100", "1").unwrap().status, Status::Idle);
+
+    let stats = cache.stats();
+    assert_eq!((stats.guilds.count, stats.channels.count, stats.roles.count), (1, 2, 3));
+    assert_eq!((stats.members.count, stats.users.count, stats.presences.count), (2, 2, 1));
+    assert!(stats.guilds.bytes > 0 && stats.messages.count == 0);
+    assert_eq!(stats.total().count, 13);
+}
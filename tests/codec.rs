@@ -0,0 +1,229 @@
+use discord_rs::client::{
+    Backpressure,
+    ClientBuilder,
+    EtfCodec,
+    Event,
+    GatewayCodec,
+    GatewayEvent,
+    GatewayIntentBits,
+    JsonCodec
+};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::io::Write;
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+fn events() -> Vec<GatewayEvent> {
+    vec![
+        GatewayEvent { op: 1, d: None, s: None, t: None },
+        GatewayEvent { op: 1, d: Some(json!(4_000_000_000u64)), s: None, t: None },
+        GatewayEvent {
+            op: 2,
+            d: Some(json!({
+                "token": "TOKEN",
+                "intents": 33281,
+                "properties": { "os": "linux", "browser": "discord-rs", "device": "discord_rs" },
+                "shard": [3, 16],
+                "presence": {
+                    "since": null,
+                    "activities": [{ "name": "with ünïcödé 🎲", "type": 0 }],
+                    "status": "online",
+                    "afk": false
+                }
+            })),
+            s: None,
+            t: None
+        },
+        GatewayEvent {
+            op: 0,
+            d: Some(json!({
+                "id": "1100000000000000001",
+                "negative": -70000,
+                "small_negative": -1,
+                "float": 0.25,
+                "empty_list": [],
+                "empty_map": {},
+                "nested": [[1, 2], [true, false, null]]
+            })),
+            s: Some(42),
+            t: Some("MESSAGE_CREATE".to_string())
+        }
+    ]
+}
+
+fn round_trip(codec: &dyn GatewayCodec) {
+    for event in events() {
+        let payload = codec.encode(&event).unwrap();
+        let decoded = codec.decode(&payload).unwrap();
+
+        assert_eq!(serde_json::to_value(decoded).unwrap(), serde_json::to_value(event).unwrap());
+    }
+}
+
+#[test]
+fn json_round_trip() {
+    round_trip(&JsonCodec);
+}
+
+#[test]
+fn etf_round_trip() {
+    round_trip(&EtfCodec);
+}
+
+#[test]
+fn etf_payloads_are_binary() {
+    let payload = EtfCodec.encode(&events().remove(0)).unwrap();
+
+    assert!(EtfCodec.is_binary());
+    assert_eq!(payload[0], 131);
+    assert!(String::from_utf8(payload).is_err());
+}
+
+/// A dispatch the way Discord encodes it, with atom keys and snowflakes as integers
+fn discord_dispatch() -> Vec<u8> {
+    fn atom(name: &str, out: &mut Vec<u8>) {
+        out.push(119);
+        out.push(name.len() as u8);
+        out.extend_from_slice(name.as_bytes());
+    }
+
+    fn binary(value: &str, out: &mut Vec<u8>) {
+        out.push(109);
+        out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        out.extend_from_slice(value.as_bytes());
+    }
+
+    let mut out = vec![131, 116, 0, 0, 0, 4];
+
+    atom("op", &mut out);
+    out.extend_from_slice(&[97, 0]);
+
+    atom("s", &mut out);
+    out.extend_from_slice(&[98, 0, 0, 1, 0]);
+
+    atom("t", &mut out);
+    atom("CHANNEL_PINS_UPDATE", &mut out);
+
+    atom("d", &mut out);
+    out.extend_from_slice(&[116, 0, 0, 0, 3]);
+
+    atom("channel_id", &mut out);
+    out.extend_from_slice(&[110, 8, 0]);
+    out.extend_from_slice(&1100000000000000010u64.to_le_bytes());
+
+    atom("guild_id", &mut out);
+    atom("nil", &mut out);
+
+    atom("last_pin_timestamp", &mut out);
+    binary("2023-06-01T12:00:00.000000+00:00", &mut out);
+
+    out
+}
+
+#[test]
+fn etf_matches_json_dispatch() {
+    let etf = EtfCodec.decode(&discord_dispatch()).unwrap();
+    let json = JsonCodec.decode(br#"{
+        "op": 0,
+        "s": 256,
+        "t": "CHANNEL_PINS_UPDATE",
+        "d": {
+            "channel_id": "1100000000000000010",
+            "guild_id": null,
+            "last_pin_timestamp": "2023-06-01T12:00:00.000000+00:00"
+        }
+    }"#).unwrap();
+
+    assert_eq!(serde_json::to_value(&etf).unwrap(), serde_json::to_value(&json).unwrap());
+
+    let event = Event::from_dispatch(etf.t.as_deref().unwrap(), etf.d.unwrap());
+    assert!(matches!(event, Event::ChannelPinsUpdate(pins) if pins.channel_id == "1100000000000000010"));
+}
+
+#[test]
+fn etf_rejects_truncated_terms() {
+    let payload = discord_dispatch();
+
+    for length in [0, 1, 6, payload.len() / 2, payload.len() - 1] {
+        assert!(EtfCodec.decode(&payload[..length]).is_err());
+    }
+}
+
+/// Wraps an ETF payload into a compressed term declaring `size` inflated bytes
+fn compressed(payload: &[u8], size: u32) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&payload[1..]).unwrap();
+
+    let mut term = vec![131, 80];
+    term.extend_from_slice(&size.to_be_bytes());
+    term.extend(encoder.finish().unwrap());
+    term
+}
+
+#[test]
+fn etf_checks_compressed_size() {
+    let payload = discord_dispatch();
+    let size = payload.len() as u32 - 1;
+
+    let decoded = EtfCodec.decode(&compressed(&payload, size)).unwrap();
+    assert_eq!(serde_json::to_value(decoded).unwrap(), serde_json::to_value(EtfCodec.decode(&payload).unwrap()).unwrap());
+    assert!(EtfCodec.decode(&compressed(&payload, size - 1)).is_err());
+    assert!(EtfCodec.decode(&compressed(&payload, size + 1)).is_err());
+    assert!(EtfCodec.decode(&compressed(&payload, u32::MAX)).is_err());
+}
+
+#[test]
+fn etf_rejects_deeply_nested_terms() {
+    let mut payload = vec![131];
+    for _ in 0..100_000 {
+        payload.extend_from_slice(&[108, 0, 0, 0, 1]);
+    }
+
+    assert!(EtfCodec.decode(&payload).is_err());
+}
+
+#[tokio::test]
+async fn client_speaks_etf() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+
+        let mut request = [0; 256];
+        let read = stream.peek(&mut request).await.unwrap();
+        let request = String::from_utf8_lossy(&request[..read]).to_string();
+
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let hello = GatewayEvent { op: 10, d: Some(json!({ "heartbeat_interval": 45000 })), s: None, t: None };
+        socket.send(Message::Binary(EtfCodec.encode(&hello).unwrap())).await.unwrap();
+
+        let identify = match socket.next().await.unwrap().unwrap() {
+            Message::Binary(payload) => EtfCodec.decode(&payload).unwrap(),
+            message => panic!("Expected a binary identify, got {:?}", message),
+        };
+
+        socket.send(Message::Binary(discord_dispatch())).await.unwrap();
+        (request, identify)
+    });
+
+    let mut client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+        .with_gateway_url(&url)
+        .with_api_url("http://127.0.0.1:9")
+        .with_codec(EtfCodec)
+        .build();
+
+    let mut events = client.events(16, Backpressure::Block);
+    client.login().await.unwrap();
+
+    assert!(matches!(events.next().await, Some(Event::Hello(hello)) if hello.heartbeat_interval == 45000));
+    assert!(matches!(events.next().await, Some(Event::ChannelPinsUpdate(_))));
+
+    let (request, identify) = server.await.unwrap();
+    assert!(request.starts_with("GET /?v=10&encoding=etf "));
+    assert_eq!(identify.op, 2);
+    assert_eq!(identify.d.unwrap()["token"], Value::from("TOKEN"));
+}
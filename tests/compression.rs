@@ -0,0 +1,139 @@
+use discord_rs::client::{
+    Backpressure,
+    ClientBuilder,
+    Compression,
+    GatewayError,
+    GatewayIntentBits,
+    ReceiveEvent,
+    ZlibInflater
+};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+/// A zlib stream as sent by the gateway, with the GUILD_CREATE split across three frames
+const FRAMES: &[u8] = include_bytes!("fixtures/zlib-stream/frames.bin");
+/// The payloads carried by the frames, one per line
+const PAYLOADS: &str = include_str!("fixtures/zlib-stream/payloads.jsonl");
+
+fn frames() -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    let mut rest = FRAMES;
+
+    while !rest.is_empty() {
+        let (length, frame) = rest.split_at(4);
+        let length = u32::from_be_bytes(length.try_into().unwrap()) as usize;
+        frames.push(frame[..length].to_vec());
+        rest = &frame[length..];
+    }
+
+    frames
+}
+
+fn payloads() -> Vec<Value> {
+    PAYLOADS
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn inflates_captured_stream() {
+    let mut inflater = ZlibInflater::new();
+    let inflated: Vec<Value> = frames()
+        .iter()
+        .filter_map(|frame| inflater.push(frame).unwrap())
+        .map(|payload| serde_json::from_slice(&payload).unwrap())
+        .collect();
+
+    assert_eq!(inflated, payloads());
+}
+
+#[test]
+fn waits_for_sync_flush_suffix() {
+    let frames = frames();
+    let mut inflater = ZlibInflater::new();
+
+    // Frames 3 to 5 hold a single GUILD_CREATE
+    for frame in &frames[..3] {
+        assert!(inflater.push(frame).unwrap().is_some());
+    }
+
+    assert_eq!(inflater.push(&frames[3]).unwrap(), None);
+    assert_eq!(inflater.push(&frames[4]).unwrap(), None);
+
+    let guild_create: Value = serde_json::from_slice(&inflater.push(&frames[5]).unwrap().unwrap()).unwrap();
+    assert_eq!(guild_create["t"], "GUILD_CREATE");
+}
+
+#[test]
+fn context_is_shared_across_payloads() {
+    let frames = frames();
+    let mut inflater = ZlibInflater::new();
+
+    // Later frames reference data from earlier ones, so they cannot be inflated on their own
+    assert!(inflater.push(&frames[6]).is_err());
+}
+
+#[test]
+fn rejects_corrupted_stream() {
+    let mut frame = frames().remove(0);
+    frame[4] ^= 0xff;
+
+    assert!(matches!(ZlibInflater::new().push(&frame), Err(GatewayError::Protocol(_))));
+}
+
+#[tokio::test]
+async fn client_inflates_binary_frames() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+
+        // Peek at the request line to see which query the client connected with
+        let mut request = [0; 256];
+        let read = stream.peek(&mut request).await.unwrap();
+        let request = String::from_utf8_lossy(&request[..read]).to_string();
+
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+        let mut frames = frames().into_iter();
+        socket.send(Message::Binary(frames.next().unwrap())).await.unwrap();
+
+        // Wait for the identify before sending the rest of the stream
+        let identify = socket.next().await.unwrap().unwrap();
+        assert!(identify.is_text());
+
+        for frame in frames {
+            socket.send(Message::Binary(frame)).await.unwrap();
+        }
+
+        request
+    });
+
+    let mut client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+        .with_gateway_url(&url)
+        .with_api_url("http://127.0.0.1:9")
+        .with_compression(Compression::ZlibStream)
+        .build();
+
+    let mut events = client.events(16, Backpressure::Block);
+    client.login().await.unwrap();
+
+    let mut kinds = Vec::new();
+    while kinds.len() < 4 {
+        kinds.push(events.next().await.unwrap().kind());
+    }
+
+    assert_eq!(kinds, [
+        ReceiveEvent::Hello,
+        ReceiveEvent::Ready,
+        ReceiveEvent::GuildCreate,
+        ReceiveEvent::MessageCreate
+    ]);
+
+    let request = server.await.unwrap();
+    assert!(request.starts_with("GET /?v=10&encoding=json&compress=zlib-stream "));
+}
@@ -0,0 +1,456 @@
+use discord_rs::client::{
+    Backpressure,
+    Client,
+    ClientBuilder,
+    Event,
+    EventStream,
+    GatewayError,
+    GatewayIntentBits,
+    MemberQuery,
+    ReceiveEvent,
+    ShardManager
+};
+use discord_rs::models::{Activity, Status};
+use discord_rs::testing::{Handshake, MockGateway, MockGatewayBuilder};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::tungstenite::Message;
+
+type RawSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+fn client(gateway: &MockGateway, token: &str) -> (Client, EventStream) {
+    let mut client = ClientBuilder::new(token, &[GatewayIntentBits::Guilds, GatewayIntentBits::GuildMessages])
+        .with_gateway_url(gateway.url())
+        .with_api_url("http://127.0.0.1:9")
+        .build();
+
+    let events = client.events(64, Backpressure::Block);
+    (client, events)
+}
+
+/// Starts every shard of a bot against the mock gateway
+async fn shards(gateway: &MockGateway, total: u32) -> (ShardManager, EventStream<(u32, Event)>) {
+    let client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+        .with_gateway_url(gateway.url())
+        .with_api_url("http://127.0.0.1:9")
+        .build();
+
+    let mut manager = ShardManager::new(client);
+    manager.with_total_shards(total);
+    let events = manager.events(64, Backpressure::Block);
+    manager.start().await.unwrap();
+
+    (manager, events)
+}
+
+/// Skips events until one matches, failing if the stream ends first
+async fn wait_for(events: &mut EventStream, matches: impl Fn(&Event) -> bool) -> Event {
+    loop {
+        let event = events.next().await.expect("The event stream ended");
+
+        if matches(&event) {
+            return event;
+        }
+    }
+}
+
+#[tokio::test]
+async fn identifies_and_receives_dispatches() {
+    let mut gateway = MockGateway::start().await;
+    let (mut client, mut events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    let connection = gateway.next_connection().await.unwrap();
+    let Handshake::Identify(identify) = &connection.handshake else {
+        panic!("Expected an identify, got {:?}", connection.handshake);
+    };
+
+    assert_eq!(identify["token"], Value::from("TOKEN"));
+    assert_eq!(identify["intents"], Value::from(513));
+
+    connection.dispatch("CHANNEL_PINS_UPDATE", json!({ "channel_id": "10", "last_pin_timestamp": null }));
+
+    assert!(matches!(events.next().await, Some(Event::Hello(hello)) if hello.heartbeat_interval == 45000));
+    assert!(matches!(events.next().await, Some(Event::Ready(ready)) if ready.session_id == connection.session_id));
+    assert!(matches!(events.next().await, Some(Event::ChannelPinsUpdate(pins)) if pins.channel_id == "10"));
+
+    let session = client.session.lock().await.clone().unwrap();
+    assert_eq!(session.id, connection.session_id);
+    assert_eq!(session.sequence, Some(2));
+}
+
+#[tokio::test]
+async fn resumes_after_reconnect() {
+    let mut gateway = MockGateway::start().await;
+    let (mut client, mut events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    let mut first = gateway.next_connection().await.unwrap();
+    first.dispatch("CHANNEL_PINS_UPDATE", json!({ "channel_id": "10", "last_pin_timestamp": null }));
+    wait_for(&mut events, |event| matches!(event, Event::ChannelPinsUpdate(_))).await;
+
+    first.reconnect();
+    assert_eq!(first.disconnected().await, Some(4000));
+
+    let second = gateway.next_connection().await.unwrap();
+    assert_eq!(second.handshake, Handshake::Resume(json!({
+        "token": "TOKEN",
+        "session_id": first.session_id,
+        "seq": 2
+    })));
+
+    wait_for(&mut events, |event| matches!(event, Event::Resumed)).await;
+    assert_eq!(client.session.lock().await.clone().unwrap().sequence, Some(3));
+    assert_eq!(client.stats().unwrap().reconnects, 1);
+}
+
+/// Connects without a client, answering the Hello with a payload
+async fn raw_handshake(gateway: &MockGateway, op: u8, data: Value) -> RawSocket {
+    let (mut socket, _) = connect_async(gateway.url()).await.unwrap();
+    socket.next().await.unwrap().unwrap();
+    socket.send(Message::text(json!({ "op": op, "d": data }).to_string())).await.unwrap();
+
+    socket
+}
+
+/// Reads the next payload of a raw connection, or the code it was closed with
+async fn raw_next(socket: &mut RawSocket) -> Result<Value, Option<u16>> {
+    match socket.next().await {
+        Some(Ok(Message::Text(text))) => Ok(serde_json::from_str(&text).unwrap()),
+        Some(Ok(Message::Close(frame))) => Err(frame.map(|frame| u16::from(frame.code))),
+        other => panic!("Expected a payload or a close, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn replays_missed_dispatches_on_resume() {
+    let mut gateway = MockGateway::start().await;
+    let identify = json!({ "token": "TOKEN", "intents": 1, "properties": {} });
+    let mut first = raw_handshake(&gateway, 2, identify).await;
+    assert_eq!(raw_next(&mut first).await.unwrap()["t"], Value::from("READY"));
+
+    let connection = gateway.next_connection().await.unwrap();
+    for channel_id in ["10", "11"] {
+        connection.dispatch("CHANNEL_PINS_UPDATE", json!({ "channel_id": channel_id, "last_pin_timestamp": null }));
+        raw_next(&mut first).await.unwrap();
+    }
+
+    // Only what was sent after the READY is missed
+    let resume = json!({ "token": "TOKEN", "session_id": connection.session_id, "seq": 1 });
+    let mut second = raw_handshake(&gateway, 6, resume).await;
+
+    for (sequence, channel_id) in [(2, "10"), (3, "11")] {
+        let missed = raw_next(&mut second).await.unwrap();
+        assert_eq!(missed["s"], Value::from(sequence));
+        assert_eq!(missed["d"]["channel_id"], Value::from(channel_id));
+    }
+
+    let resumed = raw_next(&mut second).await.unwrap();
+    assert_eq!((&resumed["t"], &resumed["s"]), (&Value::from("RESUMED"), &Value::from(4)));
+}
+
+#[tokio::test]
+async fn rejects_resume_with_invalid_sequence() {
+    let mut gateway = MockGateway::start().await;
+    let identify = json!({ "token": "TOKEN", "intents": 1, "properties": {} });
+    let mut socket = raw_handshake(&gateway, 2, identify).await;
+    raw_next(&mut socket).await.unwrap();
+
+    let session_id = gateway.next_connection().await.unwrap().session_id;
+    for seq in [Value::Null, Value::from(2)] {
+        let resume = json!({ "token": "TOKEN", "session_id": session_id, "seq": seq });
+        let mut socket = raw_handshake(&gateway, 6, resume).await;
+        assert_eq!(raw_next(&mut socket).await, Err(Some(4007)), "Accepted a resume from {seq}");
+    }
+}
+
+#[tokio::test]
+async fn identifies_again_after_invalid_session() {
+    let mut gateway = MockGateway::start().await;
+    let (mut client, mut events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    let mut first = gateway.next_connection().await.unwrap();
+    first.invalid_session(false);
+    assert_eq!(first.disconnected().await, Some(1000));
+
+    let second = gateway.next_connection().await.unwrap();
+    assert!(matches!(second.handshake, Handshake::Identify(_)));
+    assert_ne!(second.session_id, first.session_id);
+
+    wait_for(&mut events, |event| matches!(event, Event::Ready(ready) if ready.session_id == second.session_id)).await;
+    assert_eq!(client.session.lock().await.clone().unwrap().id, second.session_id);
+}
+
+#[tokio::test]
+async fn rejects_invalid_token() {
+    let gateway = MockGatewayBuilder::new()
+        .with_token("TOKEN")
+        .start()
+        .await;
+
+    let (mut client, _events) = client(&gateway, "WRONG_TOKEN");
+    assert_eq!(client.run().await, Err(GatewayError::AuthenticationFailed));
+}
+
+#[tokio::test]
+async fn stops_on_fatal_close_code() {
+    let mut gateway = MockGateway::start().await;
+    let (mut client, _events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    gateway.next_connection().await.unwrap().close(4014);
+    assert_eq!(client.run().await, Err(GatewayError::DisallowedIntents));
+}
+
+#[tokio::test]
+async fn acknowledges_heartbeats() {
+    let mut gateway = MockGatewayBuilder::new()
+        .with_heartbeat_interval(50)
+        .start()
+        .await;
+
+    let (mut client, _events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    let connection = gateway.next_connection().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert!(connection.heartbeats() >= 3);
+    assert!(client.stats().unwrap().latency.is_some());
+    assert_eq!(client.stats().unwrap().reconnects, 0);
+}
+
+#[tokio::test]
+async fn resumes_zombied_connection() {
+    let mut gateway = MockGatewayBuilder::new()
+        .with_heartbeat_interval(50)
+        .start()
+        .await;
+
+    let (mut client, _events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    let mut first = gateway.next_connection().await.unwrap();
+    first.acknowledge_heartbeats(false);
+    assert_eq!(first.disconnected().await, Some(4000));
+
+    let second = gateway.next_connection().await.unwrap();
+    assert!(matches!(second.handshake, Handshake::Resume(_)));
+}
+
+#[tokio::test]
+async fn forwards_commands() {
+    let mut gateway = MockGateway::start().await;
+    let (mut client, _events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    let mut connection = gateway.next_connection().await.unwrap();
+    client.set_presence(Status::Dnd, &[Activity::playing("tests")], false).await.unwrap();
+
+    let command = connection.next_command().await.unwrap();
+    assert_eq!(command.op, 3);
+    assert_eq!(command.d.unwrap()["status"], Value::from("dnd"));
+}
+
+fn member(id: &str) -> Value {
+    json!({ "user": { "id": id, "username": format!("user{id}") }, "roles": [], "deaf": false, "mute": false })
+}
+
+#[tokio::test]
+async fn collects_guild_member_chunks() {
+    let mut gateway = MockGateway::start().await;
+    let (mut client, _events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    let mut connection = gateway.next_connection().await.unwrap();
+    let shard = client.shard().unwrap();
+    let request = tokio::spawn(async move {
+        shard.request_guild_members("100", MemberQuery::UserIds(vec!["1".into(), "2".into(), "3".into()]), 0, false).await
+    });
+
+    let command = connection.next_command().await.unwrap();
+    assert_eq!(command.op, 8);
+    let data = command.d.unwrap();
+    assert_eq!(data["user_ids"], json!(["1", "2", "3"]));
+    let nonce = data["nonce"].as_str().unwrap().to_string();
+
+    // Chunks answering another request are not collected
+    let chunk = |index: u32, members: Value, nonce: &str| json!({
+        "guild_id": "100",
+        "members": members,
+        "chunk_index": index,
+        "chunk_count": 2,
+        "not_found": if index == 1 { json!(["3"]) } else { json!([]) },
+        "nonce": nonce
+    });
+    connection.dispatch("GUILD_MEMBERS_CHUNK", chunk(0, json!([member("9")]), "other"));
+    connection.dispatch("GUILD_MEMBERS_CHUNK", chunk(0, json!([member("1")]), &nonce));
+    connection.dispatch("GUILD_MEMBERS_CHUNK", chunk(1, json!([member("2")]), &nonce));
+
+    let members = request.await.unwrap().unwrap();
+    let ids: Vec<_> = members.members.iter().map(|member| member.user.as_ref().unwrap().id.as_str()).collect();
+    assert_eq!(members.guild_id, "100");
+    assert_eq!(ids, vec!["1", "2"]);
+    assert_eq!(members.not_found, vec!["3"]);
+
+    let ids = (0..101).map(|id| id.to_string()).collect();
+    let error = client.request_guild_members("100", MemberQuery::UserIds(ids), 0, false).await;
+    assert!(matches!(error, Err(GatewayError::InvalidCommand(_))));
+}
+
+#[tokio::test]
+async fn rejects_commands_without_a_connection() {
+    let mut gateway = MockGateway::start().await;
+    let (mut client, _events) = client(&gateway, "TOKEN");
+    assert_eq!(client.set_presence(Status::Online, &[], false).await, Err(GatewayError::ShardNotRunning));
+
+    client.login().await.unwrap();
+    let mut connection = gateway.next_connection().await.unwrap();
+
+    let name = "a".repeat(5000);
+    let error = client.set_presence(Status::Online, &[Activity::playing(&name)], false).await;
+    assert!(matches!(error, Err(GatewayError::InvalidCommand(_))));
+
+    client.shutdown_handle().shutdown();
+    client.run().await.unwrap();
+    connection.disconnected().await;
+
+    assert_eq!(client.set_presence(Status::Online, &[], false).await, Err(GatewayError::ShardNotRunning));
+}
+
+#[tokio::test]
+async fn closes_normally_on_shutdown() {
+    let mut gateway = MockGateway::start().await;
+    let (mut client, mut events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    let mut connection = gateway.next_connection().await.unwrap();
+    client.shutdown_handle().shutdown();
+
+    assert_eq!(client.run().await, Ok(()));
+    assert_eq!(connection.disconnected().await, Some(1000));
+
+    // The stream ends once the client stops
+    while events.next().await.is_some() {}
+}
+
+#[tokio::test]
+async fn sharded_stream_outlives_a_stopped_shard() {
+    let mut gateway = MockGateway::start().await;
+    let (manager, mut events) = shards(&gateway, 2).await;
+
+    let mut connections = [gateway.next_connection().await.unwrap(), gateway.next_connection().await.unwrap()];
+    connections.sort_by_key(|connection| match &connection.handshake {
+        Handshake::Identify(identify) => identify["shard"][0].as_u64(),
+        _ => None,
+    });
+
+    // Shard 0 stops for good, while shard 1 keeps running
+    connections[0].close(4004);
+
+    // Commands fail once the gateway of the shard has been dropped
+    let stopped = manager.shard(0).unwrap();
+    while stopped.set_presence(Status::Online, &[], false).await.is_ok() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    connections[1].dispatch("CHANNEL_PINS_UPDATE", json!({ "channel_id": "10", "last_pin_timestamp": null }));
+
+    loop {
+        let event = events.next().await.expect("The event stream ended with a shard still running");
+
+        if let (1, Event::ChannelPinsUpdate(pins)) = event {
+            break assert_eq!(pins.channel_id, "10");
+        }
+    }
+}
+
+#[tokio::test]
+async fn restarts_a_shard_through_its_shutdown() {
+    let mut gateway = MockGateway::start().await;
+    let (mut manager, mut events) = shards(&gateway, 1).await;
+
+    let mut first = gateway.next_connection().await.unwrap();
+    manager.restart(0).await.unwrap();
+
+    // The previous connection is closed properly instead of being dropped
+    assert_eq!(first.disconnected().await, Some(1000));
+
+    let second = gateway.next_connection().await.unwrap();
+    assert!(matches!(second.handshake, Handshake::Identify(_)));
+    second.dispatch("CHANNEL_PINS_UPDATE", json!({ "channel_id": "10", "last_pin_timestamp": null }));
+
+    loop {
+        let event = events.next().await.expect("The event stream ended after a restart");
+
+        if let (0, Event::ChannelPinsUpdate(pins)) = event {
+            break assert_eq!(pins.channel_id, "10");
+        }
+    }
+}
+
+#[tokio::test]
+async fn counts_received_events() {
+    let mut gateway = MockGateway::start().await;
+    let (mut client, mut events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    let connection = gateway.next_connection().await.unwrap();
+    connection.dispatch("CHANNEL_PINS_UPDATE", json!({ "channel_id": "10", "last_pin_timestamp": null }));
+    connection.dispatch("CHANNEL_PINS_UPDATE", json!({ "channel_id": "11", "last_pin_timestamp": null }));
+    wait_for(&mut events, |event| matches!(event, Event::ChannelPinsUpdate(pins) if pins.channel_id == "11")).await;
+
+    let stats = client.stats().unwrap();
+    assert_eq!(stats.events[&ReceiveEvent::Hello], 1);
+    assert_eq!(stats.events[&ReceiveEvent::Ready], 1);
+    assert_eq!(stats.events[&ReceiveEvent::ChannelPinsUpdate], 2);
+    assert_eq!(stats.last_sequence, Some(3));
+}
+
+#[tokio::test]
+async fn keeps_stats_per_shard() {
+    let mut gateway = MockGateway::start().await;
+    let (manager, mut events) = shards(&gateway, 2).await;
+
+    let mut connections = [gateway.next_connection().await.unwrap(), gateway.next_connection().await.unwrap()];
+    connections.sort_by_key(|connection| match &connection.handshake {
+        Handshake::Identify(identify) => identify["shard"][0].as_u64(),
+        _ => None,
+    });
+
+    connections[1].dispatch("CHANNEL_PINS_UPDATE", json!({ "channel_id": "10", "last_pin_timestamp": null }));
+    while !matches!(events.next().await, Some((1, Event::ChannelPinsUpdate(_)))) {}
+
+    let first = manager.shard(0).unwrap().stats();
+    let second = manager.shard(1).unwrap().stats();
+    assert!(!first.events.contains_key(&ReceiveEvent::ChannelPinsUpdate));
+    assert_eq!(second.events[&ReceiveEvent::ChannelPinsUpdate], 1);
+    assert_eq!((first.last_sequence, second.last_sequence), (Some(1), Some(2)));
+}
+
+#[tokio::test]
+async fn resets_uptime_on_reconnect() {
+    let mut gateway = MockGateway::start().await;
+    let (mut client, mut events) = client(&gateway, "TOKEN");
+    client.login().await.unwrap();
+
+    let first = gateway.next_connection().await.unwrap();
+    wait_for(&mut events, |event| matches!(event, Event::Ready(_))).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(client.stats().unwrap().uptime.unwrap() >= Duration::from_millis(300));
+
+    first.reconnect();
+    let _second = gateway.next_connection().await.unwrap();
+    wait_for(&mut events, |event| matches!(event, Event::Resumed)).await;
+
+    // The uptime starts over with the new connection, while the counts carry over
+    let stats = client.stats().unwrap();
+    assert!(stats.uptime.unwrap() < Duration::from_millis(300));
+    assert_eq!(stats.reconnects, 1);
+    assert_eq!(stats.events[&ReceiveEvent::Hello], 2);
+    assert_eq!(stats.events[&ReceiveEvent::Ready], 1);
+}
@@ -0,0 +1,185 @@
+use discord_rs::client::{
+    async_trait,
+    Backpressure,
+    ClientBuilder,
+    Direction,
+    Event,
+    EventHandler,
+    GatewayIntentBits,
+    Recorder,
+    Recording,
+    RecordingTransport,
+    ShardManager
+};
+use discord_rs::client::events::TypingStart;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/recordings/session.jsonl");
+
+#[tokio::test]
+async fn replays_recorded_session() {
+    let recording = Recording::open(FIXTURE).unwrap();
+    let mut client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds]).build();
+    let mut events = client.events(16, Backpressure::Block);
+
+    client.replay(recording).await.unwrap();
+
+    assert!(matches!(events.next().await, Some(Event::Hello(hello)) if hello.heartbeat_interval == 41250));
+    assert!(matches!(events.next().await, Some(Event::Ready(ready)) if ready.session_id == "4c1a9b2e7d3f"));
+    assert!(matches!(events.next().await, Some(Event::ChannelPinsUpdate(_))));
+    assert!(matches!(events.next().await, Some(Event::TypingStart(typing)) if typing.timestamp == 1685620825));
+    assert!(events.next().await.is_none());
+
+    let session = client.session.lock().await.clone().unwrap();
+    assert_eq!(session.sequence, Some(3));
+    assert_eq!(client.stats().unwrap().last_sequence, Some(3));
+}
+
+#[tokio::test]
+async fn replays_every_shard_through_a_manager() {
+    let fixture = std::fs::read_to_string(FIXTURE).unwrap();
+    let ready = fixture.lines().find(|line| line.contains("READY")).unwrap();
+    let typing = fixture.lines().last().unwrap();
+
+    // The second shard is asked to reconnect, and resumes on a new connection
+    let second = [
+        fixture.lines().next().unwrap().to_string(),
+        ready.replace("4c1a9b2e7d3f", "5d2b0c3f8e4a"),
+        json!({ "timestamp": "2023-06-01T12:00:01Z", "shard_id": 0, "direction": "inbound", "event": { "op": 7, "d": null, "s": null, "t": null } }).to_string(),
+        fixture.lines().next().unwrap().to_string(),
+        json!({ "timestamp": "2023-06-01T12:00:02Z", "shard_id": 0, "direction": "inbound", "event": { "op": 0, "d": null, "s": 2, "t": "RESUMED" } }).to_string(),
+        typing.to_string()
+    ];
+
+    let lines: Vec<_> = fixture.lines()
+        .map(str::to_string)
+        .chain(second.iter().map(|line| line.replace("\"shard_id\":0", "\"shard_id\":1")))
+        .collect();
+
+    let transport = RecordingTransport::new(Recording::from_reader(lines.join("\n").as_bytes()).unwrap());
+    let client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+        .with_api_url("http://127.0.0.1:9")
+        .with_transport(transport.clone())
+        .build();
+
+    transport.shutdown_when_finished(client.shutdown_handle());
+
+    let mut manager = ShardManager::new(client);
+    manager.with_total_shards(2);
+    let events = manager.events(16, Backpressure::Block);
+
+    let (result, events) = tokio::join!(manager.run(), events.collect::<Vec<_>>());
+    result.unwrap();
+
+    let kinds = |shard_id| events
+        .iter()
+        .filter(|(id, _)| *id == shard_id)
+        .map(|(_, event)| format!("{:?}", event.kind()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(kinds(0), ["Hello", "Ready", "ChannelPinsUpdate", "TypingStart"]);
+    assert_eq!(kinds(1), ["Hello", "Ready", "Reconnect", "Hello", "Resumed", "TypingStart"]);
+}
+
+#[derive(Default)]
+struct Typing(Arc<Mutex<Vec<String>>>);
+
+#[async_trait]
+impl EventHandler for Typing {
+    async fn typing_start(&self, event: TypingStart) {
+        self.0.lock().unwrap().push(event.user_id);
+    }
+}
+
+#[tokio::test]
+async fn replay_reaches_event_handler() {
+    let handler = Typing::default();
+    let typing = Arc::clone(&handler.0);
+
+    let mut client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+        .with_event_handler(handler)
+        .build();
+
+    // Handlers are waited for before replaying returns
+    client.replay(Recording::open(FIXTURE).unwrap()).await.unwrap();
+    assert_eq!(*typing.lock().unwrap(), vec!["1100000000000000400".to_string()]);
+}
+
+#[test]
+fn reports_invalid_lines() {
+    let lines = std::fs::read_to_string(FIXTURE).unwrap();
+    let mut lines: Vec<_> = lines.lines().take(2).collect();
+    lines.insert(1, "");
+    lines.push("{\"op\":0}");
+
+    let error = Recording::from_reader(lines.join("\n").as_bytes()).unwrap_err();
+    assert!(error.to_string().contains("line 4"));
+}
+
+#[tokio::test]
+async fn records_live_session() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+        let recording = Recording::open(FIXTURE).unwrap();
+        for event in recording.inbound().filter(|event| event.op != 11) {
+            socket.send(Message::text(serde_json::to_string(event).unwrap())).await.unwrap();
+
+            // Wait for the identify before dispatching anything
+            if event.op == 10 {
+                socket.next().await;
+            }
+        }
+
+        // Keep the connection open until the client shuts down
+        while let Some(Ok(_)) = socket.next().await {}
+    });
+
+    let path = std::env::temp_dir().join(format!("discord-rs-recording-{}.jsonl", std::process::id()));
+    let mut client = ClientBuilder::new("SECRET_TOKEN", &[GatewayIntentBits::Guilds])
+        .with_gateway_url(&url)
+        .with_api_url("http://127.0.0.1:9")
+        .with_recorder(Recorder::create(&path).unwrap())
+        .build();
+
+    let mut events = client.events(16, Backpressure::Block);
+    let shutdown = client.shutdown_handle();
+    client.login().await.unwrap();
+
+    let mut live = Vec::new();
+    while live.len() < 4 {
+        live.push(events.next().await.unwrap());
+    }
+
+    shutdown.shutdown();
+    client.run().await.unwrap();
+
+    let recording = Recording::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let identify = recording.events
+        .iter()
+        .find(|recorded| recorded.event.op == 2)
+        .unwrap();
+
+    assert_eq!(identify.direction, Direction::Outbound);
+    assert_eq!(identify.event.d.as_ref().unwrap()["token"], Value::from("REDACTED"));
+    assert_eq!(recording.inbound().count(), 4);
+    assert_eq!(recording.events[0].event.d, Some(json!({ "heartbeat_interval": 41250 })));
+
+    // Playing the recording back yields the same events
+    let mut replayed = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds]).build();
+    let events = replayed.events(16, Backpressure::Block);
+    replayed.replay(recording).await.unwrap();
+
+    let replayed: Vec<_> = events.collect().await;
+    assert_eq!(format!("{:?}", replayed), format!("{:?}", live));
+}
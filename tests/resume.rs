@@ -0,0 +1,121 @@
+use discord_rs::client::{Client, GatewayIntentBits, Session};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+type Socket = WebSocketStream<TcpStream>;
+
+/// A local stand-in for the gateway, accepting one connection at a time
+struct Server {
+    listener: TcpListener,
+    url: String
+}
+
+impl Server {
+    async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        Self { listener, url }
+    }
+
+    /// Accepts the next connection and greets it with Hello
+    async fn accept(&self) -> Socket {
+        let (stream, _) = timeout(self.listener.accept()).await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+        send(&mut socket, json!({ "op": 10, "d": { "heartbeat_interval": 45000 } })).await;
+        socket
+    }
+}
+
+async fn timeout<F: std::future::Future>(future: F) -> F::Output {
+    tokio::time::timeout(Duration::from_secs(10), future).await.expect("Timed out")
+}
+
+async fn send(socket: &mut Socket, payload: Value) {
+    socket.send(Message::text(payload.to_string())).await.unwrap();
+}
+
+/// The next payload sent by the client, skipping heartbeats
+async fn receive(socket: &mut Socket) -> Value {
+    loop {
+        match timeout(socket.next()).await {
+            Some(Ok(Message::Text(text))) => {
+                let payload: Value = serde_json::from_str(&text).unwrap();
+                if payload["op"] != 1 {
+                    return payload;
+                }
+            },
+            Some(Ok(_)) => {},
+            other => panic!("Expected a payload, got {:?}", other),
+        }
+    }
+}
+
+fn client(session_id: &str, resume_gateway_url: &str, sequence: u32) -> Client {
+    let client = Client::new("TOKEN", &[GatewayIntentBits::Guilds]);
+    *client.session.try_lock().unwrap() = Some(Session {
+        id: session_id.to_string(),
+        resume_gateway_url: resume_gateway_url.to_string(),
+        sequence: Some(sequence)
+    });
+
+    client
+}
+
+#[tokio::test]
+async fn resumes_with_the_last_sequence_after_reconnect() {
+    let server = Server::start().await;
+    let mut client = client("SESSION", &server.url, 5);
+
+    let (login, mut socket) = tokio::join!(client.login(), server.accept());
+    login.unwrap();
+
+    let resume = receive(&mut socket).await;
+    assert_eq!(resume["op"], 6);
+    assert_eq!(resume["d"], json!({ "token": "TOKEN", "session_id": "SESSION", "seq": 5 }));
+
+    send(&mut socket, json!({ "op": 0, "s": 6, "t": "MESSAGE_CREATE", "d": {} })).await;
+    send(&mut socket, json!({ "op": 7, "d": null })).await;
+
+    // Discord asked for a reconnect, so the session is resumed on a new connection
+    let mut socket = server.accept().await;
+    let resume = receive(&mut socket).await;
+    assert_eq!(resume["op"], 6);
+    assert_eq!(resume["d"]["seq"], 6);
+}
+
+#[tokio::test]
+async fn resumes_through_the_url_given_by_ready() {
+    let first = Server::start().await;
+    let second = Server::start().await;
+    let mut client = client("OLD", &first.url, 1);
+
+    let (login, mut socket) = tokio::join!(client.login(), first.accept());
+    login.unwrap();
+    assert_eq!(receive(&mut socket).await["op"], 6);
+
+    send(&mut socket, json!({
+        "op": 0,
+        "s": 1,
+        "t": "READY",
+        "d": {
+            "v": 10,
+            "user": { "id": "1", "username": "bot" },
+            "guilds": [],
+            "session_id": "NEW",
+            "resume_gateway_url": second.url,
+            "application": { "id": "1", "flags": 0 }
+        }
+    })).await;
+    send(&mut socket, json!({ "op": 9, "d": true })).await;
+
+    // A resumable invalid session resumes the session READY described
+    let mut socket = second.accept().await;
+    let resume = receive(&mut socket).await;
+    assert_eq!(resume["d"], json!({ "token": "TOKEN", "session_id": "NEW", "seq": 1 }));
+}
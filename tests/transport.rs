@@ -0,0 +1,196 @@
+use async_trait::async_trait;
+use discord_rs::client::{
+    Backpressure,
+    ChannelSocket,
+    ChannelTransport,
+    ClientBuilder,
+    Event,
+    Frame,
+    GatewayError,
+    GatewayIntentBits,
+    GatewaySocket,
+    GatewayTransport
+};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn text(payload: Value) -> Frame {
+    Frame::Text(payload.to_string())
+}
+
+fn json(frame: Option<Frame>) -> Value {
+    match frame {
+        Some(Frame::Text(text)) => serde_json::from_str(&text).unwrap(),
+        frame => panic!("Expected a text frame, got {:?}", frame),
+    }
+}
+
+/// Sends Hello and returns the identify or resume of the client
+async fn handshake(socket: &mut ChannelSocket) -> Value {
+    socket.send(text(json!({ "op": 10, "d": { "heartbeat_interval": 45000 } }))).await.unwrap();
+    json(socket.receive().await)
+}
+
+#[tokio::test]
+async fn runs_over_channels() {
+    let (transport, mut listener) = ChannelTransport::new();
+    let mut client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+        .with_api_url("http://127.0.0.1:9")
+        .with_transport(transport)
+        .build();
+
+    let mut events = client.events(16, Backpressure::Block);
+    let server = tokio::spawn(async move {
+        let (url, mut socket) = listener.accept().await.unwrap();
+        let identify = handshake(&mut socket).await;
+
+        socket.send(text(json!({
+            "op": 0,
+            "s": 1,
+            "t": "CHANNEL_PINS_UPDATE",
+            "d": { "channel_id": "10", "last_pin_timestamp": null }
+        }))).await.unwrap();
+
+        // Ask the client to reconnect, which it does with code 4000 to keep the session
+        socket.send(text(json!({ "op": 7, "d": null }))).await.unwrap();
+        assert_eq!(socket.receive().await, Some(Frame::Close(Some(4000))));
+
+        let (_, mut socket) = listener.accept().await.unwrap();
+        let resume = handshake(&mut socket).await;
+        socket.send(Frame::Close(Some(4004))).await.unwrap();
+
+        (url, identify, resume)
+    });
+
+    client.login().await.unwrap();
+
+    assert!(matches!(events.next().await, Some(Event::Hello(_))));
+    assert!(matches!(events.next().await, Some(Event::ChannelPinsUpdate(pins)) if pins.channel_id == "10"));
+    assert!(matches!(events.next().await, Some(Event::Reconnect)));
+
+    // Without READY there is no session to resume, so the client identifies again
+    assert_eq!(client.run().await, Err(GatewayError::AuthenticationFailed));
+
+    let (url, identify, resume) = server.await.unwrap();
+    assert_eq!(url, "wss://gateway.discord.gg/?v=10&encoding=json");
+    assert_eq!(identify["op"], Value::from(2));
+    assert_eq!(resume["op"], Value::from(2));
+}
+
+/// Counts the frames sent by the client before handing them to another transport
+#[derive(Debug)]
+struct CountingTransport {
+    inner: ChannelTransport,
+    sent: Arc<AtomicUsize>
+}
+
+struct CountingSocket {
+    inner: Box<dyn GatewaySocket>,
+    sent: Arc<AtomicUsize>
+}
+
+#[async_trait]
+impl GatewayTransport for CountingTransport {
+    async fn connect(&self, url: &str) -> Result<Box<dyn GatewaySocket>, GatewayError> {
+        Ok(Box::new(CountingSocket {
+            inner: self.inner.connect(url).await?,
+            sent: Arc::clone(&self.sent)
+        }))
+    }
+}
+
+#[async_trait]
+impl GatewaySocket for CountingSocket {
+    async fn send(&mut self, frame: Frame) -> Result<(), GatewayError> {
+        self.sent.fetch_add(1, Ordering::AcqRel);
+        self.inner.send(frame).await
+    }
+
+    async fn receive(&mut self) -> Option<Frame> {
+        self.inner.receive().await
+    }
+
+    async fn close(&mut self, code: u16) {
+        self.inner.close(code).await
+    }
+}
+
+#[tokio::test]
+async fn wraps_custom_transport() {
+    let (inner, mut listener) = ChannelTransport::new();
+    let sent = Arc::new(AtomicUsize::new(0));
+    let mut client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+        .with_api_url("http://127.0.0.1:9")
+        .with_transport(CountingTransport { inner, sent: Arc::clone(&sent) })
+        .build();
+
+    let server = tokio::spawn(async move {
+        let (_, mut socket) = listener.accept().await.unwrap();
+        handshake(&mut socket).await;
+
+        // Discord may ask for a heartbeat at any time
+        socket.send(text(json!({ "op": 1, "d": null }))).await.unwrap();
+        let heartbeat = json(socket.receive().await);
+
+        socket.send(Frame::Close(Some(4014))).await.unwrap();
+        heartbeat
+    });
+
+    assert_eq!(client.run().await, Err(GatewayError::DisallowedIntents));
+    assert_eq!(server.await.unwrap()["op"], Value::from(1));
+
+    // The identify and the heartbeat
+    assert_eq!(sent.load(Ordering::Acquire), 2);
+}
+
+#[tokio::test]
+async fn lost_connection_is_resumed() {
+    let (transport, mut listener) = ChannelTransport::new();
+    let mut client = ClientBuilder::new("TOKEN", &[GatewayIntentBits::Guilds])
+        .with_api_url("http://127.0.0.1:9")
+        .with_transport(transport)
+        .build();
+
+    let server = tokio::spawn(async move {
+        let (_, mut socket) = listener.accept().await.unwrap();
+        handshake(&mut socket).await;
+        socket.send(text(json!({
+            "op": 0,
+            "s": 1,
+            "t": "READY",
+            "d": {
+                "v": 10,
+                "user": { "id": "1", "username": "bot" },
+                "guilds": [],
+                "session_id": "SESSION",
+                "resume_gateway_url": "wss://resume.discord.gg",
+                "application": { "id": "1", "flags": 0 }
+            }
+        }))).await.unwrap();
+
+        // Dropping the socket loses the connection without a close frame
+        tokio::task::yield_now().await;
+        drop(socket);
+
+        let (url, mut socket) = listener.accept().await.unwrap();
+        let resume = handshake(&mut socket).await;
+        socket.send(Frame::Close(Some(4004))).await.unwrap();
+
+        (url, resume)
+    });
+
+    let mut events = client.events(16, Backpressure::Block);
+    client.login().await.unwrap();
+    assert!(matches!(events.next().await, Some(Event::Hello(_))));
+    assert!(matches!(events.next().await, Some(Event::Ready(_))));
+
+    assert_eq!(client.run().await, Err(GatewayError::AuthenticationFailed));
+
+    let (url, resume) = server.await.unwrap();
+    assert!(url.starts_with("wss://resume.discord.gg/?v=10"));
+    assert_eq!(resume["op"], Value::from(6));
+    assert_eq!(resume["d"]["session_id"], Value::from("SESSION"));
+    assert_eq!(resume["d"]["seq"], Value::from(1));
+}